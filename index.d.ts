@@ -1,5 +1,36 @@
 /* auto-generated by NAPI-RS */
 /* eslint-disable */
+/**
+ * FFT analyser in the style of Web Audio's `AnalyserNode`. Channels are
+ * mixed down to mono; attach it with `attachAnalyser` on a player, recorder
+ * or passthrough.
+ */
+export declare class AudioAnalyser {
+  constructor(options?: AnalyserOptions | undefined | null)
+  /** Change the options; unset fields keep their current value */
+  setOptions(options: AnalyserOptions): void
+  getFftSize(): number
+  /** Number of values returned by `getFrequencyData` (half the FFT size) */
+  getFrequencyBinCount(): number
+  /** Sample rate of the audio analysed last; bin `k` is centred on `k * sampleRate / fftSize` Hz */
+  getSampleRate(): number
+  /** Smoothed magnitude per frequency bin in the configured scale */
+  getFrequencyData(): Float32Array
+  /** Latest `fftSize` samples, mixed down to mono */
+  getTimeDomainData(): Float32Array
+  /**
+   * Group the spectrum into `count` log-spaced bands between `minHz`
+   * (default 20) and `maxHz` (default 20 kHz or Nyquist), each the peak of its bins
+   */
+  getBands(count: number, minHz?: number | undefined | null, maxHz?: number | undefined | null): Float32Array
+  /**
+   * Receive frequency data at most every `intervalMs` (default 50) while audio
+   * flows; with `bands` set, log-spaced band levels are delivered instead
+   */
+  setOnData(callback: ((err: Error | null, arg: Float32Array) => any), intervalMs?: number | undefined | null, bands?: number | undefined | null): void
+  clearOnData(): void
+}
+
 /** Decoder for audio files in various formats (WAV, MP3, FLAC, OGG, etc.) */
 export declare class AudioDecoder {
   /** Create a decoder from a file path */
@@ -14,16 +45,99 @@ export declare class AudioDecoder {
   getDuration(): number
   /** Reset decoder to beginning */
   reset(): void
+  /**
+   * Read up to `frames` frames from the current position, keeping the decoder open
+   * between calls. Returns an empty array once the end is reached.
+   */
+  read(frames: number): Int16Array
+  /** Read the next chunk of frames, or null once the end is reached */
+  nextChunk(): Int16Array | null
+  /**
+   * Move the read position to `seconds`, using the decoder's native seek where the
+   * format supports it
+   */
+  seek(seconds: number): void
+  /** Get the current read position in seconds */
+  getPosition(): number
   /** Decode all audio samples into a vector */
-  decodeToSamples(): Array<number>
-  /** Get a slice of decoded samples (limited by duration to prevent memory issues) */
-  decodeSlice(startSeconds: number, endSeconds: number): Array<number>
+  decodeToSamples(): Int16Array
+  /**
+   * Decode all audio samples without blocking the event loop.
+   * Rejects with an abort error when `signal` fires before decoding completes.
+   */
+  decodeToSamplesAsync(signal?: AbortSignal | undefined | null): Promise<Int16Array>
+  /**
+   * Decode the samples between two positions, seeking to the start instead of
+   * decoding the whole file. Does not move the `read` position.
+   */
+  decodeSlice(startSeconds: number, endSeconds: number): Int16Array
+  /** Get a slice of decoded samples without blocking the event loop */
+  decodeSliceAsync(startSeconds: number, endSeconds: number, signal?: AbortSignal | undefined | null): Promise<Int16Array>
+  /**
+   * Reduce the audio to min/max/RMS peaks per bin and channel for drawing
+   * waveforms, decoding in a single streaming pass. Does not move the `read` position.
+   */
+  computePeaks(options: PeaksOptions): WaveformPeaks
+  /** Compute waveform peaks without blocking the event loop */
+  computePeaksAsync(options: PeaksOptions, signal?: AbortSignal | undefined | null): Promise<WaveformPeaks>
+  /**
+   * Measure EBU R128 loudness and true peak over the whole input in one
+   * streaming pass. Does not move the `read` position.
+   */
+  analyzeLoudness(): LoudnessStats
+  /** Measure loudness without blocking the event loop */
+  analyzeLoudnessAsync(signal?: AbortSignal | undefined | null): Promise<LoudnessStats>
+  /**
+   * Compute an STFT magnitude matrix, optionally mel-scaled, in one streaming
+   * pass over a mono downmix. Does not move the `read` position.
+   */
+  spectrogram(options?: SpectrogramOptions | undefined | null): Spectrogram
+  /** Compute a spectrogram without blocking the event loop */
+  spectrogramAsync(options?: SpectrogramOptions | undefined | null, signal?: AbortSignal | undefined | null): Promise<Spectrogram>
+  /**
+   * Estimate tempo, beat positions and downbeats from onset strength in one
+   * streaming pass. Does not move the `read` position.
+   */
+  detectTempo(options?: TempoOptions | undefined | null): TempoInfo
+  /** Detect tempo without blocking the event loop */
+  detectTempoAsync(options?: TempoOptions | undefined | null, signal?: AbortSignal | undefined | null): Promise<TempoInfo>
+  /** Open and probe a file without blocking the event loop */
+  static fromFileAsync(filePath: string, signal?: AbortSignal | undefined | null): Promise<AudioDecoder>
   /** Check if this is a stereo file */
   isStereo(): boolean
   /** Check if this is a mono file */
   isMono(): boolean
 }
 
+/**
+ * Process-wide audio engine. Players, streams, buffers and the mixer share one
+ * output stream per device instead of opening their own; outputs are opened on
+ * first use, or up front with `init`.
+ */
+export declare class AudioEngine {
+  /**
+   * Open the output of a device (`host:index` id, default device if omitted)
+   * ahead of time and return its configuration
+   */
+  static init(deviceId?: string | undefined | null): StreamOutputConfig
+  /** List the output devices whose ids `init` and the players accept */
+  static getOutputDevices(): OutputDevices
+  /**
+   * Close every device output. Playing voices go silent; new playback
+   * reopens the outputs it needs.
+   */
+  static shutdown(): void
+  /** Check if any device output is open */
+  static isInitialized(): boolean
+  /**
+   * Get the `host:index` ids of the devices with an open output; the default device
+   * is listed under its own id ("default" only when its host does not list it)
+   */
+  static getOpenDevices(): Array<string>
+  /** Get the number of sources currently playing through the engine */
+  static getActiveVoices(): number
+}
+
 /**
  * Real-time audio passthrough (loopback) from input to output
  * Uses a ring buffer to transfer audio data between input and output streams
@@ -31,6 +145,11 @@ export declare class AudioDecoder {
  */
 export declare class AudioPassthrough {
   constructor()
+  /**
+   * Called with errors raised by the input or output stream, e.g. a
+   * `DeviceLost` error when either device disappears
+   */
+  setOnError(callback: (err: Error) => void): void
   /** Set callback for audio level updates (peak, RMS) */
   setOnLevels(callback: ((err: Error | null, arg: AudioLevels) => any)): void
   /**
@@ -48,21 +167,39 @@ export declare class AudioPassthrough {
   isRunning(): boolean
   /** Get current audio levels */
   getLevels(): AudioLevels
+  /** Get EBU R128 loudness of the input since `start` or `resetLoudness` */
+  getLoudness(): LoudnessStats
+  /** Restart loudness measurement, e.g. at the start of a programme */
+  resetLoudness(): void
+  /** Feed the input to `analyser`, replacing any analyser attached before */
+  attachAnalyser(analyser: AudioAnalyser): void
+  detachAnalyser(): void
   /** Get the current sample rate */
   getSampleRate(): number
   /** Get the current channel count */
   getChannels(): number
   /** Get available input devices */
   static getInputDevices(): Array<AudioDeviceInfo>
-  /** Get available output devices */
+  /** Get available output devices, with the ids the audio engine uses */
   static getOutputDevices(): Array<AudioDeviceInfo>
 }
 
 /** Thread-safe audio player with rodio backend */
 export declare class AudioPlayer {
   constructor()
+  /**
+   * Called with errors raised on the audio thread, e.g. a `DeviceLost`
+   * error when the output device disappears
+   */
+  setOnError(callback: (err: Error) => void): void
   getDevices(): Array<AudioDeviceInfo>
   loadFile(filePath: string): void
+  /**
+   * Load a file without blocking the event loop.
+   * Opening and probing happen in the background; the player is only
+   * updated once that succeeds and `signal` has not fired.
+   */
+  loadFileAsync(filePath: string, signal?: AbortSignal | undefined | null): Promise<void>
   loadBuffer(audioData: Array<number>): void
   loadBase64(base64Data: string): void
   play(): void
@@ -70,6 +207,26 @@ export declare class AudioPlayer {
   stop(): void
   setVolume(volume: number): void
   getVolume(): number
+  /**
+   * Set how tracks are normalised. The gain multiplies the `setVolume` level;
+   * sources without ReplayGain/R128 tags play unchanged until `scanReplayGainAsync`
+   * (or `loadFileAsync` with normalisation on) has measured them.
+   */
+  setNormalization(config: NormalizationConfig): void
+  getNormalization(): NormalizationConfig
+  /** Gain tags of the loaded source, or the measured values when it had none */
+  getReplayGain(): ReplayGainInfo | null
+  /**
+   * Measure the loudness of a loaded source without gain tags in the background.
+   * Until a source has tags or a scan, normalisation leaves its level unchanged;
+   * `loadFileAsync` scans by itself when normalisation is on.
+   */
+  scanReplayGainAsync(signal?: AbortSignal | undefined | null): Promise<ReplayGainInfo | null>
+  /** Gain applied by normalisation in dB, on top of the volume */
+  getNormalizationGain(): number
+  /** Feed the decoded audio (before volume and normalisation) to `analyser`, replacing any analyser attached before */
+  attachAnalyser(analyser: AudioAnalyser): void
+  detachAnalyser(): void
   isPlaying(): boolean
   getState(): PlaybackState
   getDuration(): number
@@ -80,16 +237,35 @@ export declare class AudioPlayer {
 
 export declare class AudioRecorder {
   constructor()
-  setOnData(callback: ((err: Error | null, arg: Array<number>) => any)): void
+  setOnData(callback: ((err: Error | null, arg: Int16Array) => any)): void
+  /**
+   * Called with errors raised on the audio thread. Recording stops when
+   * the error is `DeviceLost`.
+   */
+  setOnError(callback: (err: Error) => void): void
   setRingBufferSize(sizeSamples: number): void
   start(deviceId?: string | undefined | null): void
   stop(): void
   isRecording(): boolean
   getBuffer(): SamplesBuffer
-  getRingBufferSamples(): Array<number>
+  getRingBufferSamples(): Int16Array
   clear(): void
   getConfig(): RecorderConfig
   getLevels(): AudioLevels
+  /** Get EBU R128 loudness of the recording since `start` or `resetLoudness` */
+  getLoudness(): LoudnessStats
+  /** Restart loudness measurement, e.g. at the start of a programme */
+  resetLoudness(): void
+  /**
+   * Estimate tempo and beats from the latest 30 s of input; beat times are
+   * relative to `start` or `resetTempo`
+   */
+  getTempo(options?: TempoOptions | undefined | null): TempoInfo
+  /** Restart tempo tracking, e.g. when the music changes */
+  resetTempo(): void
+  /** Feed the recorded input to `analyser`, replacing any analyser attached before */
+  attachAnalyser(analyser: AudioAnalyser): void
+  detachAnalyser(): void
 }
 
 /** A queue for managing multiple audio sources that play in sequence */
@@ -97,16 +273,56 @@ export declare class AudioSourceQueue {
   constructor()
   /** Add an audio source from a file */
   addSource(filePath: string, title?: string | undefined | null): string
-  /** Add an audio source from a buffer */
-  addBuffer(buffer: Array<number>, title?: string | undefined | null): string
-  /** Remove a source by its ID */
+  /** Add an audio source from an `Int16Array` or a `Float32Array` in [-1, 1] */
+  addBuffer(buffer: Int16Array | Float32Array, title?: string | undefined | null): string
+  /** Insert an audio source from a file at the given position */
+  insertAt(index: number, filePath: string, title?: string | undefined | null): string
+  /** Insert an audio source from a buffer at the given position */
+  insertBufferAt(index: number, buffer: Int16Array | Float32Array, title?: string | undefined | null): string
+  /**
+   * Append the entries of an M3U/M3U8, PLS, XSPF or CUE playlist file.
+   * The format is taken from `format`, the file extension or the contents, in that order.
+   * Relative paths are resolved against the playlist's directory. CUE tracks share
+   * one file and carry their range in `startTime`/`endTime`; the queue does not trim it.
+   * Returns the IDs of the added items.
+   */
+  loadPlaylist(path: string, format?: PlaylistFormat | undefined | null): Array<string>
+  /**
+   * Save the file-backed items of the queue as a playlist.
+   * Buffer items have no location and are skipped. Returns the number of entries written.
+   */
+  savePlaylist(path: string, format: PlaylistFormat): number
+  /**
+   * Remove a source by its ID.
+   * If it was the current item, the current index moves to the item that followed it,
+   * or to the new last item when the last one was removed.
+   */
   removeSource(sourceId: string): void
+  /** Move a source to a new position in the queue */
+  moveItem(sourceId: string, newIndex: number): void
+  /** Swap the positions of two sources */
+  swap(firstId: string, secondId: string): void
   /** Get a specific source by its ID */
   getSource(sourceId: string): AudioQueueItem
   getSources(): Array<AudioQueueItem>
   getLength(): number
   getCurrentIndex(): number
   setCurrentIndex(index: number): void
+  /** Set the playback mode. `seed` makes the shuffle order reproducible. */
+  setPlaybackMode(mode: QueuePlaybackMode, seed?: number | undefined | null): void
+  getPlaybackMode(): QueuePlaybackMode
+  /** Get the item at the current index */
+  getCurrent(): AudioQueueItem | null
+  /**
+   * Advance according to the playback mode and return the new current item.
+   * Returns null when the end of the queue is reached with repeat off.
+   */
+  next(): AudioQueueItem | null
+  /**
+   * Step back through the playback history and return the new current item.
+   * Without history, linear modes fall back to the preceding item.
+   */
+  previous(): AudioQueueItem | null
   clear(): void
   isPlaying(): boolean
   setPlaying(playing: boolean): void
@@ -115,17 +331,52 @@ export declare class AudioSourceQueue {
 /** Audio stream for real-time playback */
 export declare class AudioStream {
   constructor()
-  /** Open and initialize the audio stream */
+  /** Open and initialize the audio stream on the shared default output */
   open(): void
+  /** Get the configuration the output was opened with, or null before `open` */
+  getConfig(): StreamOutputConfig | null
   /** Play an audio file */
   playFile(filePath: string): void
   /** Play raw audio data from buffer */
   playBuffer(buffer: SamplesBuffer): void
   /** Play base64 encoded audio data */
   playBase64(base64Data: string): void
-  /** Get the current playback state */
-  getState(): PlayError
-  /** Check if audio is currently playing */
+  /**
+   * Start push mode: PCM chunks passed to `write` are played as they arrive.
+   * Opens the output if needed; a previous push stream is ended first.
+   */
+  openPush(options: PushStreamOptions): void
+  /**
+   * Queue a chunk of interleaved PCM samples in the format given to `openPush`.
+   * Returns false once the high-water mark is reached; wait for the drain
+   * event before writing more.
+   */
+  write(data: Int16Array | Float32Array): boolean
+  /** Stop accepting data; queued audio is played out before the ended event */
+  end(): void
+  /** Get the amount of audio queued in the push buffer in milliseconds */
+  getBufferedMs(): number
+  /** Get the number of underruns of the current push stream */
+  getUnderrunCount(): number
+  /** Called with the buffered milliseconds once the buffer drains below half the high-water mark */
+  setOnDrain(callback: ((err: Error | null, arg: number) => any)): void
+  /** Called with the total underrun count whenever the push buffer runs dry */
+  setOnUnderrun(callback: ((err: Error | null, arg: number) => any)): void
+  /**
+   * Called with the total underrun count once the push stream has ended
+   * and all queued audio was played
+   */
+  setOnEnded(callback: ((err: Error | null, arg: number) => any)): void
+  /** Get the current stream state */
+  getState(): StreamState
+  /** Called with the new state whenever the stream state changes */
+  setOnStateChange(callback: ((err: Error | null, arg: StreamState) => any)): void
+  /**
+   * Called with errors raised on the audio thread, e.g. a `DeviceLost`
+   * error when the output device disappears
+   */
+  setOnError(callback: (err: Error) => void): void
+  /** Check if audio is currently playing (including a draining push stream) */
   isPlaying(): boolean
   /** Pause the stream */
   pause(): void
@@ -137,8 +388,10 @@ export declare class AudioStream {
   setVolume(volume: number): void
   /** Get the current volume */
   getVolume(): number
-  /** Get supported stream configurations */
+  /** Get supported stream configurations of the default output device */
   static getSupportedConfigs(): Array<SupportedStreamConfig>
+  /** Get the supported output ranges of a device (default device if omitted) */
+  static getDeviceConfigs(deviceId?: string | undefined | null): DeviceSupportedConfigs
 }
 
 /** Stream builder for creating audio streams with specific configurations */
@@ -147,13 +400,21 @@ export declare class AudioStreamBuilder {
   setSampleRate(rate: number): void
   setChannels(channels: number): void
   setBufferSize(size: number): void
+  /** Use a specific output device (`host:index` id); the default device is used otherwise */
+  setDevice(deviceId: string): void
+  /** Apply every field set in `config` */
+  setConfig(config: StreamOutputConfig): void
+  /**
+   * Open the output with the requested settings. Fails if the device cannot
+   * provide them instead of silently falling back to another configuration.
+   */
   build(): AudioStream
 }
 
 /** Blue noise generator - high frequency emphasis */
 export declare class BlueNoise {
   constructor(durationMs: number, sampleRate: number, channels: number)
-  getSamples(): Array<number>
+  getSamples(): Int16Array
   getNext(): number | null
   reset(): void
 }
@@ -161,7 +422,7 @@ export declare class BlueNoise {
 /** Brownian noise (random walk) - low frequency emphasis */
 export declare class BrownianNoise {
   constructor(durationMs: number, sampleRate: number, channels: number)
-  getSamples(): Array<number>
+  getSamples(): Int16Array
   getNext(): number | null
   reset(): void
 }
@@ -170,7 +431,7 @@ export declare class BrownianNoise {
 export declare class ChannelCountConverter {
   constructor(sourceChannels: number, targetChannels: number)
   /** Convert audio samples from source channel count to target channel count */
-  convert(samples: Array<number>): Array<number>
+  convert(samples: Int16Array): Int16Array
   sourceChannels(): number
   targetChannels(): number
 }
@@ -182,9 +443,14 @@ export declare class DecoderBuilder {
   setLoopCount(count: number): void
   setSampleRate(rate: number): void
   setChannels(channels: number): void
+  /** Build a decoder whose output is converted to the configured rate and channels */
   buildFromFile(filePath: string): AudioDecoder
+  /** Build a decoder whose output is converted to the configured rate and channels */
   buildFromData(data: Array<number>): AudioDecoder
+  /** Build a looped decoder; plays once unless looping is enabled */
   buildLooped(filePath: string): LoopedDecoder
+  /** Build a looped decoder from raw audio data; plays once unless looping is enabled */
+  buildLoopedFromData(data: Array<number>): LoopedDecoder
 }
 
 /** Decoder builder settings */
@@ -203,8 +469,22 @@ export declare class LoopedDecoder {
   getLoopCount(): number
   /** Set the loop count (use u32::MAX for infinite) */
   setLoopCount(count: number): void
+  /**
+   * Read up to `frames` frames, wrapping back to the start at the end of each pass so
+   * that loop boundaries fall inside a chunk without a gap. Only one pass is decoded
+   * at a time; an empty array means every loop has been played.
+   */
+  read(frames: number): Int16Array
+  /** Read the next chunk of looped frames, or null once every loop has been played */
+  nextChunk(): Int16Array | null
+  /** Get the number of passes completed by `read` */
+  getLoopsPlayed(): number
+  /** Restart streaming from the first loop */
+  reset(): void
   /** Decode with loops applied */
-  decodeLooped(): Array<number>
+  decodeLooped(): Int16Array
+  /** Decode with loops applied without blocking the event loop */
+  decodeLoopedAsync(signal?: AbortSignal | undefined | null): Promise<Int16Array>
   /** Get reference to inner decoder */
   getDecoder(): AudioDecoder
 }
@@ -229,11 +509,42 @@ export declare class Mixer {
    * Mix all sources at a specific time point (synchronous operation)
    * Returns a buffer of mixed samples
    */
-  sampleAt(timeMs: number): Array<number>
-  /** Start mixing multiple sources in real-time (simulated) */
+  sampleAt(timeMs: number): Int16Array
+  /**
+   * Render a block of mixed samples starting at `time_ms`, updating the level meters.
+   * Sources are scaled by their volume and pan and summed; the result is scaled by the
+   * master volume and clamped to full scale.
+   */
+  renderBlock(timeMs: number, frames: number): Int16Array
+  /**
+   * Render a block like `renderBlock` on a worker thread, so long offline
+   * renders do not stall the event loop. Rejects once `signal` aborts.
+   */
+  renderBlockAsync(timeMs: number, frames: number, signal?: AbortSignal | undefined | null): Promise<Int16Array>
+  /** Start mixing all sources in real-time on the default output device */
   startMixing(): void
   /** Stop all mixing */
   stopMixing(): void
+  /** Check if the mixer is currently playing in real-time */
+  isMixing(): boolean
+  /**
+   * Set callback for level updates, fired once per metering window
+   *
+   * # Arguments
+   * * `callback` - Receives the levels of every source, bus and the master output
+   * * `interval_ms` - Metering window length in milliseconds (default: 50)
+   */
+  setOnLevels(callback: ((err: Error | null, arg: MixerLevels) => any), intervalMs?: number | undefined | null): void
+  /** Get the levels of every source, bus and the master output */
+  getLevels(): MixerLevels
+  /** Get the master output levels */
+  getMasterLevels(): MixerMeterLevels
+  /** Get the levels of a bus by name */
+  getBusLevels(bus: string): MixerMeterLevels
+  /** Get the levels of a source by its ID */
+  getSourceLevels(sourceId: string): MixerMeterLevels
+  /** Reset all meters and clear the clip indicators */
+  resetLevels(): void
   /** Get the sample rate of the mixer */
   getSampleRate(): number
   /** Get the channel count of the mixer */
@@ -246,13 +557,14 @@ export declare class Mixer {
 
 /** A source that can be added to a mixer */
 export declare class MixerSource {
-  constructor(id: string, samples: Array<number>, sampleRate: number, channels: number)
+  /** Create a source from an `Int16Array` or a `Float32Array` in [-1, 1] */
+  constructor(id: string, samples: Int16Array | Float32Array, sampleRate: number, channels: number)
   /** Get source ID */
   getId(): string
   /** Get audio samples */
-  getSamples(): Array<number>
+  getSamples(): Int16Array
   /** Get samples at a specific time (simplified to return relative audio) */
-  getSamplesAt(timeMs: number): Array<number>
+  getSamplesAt(timeMs: number): Int16Array
   /** Get sample rate */
   getSampleRate(): number
   /** Get channels */
@@ -269,6 +581,12 @@ export declare class MixerSource {
   setEnabled(enabled: boolean): void
   /** Check if source is enabled */
   isEnabled(): boolean
+  /** Route this source to a named bus for metering (None to remove it from any bus) */
+  setBus(bus?: string | undefined | null): void
+  /** Get the bus this source is routed to */
+  getBus(): string | null
+  /** Get the levels measured for this source during mixing */
+  getLevels(): MixerMeterLevels
   /** Get duration in milliseconds */
   durationMs(): number
 }
@@ -276,24 +594,43 @@ export declare class MixerSource {
 /** Pink noise generator - equal power per octave */
 export declare class PinkNoise {
   constructor(durationMs: number, sampleRate: number, channels: number)
-  getSamples(): Array<number>
+  getSamples(): Int16Array
   getNext(): number | null
   reset(): void
 }
 
+/**
+ * Handle to a sound started with `SamplesBuffer.play` or `testTone`.
+ * Playback continues in the background for as long as the handle is alive.
+ */
+export declare class PlaybackHandle {
+  /** Stop playback; the ended promise resolves */
+  stop(): void
+  pause(): void
+  resume(): void
+  isPaused(): boolean
+  /** Set the volume (0.0 to 1.0) */
+  setVolume(volume: number): void
+  getVolume(): number
+  /** Check if playback has finished or was stopped */
+  isFinished(): boolean
+  /** Resolves when playback has finished or was stopped */
+  ended(): Promise<void>
+}
+
 /** Sample rate converter - handles converting between different sample rates (e.g., 44100 to 48000) */
 export declare class SampleRateConverter {
   constructor(sourceRate: number, targetRate: number)
   /** Convert audio samples from source rate to target rate using linear interpolation */
-  convert(samples: Array<number>): Array<number>
+  convert(samples: Int16Array): Int16Array
   sourceRate(): number
   targetRate(): number
 }
 
 /** A buffer containing audio samples */
 export declare class SamplesBuffer {
-  /** Create a new samples buffer */
-  constructor(channels: number, sampleRate: number, samples: Array<number>)
+  /** Create a new samples buffer from an `Int16Array` or a `Float32Array` in [-1, 1] */
+  constructor(channels: number, sampleRate: number, samples: Int16Array | Float32Array)
   /** Get the number of channels in this buffer (1=mono, 2=stereo) */
   getChannels(): number
   /** Get the sample rate of this buffer */
@@ -302,49 +639,87 @@ export declare class SamplesBuffer {
   getLen(): number
   /** Get the duration of this buffer in seconds */
   getDuration(): number
-  /** Get a copy of the samples in this buffer */
-  getSamples(): Array<number>
-  /** Create a buffer from raw bytes (16-bit little-endian samples) */
-  static fromBytes(bytes: Array<number>, channels: number, sampleRate: number): SamplesBuffer
-  /** Play this buffer with the given sink */
-  play(): void
+  /** Get a copy of the samples in this buffer, handed to JS without a per-sample conversion */
+  getSamples(): Int16Array
+  /** Create a buffer from raw bytes (16-bit little-endian samples) in a `Buffer` or `Uint8Array` */
+  static fromBytes(bytes: Uint8Array, channels: number, sampleRate: number): SamplesBuffer
+  /** Measure EBU R128 loudness and true peak of this buffer */
+  analyzeLoudness(): LoudnessStats
+  /** Start playing this buffer on the default output and return immediately */
+  play(): PlaybackHandle
 }
 
 /** Sample type converter - handles converting between different bit depths (8, 16, 24, 32 bit) */
 export declare class SampleTypeConverter {
   constructor(sourceBits: number, targetBits: number)
   /** Convert between different sample bit depths */
-  convert(samples: Array<number>): Array<number>
+  convert(samples: Int32Array): Int32Array
   sourceBits(): number
   targetBits(): number
 }
 
+/** Queue input interface - for producing sources into a queue */
 export declare class SourcesQueueInput {
+  /** Create an input with its own private queue (see `sources_queue` for a connected pair) */
   constructor()
   pushFile(filePath: string): string
-  pushBuffer(buffer: Array<number>): string
+  pushBuffer(buffer: Int16Array | Float32Array): string
   setTitle(sourceId: string, title: string): void
+  /** Signal that no more items will be pushed; waiting pops return null once drained */
+  close(): void
+  isClosed(): boolean
 }
 
 /** Queue output interface - for consuming sources from a queue */
 export declare class SourcesQueueOutput {
+  /** Create an output with its own private queue (see `sources_queue` for a connected pair) */
   constructor()
   peek(): AudioQueueItem
   pop(): AudioQueueItem
+  /**
+   * Pop the next item, blocking the calling thread until one arrives.
+   * Returns null after `timeout_ms`, or once the input is closed and the queue drained.
+   */
+  popBlocking(timeoutMs?: number | undefined | null): AudioQueueItem | null
+  /**
+   * Pop the next item without blocking the event loop.
+   * Resolves to null after `timeout_ms`, or once the input is closed and the queue drained.
+   */
+  popAsync(timeoutMs?: number | undefined | null): Promise<AudioQueueItem | null>
+  /** Set callback fired whenever an item is pushed into the connected input */
+  setOnItemAdded(callback: ((err: Error | null, arg: AudioQueueItem) => any)): void
   hasNext(): boolean
   getRemaining(): number
 }
 
+/** STFT magnitudes over time; rows run from the lowest frequency up */
+export declare class Spectrogram {
+  /** Number of columns (time steps) */
+  getColumns(): number
+  /** Number of rows (frequency bins or mel bands) */
+  getRows(): number
+  getSampleRate(): number
+  /** Time covered by each column */
+  getSecondsPerColumn(): number
+  /** Centre frequency of each row in Hz */
+  getFrequencies(): Float32Array
+  /** Every magnitude, column by column (`column * rows + row`) */
+  getData(): Float32Array
+  getColumn(column: number): Float32Array
+  /** Render as a PNG image, low frequencies at the bottom */
+  toPng(options?: SpectrogramImageOptions | undefined | null): Buffer
+}
+
 /** Static buffer that owns its audio data */
 export declare class StaticSamplesBuffer {
-  constructor(channels: number, sampleRate: number, samples: Array<number>)
+  constructor(channels: number, sampleRate: number, samples: Int16Array | Float32Array)
   getInner(): SamplesBuffer
 }
 
 /** Velvet noise - sparse, crackly noise */
 export declare class VelvetNoise {
   constructor(durationMs: number, sampleRate: number, channels: number)
-  getSamples(): Array<number>
+  getSamples(): Int16Array
   getNext(): number | null
   reset(): void
 }
@@ -352,15 +727,38 @@ export declare class VelvetNoise {
 /** Violet noise - very high frequency emphasis */
 export declare class VioletNoise {
   constructor(durationMs: number, sampleRate: number, channels: number)
-  getSamples(): Array<number>
+  getSamples(): Int16Array
   getNext(): number | null
   reset(): void
 }
 
+/** Min/max/RMS per bin and channel, with values in [-1, 1] */
+export declare class WaveformPeaks {
+  getSampleRate(): number
+  getChannels(): number
+  /** Frames reduced into each bin (the last bin may hold fewer) */
+  getSamplesPerBin(): number
+  /** Number of bins per channel */
+  getLength(): number
+  /** Smallest sample of each bin on `channel` */
+  getMin(channel: number): Float32Array
+  /** Largest sample of each bin on `channel` */
+  getMax(channel: number): Float32Array
+  /** Root mean square of each bin on `channel` */
+  getRms(channel: number): Float32Array
+  /**
+   * Serialise as audiowaveform JSON, readable by waveform-data.js and peaks.js.
+   * `bits` is 8 or 16 (default).
+   */
+  toJson(bits?: number | undefined | null): string
+  /** Serialise as an audiowaveform binary `.dat` file. `bits` is 8 or 16 (default). */
+  toDat(bits?: number | undefined | null): Buffer
+}
+
 /** White Gaussian noise */
 export declare class WhiteGaussianNoise {
   constructor(durationMs: number, sampleRate: number, channels: number, stdDev?: number | undefined | null)
-  getSamples(): Array<number>
+  getSamples(): Int16Array
   getNext(): number | null
   reset(): void
 }
@@ -368,7 +766,7 @@ export declare class WhiteGaussianNoise {
 /** White Triangular noise */
 export declare class WhiteTriangularNoise {
   constructor(durationMs: number, sampleRate: number, channels: number)
-  getSamples(): Array<number>
+  getSamples(): Int16Array
   getNext(): number | null
   reset(): void
 }
@@ -376,11 +774,21 @@ export declare class WhiteTriangularNoise {
 /** White Uniform noise (standard random noise) */
 export declare class WhiteUniformNoise {
   constructor(durationMs: number, sampleRate: number, channels: number)
-  getSamples(): Array<number>
+  getSamples(): Int16Array
   getNext(): number | null
   reset(): void
 }
 
+export interface AnalyserOptions {
+  /** Samples per transform, a power of two from 32 to 32768 (default 2048) */
+  fftSize?: number
+  /** Window applied before the transform (default Hann) */
+  window?: WindowFunction
+  /** Weight of the previous frame's magnitudes, 0 to 1 (default 0.8) */
+  smoothing?: number
+  scale?: MagnitudeScale
+}
+
 /** Audio device information structure */
 export interface AudioDeviceInfo {
   id: string
@@ -412,11 +820,43 @@ export interface AudioPlayerConfig {
   debug?: boolean
 }
 
+/** Stream information read from an input's headers */
+export interface AudioProbe {
+  /** Container detected from the magic bytes: wav, mp3, flac, ogg, mp4 or adts */
+  container: string
+  /** Codec short name, e.g. "pcm_s16le", "mp3", "flac", "vorbis" or "aac" */
+  codec: string
+  sampleRate: number
+  channels: number
+  /** Bits per sample; unset for lossy codecs that do not declare one */
+  bitDepth?: number
+  /** Bits per second; exact for PCM, otherwise averaged over the input size */
+  bitrate?: number
+  /** Duration in seconds; unset when the headers do not declare a length */
+  duration?: number
+  /** Whether the container supports seeking */
+  seekable: boolean
+  /** Whether packet sizes vary (always for FLAC/Vorbis/AAC, Xing/VBRI-tagged MP3) */
+  isVbr: boolean
+}
+
 export interface AudioQueueItem {
   sourceId: string
   filePath?: string
-  buffer?: Array<number>
+  buffer?: Int16Array
   title?: string
+  /** Duration in seconds, when known */
+  duration?: number
+  /**
+  * Start of the item within `file_path` in seconds (e.g. a CUE track).
+  * Metadata only: play the range with `AudioDecoder.decodeSlice`.
+  */
+  startTime?: number
+  /**
+  * End of the item within `file_path` in seconds (e.g. a CUE track); unset
+  * means the end of the file. Metadata only, like `startTime`.
+  */
+  endTime?: number
 }
 
 /** Parameters for channel count conversion */
@@ -425,6 +865,12 @@ export interface ChannelCountConversion {
   targetChannels: number
 }
 
+export declare const enum ColorMap {
+  Viridis = 'Viridis',
+  Magma = 'Magma',
+  Grayscale = 'Grayscale'
+}
+
 export declare function createAudioPlayer(config?: AudioPlayerConfig | undefined | null): AudioPlayer
 
 /**
@@ -446,20 +892,64 @@ export declare function createAudioPlayer(config?: AudioPlayerConfig | undefined
  */
 export declare function dbToLinear(db: number): number
 
-/** Decoder error types (for audio decoding operations) */
+/** Decoder error types (for audio decoding operations), exposed as the JS error `code` */
 export declare const enum DecoderError {
+  /** The file does not exist */
+  FileNotFound = 'FileNotFound',
+  /** The magic bytes match no known audio container */
   InvalidFormat = 'InvalidFormat',
+  /** The headers or the first packet could not be parsed */
   CorruptedData = 'CorruptedData',
+  /** The container is readable but its audio codec is not compiled in */
   UnsupportedCodec = 'UnsupportedCodec',
-  IoError = 'IoError'
+  /** The file could not be opened or read */
+  IoError = 'IoError',
+  /** A known container this build cannot read (AIFF, Matroska, CAF) */
+  UnsupportedFormat = 'UnsupportedFormat',
+  /** The container holds no audio track */
+  NoAudioTrack = 'NoAudioTrack',
+  /** The input holds no data */
+  EmptyInput = 'EmptyInput'
+}
+
+/** A range of stream configurations supported by a device */
+export interface DeviceConfigRange {
+  channels: ChannelCount
+  minSampleRate: SampleRate
+  maxSampleRate: SampleRate
+  /** cpal sample format name, e.g. "i16" or "f32" */
+  sampleFormat: string
+  /** Smallest buffer in frames; unset when the platform does not report it */
+  minBufferSize?: number
+  /** Largest buffer in frames; unset when the platform does not report it */
+  maxBufferSize?: number
 }
 
+/** Get the supported input ranges of a device (`host:index` id, default device if omitted) */
+export declare function deviceInputConfigs(deviceId?: string | undefined | null): DeviceSupportedConfigs
+
+/** Get the supported output ranges of a device (`host:index` id, default device if omitted) */
+export declare function deviceOutputConfigs(deviceId?: string | undefined | null): DeviceSupportedConfigs
+
 /** Error types for device operations */
 export declare const enum DevicesError {
   NoDevicesFound = 'NoDevicesFound',
   PermissionDenied = 'PermissionDenied',
+  /** The requested device id or name does not exist */
   InvalidDevice = 'InvalidDevice',
-  NotInitialized = 'NotInitialized'
+  NotInitialized = 'NotInitialized',
+  /** The device exists but could not be opened or configured */
+  DeviceUnavailable = 'DeviceUnavailable',
+  /** The device disappeared while a stream was running */
+  DeviceLost = 'DeviceLost'
+}
+
+/** Supported configurations of one device in one direction */
+export interface DeviceSupportedConfigs {
+  device: AudioDeviceInfo
+  /** The configuration the device uses when none is requested */
+  defaultConfig?: DeviceConfigRange
+  configs: Array<DeviceConfigRange>
 }
 
 export declare function getAudioInfo(): string
@@ -472,6 +962,10 @@ export declare function getInputDevices(): Array<AudioDeviceInfo>
 
 export declare function getInputDevicesByHost(hostName: string): Array<AudioDeviceInfo>
 
+/**
+ * Get the file formats whose codecs are compiled into the decoder.
+ * `ogg` means Ogg Vorbis; Ogg Opus files need `opus`.
+ */
 export declare function getSupportedFormats(): Array<string>
 
 export declare function initializeAudio(): string
@@ -500,9 +994,92 @@ export declare function isFormatSupported(format: string): boolean
  */
 export declare function linearToDb(linear: number): number
 
+/**
+ * Loudness statistics: levels in LUFS, range in LU, peaks in dBFS/dBTP.
+ * Levels are `-Infinity` until enough audio above the gates was measured.
+ */
+export interface LoudnessStats {
+  /** Gated loudness of everything measured so far */
+  integrated: number
+  /** Loudness of the latest 400 ms */
+  momentary: number
+  /** Loudness of the latest 3 s */
+  shortTerm: number
+  maxMomentary: number
+  maxShortTerm: number
+  /** Loudness range (LRA) between the 10th and 95th percentile of short-term loudness */
+  loudnessRange: number
+  /** Highest inter-sample peak, in dBTP */
+  truePeak: number
+  /** Highest sample value, in dBFS */
+  samplePeak: number
+}
+
+export declare const enum MagnitudeScale {
+  /** dBFS; a full-scale sine reads 0 dB */
+  Decibels = 'Decibels',
+  /** Amplitude; a full-scale sine reads 1.0 */
+  Linear = 'Linear'
+}
+
 /** Create a new mixer instance */
 export declare function mixer(maxSources?: number | undefined | null): Mixer
 
+/** Snapshot of every meter in a mixer */
+export interface MixerLevels {
+  master: MixerMeterLevels
+  sources: Array<MixerMeterLevels>
+  buses: Array<MixerMeterLevels>
+}
+
+/** Peak, RMS and clip reading for a source, a bus or the master output */
+export interface MixerMeterLevels {
+  id: string
+  peak: number
+  rms: number
+  /** Latched when a sample reached full scale, until `reset_levels` is called */
+  clipped: boolean
+}
+
+export interface NormalizationConfig {
+  mode: NormalizationMode
+  /** Extra gain in dB applied on top of the track or album gain (default 0) */
+  preAmpDb?: number
+  /** Lower the gain so the tagged or measured peak does not clip (default true) */
+  preventClipping?: boolean
+}
+
+/** How `AudioPlayer` adjusts each track's level */
+export declare const enum NormalizationMode {
+  Off = 'Off',
+  /** Play every track at the reference loudness */
+  Track = 'Track',
+  /** Keep level differences within an album; tracks without album gain use track gain */
+  Album = 'Album'
+}
+
+/** Create and open a stream in push mode, ready for `write` calls */
+export declare function openPushStream(options: PushStreamOptions): AudioStream
+
+/** Sample format of the PCM data written to a push stream */
+export declare const enum PcmSampleFormat {
+  /** Signed 16-bit samples, written as `Int16Array` */
+  I16 = 'I16',
+  /** 32-bit float samples in [-1, 1], written as `Float32Array` */
+  F32 = 'F32'
+}
+
+/** How finely `computePeaks` divides the audio; set exactly one field */
+export interface PeaksOptions {
+  /** Bins per second of audio; the bin width is rounded to whole frames */
+  binsPerSecond?: number
+  /**
+  * Number of bins over the whole input. Uses the declared duration when there
+  * is one, otherwise the input is decoded once more to count its frames.
+  */
+  totalBins?: number
+}
+
 /** Create pink noise (with 1/f frequency spectrum) */
 export declare function pink(durationMs: number, sampleRate: number, channels: number): PinkNoise
 
@@ -527,9 +1104,49 @@ export declare const enum PlayError {
   SystemError = 'SystemError'
 }
 
+/** Supported playlist file formats */
+export declare const enum PlaylistFormat {
+  M3u = 'M3u',
+  M3u8 = 'M3u8',
+  Pls = 'Pls',
+  Xspf = 'Xspf',
+  Cue = 'Cue'
+}
+
+/**
+ * Inspect a file path or an in-memory buffer without decoding it for playback.
+ * Throws an error whose `code` is a `DecoderError` when the input cannot be played.
+ */
+export declare function probe(input: string | Buffer): AudioProbe
+
+/** Configuration of a push-mode stream */
+export interface PushStreamOptions {
+  sampleRate: number
+  channels: number
+  format: PcmSampleFormat
+  /** Capacity of the jitter buffer in milliseconds (default 2000) */
+  bufferMs?: number
+  /** `write` returns false once this much audio is queued (default 500) */
+  highWaterMs?: number
+  /** Audio collected before playback starts or resumes after an underrun (default 100) */
+  prebufferMs?: number
+}
+
 /** Creates a new audio source queue */
 export declare function queue(): AudioSourceQueue
 
+/** How the queue picks the next item */
+export declare const enum QueuePlaybackMode {
+  /** Play in order and stop after the last item */
+  RepeatOff = 'RepeatOff',
+  /** Keep returning the current item */
+  RepeatOne = 'RepeatOne',
+  /** Play in order and wrap around to the first item */
+  RepeatAll = 'RepeatAll',
+  /** Play every item once in random order, then reshuffle */
+  Shuffle = 'Shuffle'
+}
+
 export declare function quickPlay(filePath: string, config?: AudioPlayerConfig | undefined | null): AudioPlayer
 
 export interface RecorderConfig {
@@ -538,6 +1155,16 @@ export interface RecorderConfig {
   sampleFormat: string
 }
 
+/** Gains in dB relative to the -18 LUFS ReplayGain reference, peaks as linear amplitude */
+export interface ReplayGainInfo {
+  trackGain?: number
+  trackPeak?: number
+  albumGain?: number
+  albumPeak?: number
+  /** Whether the values were measured because the file had no gain tags */
+  scanned: boolean
+}
+
 /** Parameters for sample rate conversion */
 export interface SampleRateConversion {
   sourceRate: number
@@ -571,6 +1198,49 @@ export declare const enum SourceFunction {
   BrownNoise = 'BrownNoise'
 }
 
+/**
+ * Creates an input and an output connected to the same queue:
+ * whatever is pushed into the input can be popped from the output
+ */
+export declare function sourcesQueue(): SourcesQueuePair
+
+/** A connected input/output pair sharing one queue */
+export interface SourcesQueuePair {
+  input: SourcesQueueInput
+  output: SourcesQueueOutput
+}
+
+export interface SpectrogramImageOptions {
+  colorMap?: ColorMap
+  /** Level drawn with the brightest colour (default 0 dBFS) */
+  maxDb?: number
+  /** Level drawn with the darkest colour (default 80 dB below `maxDb`) */
+  minDb?: number
+  /** Draw frequency and time axes with labels (default true) */
+  axes?: boolean
+  /** Plot size in pixels, excluding axes; defaults to one pixel per column and row */
+  width?: number
+  height?: number
+}
+
+export interface SpectrogramOptions {
+  /** Samples per transform, a power of two from 32 to 32768 (default 2048) */
+  fftSize?: number
+  /** Samples between transforms, at most `fftSize` (default a quarter of it) */
+  hopSize?: number
+  /** Window applied before each transform (default Hann) */
+  window?: WindowFunction
+  /** Group the bins into this many mel bands instead of returning every bin */
+  melBands?: number
+  /** Scale of `getData` and `getColumn` (default Decibels) */
+  scale?: MagnitudeScale
+  /**
+  * Largest size of the magnitude matrix in MB (default 64). Longer inputs
+  * fold several transforms into each column, keeping the loudest value.
+  */
+  memoryBudgetMb?: number
+}
+
 /**
  * Simple audio passthrough with minimal configuration
  *
@@ -586,13 +1256,18 @@ export declare const enum StreamError {
   NotPlaying = 'NotPlaying',
   EndOfFile = 'EndOfFile',
   InvalidData = 'InvalidData',
-  UnsupportedFormat = 'UnsupportedFormat'
+  UnsupportedFormat = 'UnsupportedFormat',
+  /** The stream or queue no longer accepts data */
+  Ended = 'Ended',
+  /** More data was written than the stream can buffer */
+  Overflow = 'Overflow'
 }
 
 /** Stream output configuration */
 export interface StreamOutputConfig {
   sampleRate?: SampleRate
   channels?: ChannelCount
+  /** Buffer size in frames; unset means the device default */
   bufferSize?: number
 }
 
@@ -603,6 +1278,20 @@ export declare const enum StreamPlayError {
   SystemError = 'SystemError'
 }
 
+/** Audio stream state, driven by the output sink */
+export declare const enum StreamState {
+  /** Nothing has been played since the stream was opened or stopped */
+  Idle = 'Idle',
+  Playing = 'Playing',
+  Paused = 'Paused',
+  /** A push stream was ended and its queued audio is being played out */
+  Draining = 'Draining',
+  /** Everything queued has finished playing */
+  Ended = 'Ended',
+  /** The output device failed or could not be opened */
+  Error = 'Error'
+}
+
 /** Get supported output configurations for the audio system */
 export declare function supportedOutputConfigs(): Array<SupportedStreamConfig>
 
@@ -613,7 +1302,35 @@ export interface SupportedStreamConfig {
   sampleWidth: number
 }
 
-export declare function testTone(frequency: number, durationMs: number): void
+/** Estimated tempo and beat grid; times are in seconds from the start of the input */
+export interface TempoInfo {
+  /** 0 when no steady pulse was found */
+  bpm: number
+  /** From 0 (no periodicity) to 1 (a strictly periodic onset pattern) */
+  confidence: number
+  beats: Array<number>
+  /** The beats estimated to start a bar */
+  downbeats: Array<number>
+}
+
+export interface TempoOptions {
+  /** Slowest tempo considered, at least 1 (default 60) */
+  minBpm?: number
+  /** Fastest tempo considered (default 200) */
+  maxBpm?: number
+  /** Beats per bar for downbeat estimation (default 4) */
+  beatsPerBar?: number
+}
+
+/** Play a sine tone on the default output and return immediately */
+export declare function testTone(frequency: number, durationMs: number): PlaybackHandle
 
 /** Create white noise (neutral frequency spectrum) */
 export declare function white(durationMs: number, sampleRate: number, channels: number): WhiteUniformNoise
+
+export declare const enum WindowFunction {
+  Rectangular = 'Rectangular',
+  Hann = 'Hann',
+  Hamming = 'Hamming',
+  Blackman = 'Blackman'
+}
@@ -576,7 +576,9 @@ if (!nativeBinding) {
 }
 
 module.exports = nativeBinding
+module.exports.AudioAnalyser = nativeBinding.AudioAnalyser
 module.exports.AudioDecoder = nativeBinding.AudioDecoder
+module.exports.AudioEngine = nativeBinding.AudioEngine
 module.exports.AudioPassthrough = nativeBinding.AudioPassthrough
 module.exports.AudioPlayer = nativeBinding.AudioPlayer
 module.exports.AudioRecorder = nativeBinding.AudioRecorder
@@ -592,20 +594,26 @@ module.exports.LoopedDecoder = nativeBinding.LoopedDecoder
 module.exports.Mixer = nativeBinding.Mixer
 module.exports.MixerSource = nativeBinding.MixerSource
 module.exports.PinkNoise = nativeBinding.PinkNoise
+module.exports.PlaybackHandle = nativeBinding.PlaybackHandle
 module.exports.SampleRateConverter = nativeBinding.SampleRateConverter
 module.exports.SamplesBuffer = nativeBinding.SamplesBuffer
 module.exports.SampleTypeConverter = nativeBinding.SampleTypeConverter
 module.exports.SourcesQueueInput = nativeBinding.SourcesQueueInput
 module.exports.SourcesQueueOutput = nativeBinding.SourcesQueueOutput
+module.exports.Spectrogram = nativeBinding.Spectrogram
 module.exports.StaticSamplesBuffer = nativeBinding.StaticSamplesBuffer
 module.exports.VelvetNoise = nativeBinding.VelvetNoise
 module.exports.VioletNoise = nativeBinding.VioletNoise
+module.exports.WaveformPeaks = nativeBinding.WaveformPeaks
 module.exports.WhiteGaussianNoise = nativeBinding.WhiteGaussianNoise
 module.exports.WhiteTriangularNoise = nativeBinding.WhiteTriangularNoise
 module.exports.WhiteUniformNoise = nativeBinding.WhiteUniformNoise
+module.exports.ColorMap = nativeBinding.ColorMap
 module.exports.createAudioPlayer = nativeBinding.createAudioPlayer
 module.exports.dbToLinear = nativeBinding.dbToLinear
 module.exports.DecoderError = nativeBinding.DecoderError
+module.exports.deviceInputConfigs = nativeBinding.deviceInputConfigs
+module.exports.deviceOutputConfigs = nativeBinding.deviceOutputConfigs
 module.exports.DevicesError = nativeBinding.DevicesError
 module.exports.getAudioInfo = nativeBinding.getAudioInfo
 module.exports.getAudioMetadata = nativeBinding.getAudioMetadata
@@ -617,19 +625,29 @@ module.exports.initializeAudio = nativeBinding.initializeAudio
 module.exports.isDebugEnabled = nativeBinding.isDebugEnabled
 module.exports.isFormatSupported = nativeBinding.isFormatSupported
 module.exports.linearToDb = nativeBinding.linearToDb
+module.exports.MagnitudeScale = nativeBinding.MagnitudeScale
 module.exports.mixer = nativeBinding.mixer
+module.exports.NormalizationMode = nativeBinding.NormalizationMode
+module.exports.openPushStream = nativeBinding.openPushStream
+module.exports.PcmSampleFormat = nativeBinding.PcmSampleFormat
 module.exports.pink = nativeBinding.pink
 module.exports.play = nativeBinding.play
 module.exports.PlaybackState = nativeBinding.PlaybackState
 module.exports.PlayError = nativeBinding.PlayError
+module.exports.PlaylistFormat = nativeBinding.PlaylistFormat
+module.exports.probe = nativeBinding.probe
 module.exports.queue = nativeBinding.queue
+module.exports.QueuePlaybackMode = nativeBinding.QueuePlaybackMode
 module.exports.quickPlay = nativeBinding.quickPlay
 module.exports.SeekError = nativeBinding.SeekError
 module.exports.setDebug = nativeBinding.setDebug
 module.exports.SourceFunction = nativeBinding.SourceFunction
+module.exports.sourcesQueue = nativeBinding.sourcesQueue
 module.exports.startPassthrough = nativeBinding.startPassthrough
 module.exports.StreamError = nativeBinding.StreamError
 module.exports.StreamPlayError = nativeBinding.StreamPlayError
+module.exports.StreamState = nativeBinding.StreamState
 module.exports.supportedOutputConfigs = nativeBinding.supportedOutputConfigs
 module.exports.testTone = nativeBinding.testTone
 module.exports.white = nativeBinding.white
+module.exports.WindowFunction = nativeBinding.WindowFunction
@@ -0,0 +1,582 @@
+//! Real-time spectrum analyser that taps player output or recorder/passthrough input
+
+use crate::error::{error, ErrorCode, LockExt, Result};
+use napi::bindgen_prelude::{Float32Array, Function, Unknown};
+use napi::threadsafe_function::{
+    ThreadsafeCallContext, ThreadsafeFunction, ThreadsafeFunctionCallMode,
+};
+use napi::Status;
+use napi_derive::napi;
+use rodio::source::SeekError;
+use rodio::Source;
+use std::f64::consts::PI;
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant};
+
+const DEFAULT_FFT_SIZE: u32 = 2048;
+const MIN_FFT_SIZE: u32 = 32;
+const MAX_FFT_SIZE: u32 = 32768;
+const DEFAULT_SMOOTHING: f64 = 0.8;
+const DEFAULT_INTERVAL_MS: u32 = 50;
+const DEFAULT_MIN_BAND_HZ: f64 = 20.0;
+const DEFAULT_MAX_BAND_HZ: f64 = 20000.0;
+/// Samples a tapped source collects before handing them to the analyser
+const TAP_BLOCK_LEN: usize = 512;
+
+/// Analyser attached to a player, recorder or passthrough
+pub(crate) type AnalyserSlot = Arc<Mutex<Option<Arc<AnalyserShared>>>>;
+
+/// Mono history and its sample rate, handed to the JS thread for the transform
+type HistoryFrame = (Vec<f32>, u32);
+type DataCallback = ThreadsafeFunction<HistoryFrame, Unknown<'static>, Float32Array, Status, true>;
+
+#[napi(string_enum)]
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum WindowFunction {
+    Rectangular,
+    #[default]
+    Hann,
+    Hamming,
+    Blackman,
+}
+
+#[napi(string_enum)]
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum MagnitudeScale {
+    /// dBFS; a full-scale sine reads 0 dB
+    #[default]
+    Decibels,
+    /// Amplitude; a full-scale sine reads 1.0
+    Linear,
+}
+
+#[napi(object)]
+#[derive(Debug, Clone, Default)]
+pub struct AnalyserOptions {
+    /// Samples per transform, a power of two from 32 to 32768 (default 2048)
+    pub fft_size: Option<u32>,
+    /// Window applied before the transform (default Hann)
+    pub window: Option<WindowFunction>,
+    /// Weight of the previous frame's magnitudes, 0 to 1 (default 0.8)
+    pub smoothing: Option<f64>,
+    pub scale: Option<MagnitudeScale>,
+}
+
+/// FFT analyser in the style of Web Audio's `AnalyserNode`. Channels are
+/// mixed down to mono; attach it with `attachAnalyser` on a player, recorder
+/// or passthrough.
+#[napi]
+pub struct AudioAnalyser {
+    shared: Arc<AnalyserShared>,
+}
+
+#[napi]
+impl AudioAnalyser {
+    #[napi(constructor)]
+    pub fn new(options: Option<AnalyserOptions>) -> Result<Self> {
+        let spectrum = Spectrum::new(&options.unwrap_or_default())?;
+        Ok(Self {
+            shared: Arc::new(AnalyserShared {
+                history: Mutex::new(History::new(spectrum.fft_size)),
+                spectrum: Mutex::new(spectrum),
+                listener: Mutex::new(None),
+            }),
+        })
+    }
+
+    /// Change the options; unset fields keep their current value
+    #[napi]
+    pub fn set_options(&self, options: AnalyserOptions) -> Result<()> {
+        let mut spectrum = self.shared.spectrum.lock_or_recover();
+        let merged = AnalyserOptions {
+            fft_size: options.fft_size.or(Some(spectrum.fft_size as u32)),
+            window: options.window.or(Some(spectrum.window)),
+            smoothing: options.smoothing.or(Some(spectrum.smoothing)),
+            scale: options.scale.or(Some(spectrum.scale)),
+        };
+        *spectrum = Spectrum::new(&merged)?;
+        let mut history = self.shared.history.lock_or_recover();
+        if history.samples.len() != spectrum.fft_size {
+            let sample_rate = history.sample_rate;
+            *history = History::new(spectrum.fft_size);
+            history.sample_rate = sample_rate;
+        }
+        Ok(())
+    }
+
+    #[napi]
+    pub fn get_fft_size(&self) -> u32 {
+        self.shared.spectrum.lock_or_recover().fft_size as u32
+    }
+
+    /// Number of values returned by `getFrequencyData` (half the FFT size)
+    #[napi]
+    pub fn get_frequency_bin_count(&self) -> u32 {
+        self.get_fft_size() / 2
+    }
+
+    /// Sample rate of the audio analysed last; bin `k` is centred on `k * sampleRate / fftSize` Hz
+    #[napi]
+    pub fn get_sample_rate(&self) -> u32 {
+        self.shared.history.lock_or_recover().sample_rate
+    }
+
+    /// Smoothed magnitude per frequency bin in the configured scale
+    #[napi]
+    pub fn get_frequency_data(&self) -> Float32Array {
+        let (frame, _) = self.shared.latest();
+        Float32Array::new(
+            self.shared
+                .spectrum
+                .lock_or_recover()
+                .frequency_data(&frame),
+        )
+    }
+
+    /// Latest `fftSize` samples, mixed down to mono
+    #[napi]
+    pub fn get_time_domain_data(&self) -> Float32Array {
+        Float32Array::new(self.shared.latest().0)
+    }
+
+    /// Group the spectrum into `count` log-spaced bands between `minHz`
+    /// (default 20) and `maxHz` (default 20 kHz or Nyquist), each the peak of its bins
+    #[napi]
+    pub fn get_bands(
+        &self,
+        count: u32,
+        min_hz: Option<f64>,
+        max_hz: Option<f64>,
+    ) -> Result<Float32Array> {
+        let bands = BandLayout::new(count, min_hz, max_hz)?;
+        let (frame, sample_rate) = self.shared.latest();
+        Ok(Float32Array::new(
+            self.shared
+                .spectrum
+                .lock_or_recover()
+                .bands(&frame, sample_rate, &bands),
+        ))
+    }
+
+    /// Receive frequency data at most every `intervalMs` (default 50) while audio
+    /// flows; with `bands` set, log-spaced band levels are delivered instead
+    #[napi(
+        ts_args_type = "callback: ((err: Error | null, arg: Float32Array) => any), intervalMs?: number | undefined | null, bands?: number | undefined | null"
+    )]
+    pub fn set_on_data(
+        &self,
+        callback: Function<Float32Array, Unknown<'static>>,
+        interval_ms: Option<u32>,
+        bands: Option<u32>,
+    ) -> Result<()> {
+        let bands = bands
+            .map(|count| BandLayout::new(count, None, None))
+            .transpose()?;
+        // Audio threads only copy the history; the transform runs on the JS thread.
+        // A weak reference avoids a cycle through the listener stored in `shared`.
+        let shared = Arc::downgrade(&self.shared);
+        let callback = callback
+            .build_threadsafe_function::<HistoryFrame>()
+            .callee_handled::<true>()
+            .build_callback(move |ctx: ThreadsafeCallContext<HistoryFrame>| {
+                let (frame, sample_rate) = ctx.value;
+                let data = Weak::upgrade(&shared).map_or_else(Vec::new, |shared| {
+                    let mut spectrum = shared.spectrum.lock_or_recover();
+                    match &bands {
+                        Some(bands) => spectrum.bands(&frame, sample_rate, bands),
+                        None => spectrum.frequency_data(&frame),
+                    }
+                });
+                Ok(Float32Array::new(data))
+            })
+            .map_err(|e| error(e.status, &e.reason))?;
+        *self.shared.listener.lock_or_recover() = Some(Listener {
+            callback,
+            interval: Duration::from_millis(interval_ms.unwrap_or(DEFAULT_INTERVAL_MS) as u64),
+            last: None,
+        });
+        Ok(())
+    }
+
+    #[napi]
+    pub fn clear_on_data(&self) {
+        *self.shared.listener.lock_or_recover() = None;
+    }
+}
+
+impl AudioAnalyser {
+    pub(crate) fn shared(&self) -> Arc<AnalyserShared> {
+        self.shared.clone()
+    }
+}
+
+pub(crate) struct AnalyserShared {
+    // Fed from audio threads; held only to copy samples in or out
+    history: Mutex<History>,
+    // Transform state, used on the JS thread
+    spectrum: Mutex<Spectrum>,
+    listener: Mutex<Option<Listener>>,
+}
+
+struct Listener {
+    callback: DataCallback,
+    interval: Duration,
+    last: Option<Instant>,
+}
+
+impl AnalyserShared {
+    /// Feed interleaved samples, handing the history to the listener when it is due
+    pub(crate) fn push<I: IntoIterator<Item = f32>>(
+        &self,
+        samples: I,
+        channels: u16,
+        sample_rate: u32,
+    ) {
+        self.history
+            .lock_or_recover()
+            .feed(samples, channels, sample_rate);
+
+        let mut listener = self.listener.lock_or_recover();
+        let Some(listener) = listener.as_mut() else {
+            return;
+        };
+        let now = Instant::now();
+        if listener
+            .last
+            .is_some_and(|last| now.duration_since(last) < listener.interval)
+        {
+            return;
+        }
+        listener.last = Some(now);
+        listener
+            .callback
+            .call(Ok(self.latest()), ThreadsafeFunctionCallMode::NonBlocking);
+    }
+
+    /// Copy of the mono history, oldest sample first, and its sample rate
+    fn latest(&self) -> HistoryFrame {
+        let history = self.history.lock_or_recover();
+        (history.ordered(), history.sample_rate)
+    }
+}
+
+/// Ring of the latest `fft_size` samples, mixed down to mono
+struct History {
+    sample_rate: u32,
+    // Oldest sample at `write`
+    samples: Vec<f32>,
+    write: usize,
+    // Channels summed so far for a frame split across pushes
+    frame_sum: f32,
+    frame_channel: usize,
+}
+
+impl History {
+    fn new(len: usize) -> Self {
+        Self {
+            sample_rate: 0,
+            samples: vec![0.0; len],
+            write: 0,
+            frame_sum: 0.0,
+            frame_channel: 0,
+        }
+    }
+
+    fn feed<I: IntoIterator<Item = f32>>(&mut self, samples: I, channels: u16, sample_rate: u32) {
+        let channels = channels.max(1) as usize;
+        self.sample_rate = sample_rate;
+        for sample in samples {
+            self.frame_sum += sample;
+            self.frame_channel += 1;
+            if self.frame_channel >= channels {
+                self.samples[self.write] = self.frame_sum / channels as f32;
+                self.write = (self.write + 1) % self.samples.len();
+                self.frame_sum = 0.0;
+                self.frame_channel = 0;
+            }
+        }
+    }
+
+    fn ordered(&self) -> Vec<f32> {
+        let (newest, oldest) = self.samples.split_at(self.write);
+        oldest.iter().chain(newest).copied().collect()
+    }
+}
+
+/// Windowed transform with smoothing across calls
+struct Spectrum {
+    fft_size: usize,
+    window: WindowFunction,
+    smoothing: f64,
+    scale: MagnitudeScale,
+    coefficients: Vec<f64>,
+    // Scales bin magnitudes so a full-scale sine reads 1.0
+    normalization: f64,
+    fft: Fft,
+    re: Vec<f64>,
+    im: Vec<f64>,
+    smoothed: Vec<f64>,
+}
+
+impl Spectrum {
+    fn new(options: &AnalyserOptions) -> Result<Self> {
+        let fft_size = options.fft_size.unwrap_or(DEFAULT_FFT_SIZE);
+        if !fft_size.is_power_of_two() || !(MIN_FFT_SIZE..=MAX_FFT_SIZE).contains(&fft_size) {
+            return Err(error(
+                ErrorCode::InvalidArgument,
+                format!(
+                    "FFT size must be a power of two between {} and {}",
+                    MIN_FFT_SIZE, MAX_FFT_SIZE
+                ),
+            ));
+        }
+        let smoothing = options.smoothing.unwrap_or(DEFAULT_SMOOTHING);
+        if !(0.0..=1.0).contains(&smoothing) {
+            return Err(error(
+                ErrorCode::InvalidArgument,
+                "Smoothing must be between 0.0 and 1.0",
+            ));
+        }
+
+        let fft_size = fft_size as usize;
+        let window = options.window.unwrap_or_default();
+        let coefficients = window_coefficients(window, fft_size);
+        let normalization = 2.0 / coefficients.iter().sum::<f64>();
+        Ok(Self {
+            fft_size,
+            window,
+            smoothing,
+            scale: options.scale.unwrap_or_default(),
+            coefficients,
+            normalization,
+            fft: Fft::new(fft_size),
+            re: vec![0.0; fft_size],
+            im: vec![0.0; fft_size],
+            smoothed: vec![0.0; fft_size / 2],
+        })
+    }
+
+    /// Transform `frame` and fold it into the smoothed linear magnitudes
+    fn analyse(&mut self, frame: &[f32]) {
+        for ((re, &s), &w) in self.re.iter_mut().zip(frame).zip(&self.coefficients) {
+            *re = s as f64 * w;
+        }
+        self.im.fill(0.0);
+        self.fft.process(&mut self.re, &mut self.im);
+
+        for (k, smoothed) in self.smoothed.iter_mut().enumerate() {
+            let magnitude = self.re[k].hypot(self.im[k]) * self.normalization;
+            *smoothed = self.smoothing * *smoothed + (1.0 - self.smoothing) * magnitude;
+        }
+    }
+
+    fn scaled(&self, magnitude: f64) -> f32 {
+        match self.scale {
+            MagnitudeScale::Decibels => (20.0 * magnitude.log10()) as f32,
+            MagnitudeScale::Linear => magnitude as f32,
+        }
+    }
+
+    fn frequency_data(&mut self, frame: &[f32]) -> Vec<f32> {
+        self.analyse(frame);
+        self.smoothed.iter().map(|&m| self.scaled(m)).collect()
+    }
+
+    fn bands(&mut self, frame: &[f32], sample_rate: u32, layout: &BandLayout) -> Vec<f32> {
+        self.analyse(frame);
+        let bins = self.smoothed.len();
+        let bin_hz = sample_rate.max(1) as f64 / self.fft_size as f64;
+        let nyquist = sample_rate as f64 / 2.0;
+        let max_hz = layout.max_hz.unwrap_or(DEFAULT_MAX_BAND_HZ.min(nyquist));
+        let min_hz = layout.min_hz.min(max_hz);
+        let ratio = (max_hz / min_hz).max(1.0);
+
+        (0..layout.count)
+            .map(|band| {
+                let edge = |i: u32| min_hz * ratio.powf(i as f64 / layout.count as f64);
+                let lo = ((edge(band) / bin_hz).round() as usize).min(bins.saturating_sub(1));
+                let hi = ((edge(band + 1) / bin_hz).round() as usize).clamp(lo + 1, bins.max(1));
+                let peak = self.smoothed[lo..hi].iter().fold(0.0f64, |a, &m| a.max(m));
+                self.scaled(peak)
+            })
+            .collect()
+    }
+}
+
+/// Log-spaced band edges; `max_hz` falls back to the analysed rate's limit
+struct BandLayout {
+    count: u32,
+    min_hz: f64,
+    max_hz: Option<f64>,
+}
+
+impl BandLayout {
+    fn new(count: u32, min_hz: Option<f64>, max_hz: Option<f64>) -> Result<Self> {
+        let min_hz = min_hz.unwrap_or(DEFAULT_MIN_BAND_HZ);
+        let valid = count > 0
+            && min_hz.is_finite()
+            && min_hz > 0.0
+            && max_hz.is_none_or(|max| max.is_finite() && max > min_hz);
+        if !valid {
+            return Err(error(
+                ErrorCode::InvalidArgument,
+                "Bands need a positive count and 0 < minHz < maxHz",
+            ));
+        }
+        Ok(Self {
+            count,
+            min_hz,
+            max_hz,
+        })
+    }
+}
+
+pub(crate) fn window_coefficients(window: WindowFunction, len: usize) -> Vec<f64> {
+    let span = (len - 1) as f64;
+    (0..len)
+        .map(|n| {
+            let x = 2.0 * PI * n as f64 / span;
+            match window {
+                WindowFunction::Rectangular => 1.0,
+                WindowFunction::Hann => 0.5 - 0.5 * x.cos(),
+                WindowFunction::Hamming => 0.54 - 0.46 * x.cos(),
+                WindowFunction::Blackman => 0.42 - 0.5 * x.cos() + 0.08 * (2.0 * x).cos(),
+            }
+        })
+        .collect()
+}
+
+/// In-place iterative radix-2 FFT of one size, with its twiddle factors precomputed
+pub(crate) struct Fft {
+    // (cos, sin) of -2πk/n for k < n/2
+    twiddles: Vec<(f64, f64)>,
+}
+
+impl Fft {
+    /// `size` must be a power of two
+    pub(crate) fn new(size: usize) -> Self {
+        let angle = -2.0 * PI / size as f64;
+        Self {
+            twiddles: (0..size / 2)
+                .map(|k| {
+                    let (sin, cos) = (angle * k as f64).sin_cos();
+                    (cos, sin)
+                })
+                .collect(),
+        }
+    }
+
+    /// Transform `re`/`im`, which must have the size given to `new`
+    pub(crate) fn process(&self, re: &mut [f64], im: &mut [f64]) {
+        let n = re.len();
+        debug_assert_eq!(n, self.twiddles.len() * 2);
+        let mut j = 0;
+        for i in 1..n {
+            let mut bit = n >> 1;
+            while j & bit != 0 {
+                j ^= bit;
+                bit >>= 1;
+            }
+            j |= bit;
+            if i < j {
+                re.swap(i, j);
+                im.swap(i, j);
+            }
+        }
+
+        let mut len = 2;
+        while len <= n {
+            let half = len / 2;
+            let stride = n / len;
+            for start in (0..n).step_by(len) {
+                for k in 0..half {
+                    let (w_re, w_im) = self.twiddles[k * stride];
+                    let (a, b) = (start + k, start + k + half);
+                    let t_re = re[b] * w_re - im[b] * w_im;
+                    let t_im = re[b] * w_im + im[b] * w_re;
+                    re[b] = re[a] - t_re;
+                    im[b] = im[a] - t_im;
+                    re[a] += t_re;
+                    im[a] += t_im;
+                }
+            }
+            len <<= 1;
+        }
+    }
+}
+
+/// Source wrapper that hands the samples it plays to the attached analyser
+pub(crate) struct Tapped<S> {
+    source: S,
+    analyser: AnalyserSlot,
+    block: Vec<f32>,
+}
+
+impl<S: Source<Item = f32>> Tapped<S> {
+    pub(crate) fn new(source: S, analyser: AnalyserSlot) -> Self {
+        Self {
+            source,
+            analyser,
+            block: Vec::with_capacity(TAP_BLOCK_LEN),
+        }
+    }
+
+    fn flush(&mut self) {
+        if self.block.is_empty() {
+            return;
+        }
+        if let Some(analyser) = self.analyser.lock_or_recover().as_ref() {
+            analyser.push(
+                self.block.iter().copied(),
+                self.source.channels(),
+                self.source.sample_rate(),
+            );
+        }
+        self.block.clear();
+    }
+}
+
+impl<S: Source<Item = f32>> Iterator for Tapped<S> {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let sample = self.source.next();
+        match sample {
+            Some(sample) => {
+                self.block.push(sample);
+                if self.block.len() >= TAP_BLOCK_LEN {
+                    self.flush();
+                }
+            }
+            None => self.flush(),
+        }
+        sample
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.source.size_hint()
+    }
+}
+
+impl<S: Source<Item = f32>> Source for Tapped<S> {
+    fn current_span_len(&self) -> Option<usize> {
+        self.source.current_span_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.source.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.source.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.source.total_duration()
+    }
+
+    fn try_seek(&mut self, pos: Duration) -> std::result::Result<(), SeekError> {
+        self.block.clear();
+        self.source.try_seek(pos)
+    }
+}
@@ -1,12 +1,20 @@
 //! Real-time Audio Passthrough Module
 //! Provides low-latency audio loopback from input device to output device
 
+use crate::analyser::{AnalyserSlot, AudioAnalyser};
+use crate::engine::find_output_device;
+use crate::error::{
+    caused_by, error, stream_failure, ErrorCallback, ErrorReporter, LockExt, Result,
+};
 use crate::input::AudioLevels;
+use crate::loudness::{LoudnessMeter, LoudnessStats};
+use crate::types::{DevicesError, PlayError, StreamError};
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
 use napi::threadsafe_function::{ThreadsafeFunction, ThreadsafeFunctionCallMode};
-use napi::{Error, Result, Status};
 use napi_derive::napi;
 use ringbuf::HeapRb;
+// Output streams are built with rodio's cpal version
+use rodio::cpal::traits::{DeviceTrait as _, StreamTrait as _};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 
@@ -27,7 +35,8 @@ const I8_MAX_F32: f32 = 128.0;
 pub struct AudioPassthrough {
     // Streams
     input_stream: Option<cpal::Stream>,
-    output_stream: Option<cpal::Stream>,
+    // The output goes through rodio's cpal, which resolves the engine's device ids
+    output_stream: Option<rodio::cpal::Stream>,
 
     // Ring buffer for passing audio from input to output
     ring_buffer: Arc<Mutex<Option<HeapRb<f32>>>>,
@@ -40,6 +49,9 @@ pub struct AudioPassthrough {
     // Audio levels
     last_peak: Arc<Mutex<f64>>,
     last_rms: Arc<Mutex<f64>>,
+    loudness: Arc<Mutex<LoudnessMeter>>,
+    analyser: AnalyserSlot,
+    errors: ErrorReporter,
 
     // Callbacks
     on_levels_callback: Arc<Mutex<Option<OnLevelsCallback>>>,
@@ -70,21 +82,35 @@ impl AudioPassthrough {
             channels: DEFAULT_CHANNELS,
             last_peak: Arc::new(Mutex::new(0.0)),
             last_rms: Arc::new(Mutex::new(0.0)),
+            loudness: Arc::new(Mutex::new(LoudnessMeter::new(
+                DEFAULT_SAMPLE_RATE,
+                DEFAULT_CHANNELS,
+            ))),
+            analyser: Arc::new(Mutex::new(None)),
             on_levels_callback: Arc::new(Mutex::new(None)),
+            errors: ErrorReporter::default(),
         }
     }
 
+    /// Called with errors raised by the input or output stream, e.g. a
+    /// `DeviceLost` error when either device disappears
+    #[napi(ts_args_type = "callback: (err: Error) => void")]
+    pub fn set_on_error(&self, callback: ErrorCallback) -> Result<()> {
+        self.errors.set(callback);
+        Ok(())
+    }
+
     /// Set callback for audio level updates (peak, RMS)
     #[napi]
     pub fn set_on_levels(&self, callback: ThreadsafeFunction<AudioLevels>) -> Result<()> {
         let cb = Box::new(move |levels: AudioLevels| {
             callback.call(
-                Ok::<_, Error>(levels),
+                Ok::<_, napi::Error>(levels),
                 ThreadsafeFunctionCallMode::NonBlocking,
             );
         });
 
-        *self.on_levels_callback.lock().unwrap() = Some(cb);
+        *self.on_levels_callback.lock_or_recover() = Some(cb);
         Ok(())
     }
 
@@ -102,8 +128,8 @@ impl AudioPassthrough {
         latency_ms: Option<u32>,
     ) -> Result<()> {
         if self.is_running.load(Ordering::SeqCst) {
-            return Err(Error::new(
-                Status::GenericFailure,
+            return Err(error(
+                PlayError::AlreadyPlaying,
                 "Passthrough is already running",
             ));
         }
@@ -127,17 +153,18 @@ impl AudioPassthrough {
                 }
             }
             found_device
-                .ok_or_else(|| Error::new(Status::GenericFailure, "No input device found"))?
+                .ok_or_else(|| error(DevicesError::NoDevicesFound, "No input device found"))?
         };
 
         // Get output device
-        let output_device = self.get_output_device(&host, output_device_id.as_deref())?;
+        let output_device = find_output_device(output_device_id.as_deref())?;
 
         // Get input config
         let input_config = input_device.default_input_config().map_err(|e| {
-            Error::new(
-                Status::GenericFailure,
+            caused_by(
+                DevicesError::DeviceUnavailable,
                 format!("Failed to get input config: {}", e),
+                e,
             )
         })?;
 
@@ -146,9 +173,10 @@ impl AudioPassthrough {
 
         // Get output config - try to match input config
         let _output_config = output_device.default_output_config().map_err(|e| {
-            Error::new(
-                Status::GenericFailure,
+            caused_by(
+                DevicesError::DeviceUnavailable,
                 format!("Failed to get output config: {}", e),
+                e,
             )
         })?;
 
@@ -159,7 +187,7 @@ impl AudioPassthrough {
         let ring = HeapRb::<f32>::new(buffer_size as usize);
 
         {
-            let mut rb_guard = self.ring_buffer.lock().unwrap();
+            let mut rb_guard = self.ring_buffer.lock_or_recover();
             *rb_guard = Some(ring);
         }
 
@@ -169,13 +197,18 @@ impl AudioPassthrough {
         let last_peak = self.last_peak.clone();
         let last_rms = self.last_rms.clone();
         let on_levels = self.on_levels_callback.clone();
+        *self.loudness.lock_or_recover() = LoudnessMeter::new(self.sample_rate, self.channels);
+        let taps = InputTaps {
+            loudness: self.loudness.clone(),
+            analyser: self.analyser.clone(),
+            channels: self.channels,
+            sample_rate: self.sample_rate,
+        };
 
         // Build input stream
         let stream_config: cpal::StreamConfig = input_config.clone().into();
 
-        let err_fn = |err| {
-            eprintln!("Input stream error: {}", err);
-        };
+        let err_fn = report_stream_errors(self.errors.clone());
 
         // Create input stream
         let input_stream = match input_config.sample_format() {
@@ -183,10 +216,17 @@ impl AudioPassthrough {
                 &stream_config,
                 move |data: &[f32], _: &cpal::InputCallbackInfo| {
                     if is_running.load(Ordering::SeqCst) {
-                        process_input_data(data, &ring_buffer, &last_peak, &last_rms, &on_levels);
+                        process_input_data(
+                            data,
+                            &ring_buffer,
+                            &last_peak,
+                            &last_rms,
+                            &taps,
+                            &on_levels,
+                        );
                     }
                 },
-                err_fn,
+                err_fn.clone(),
                 None,
             ),
             cpal::SampleFormat::I16 => input_device.build_input_stream(
@@ -200,11 +240,12 @@ impl AudioPassthrough {
                             &ring_buffer,
                             &last_peak,
                             &last_rms,
+                            &taps,
                             &on_levels,
                         );
                     }
                 },
-                err_fn,
+                err_fn.clone(),
                 None,
             ),
             cpal::SampleFormat::I8 => input_device.build_input_stream(
@@ -218,11 +259,12 @@ impl AudioPassthrough {
                             &ring_buffer,
                             &last_peak,
                             &last_rms,
+                            &taps,
                             &on_levels,
                         );
                     }
                 },
-                err_fn,
+                err_fn.clone(),
                 None,
             ),
             cpal::SampleFormat::U16 => input_device.build_input_stream(
@@ -238,16 +280,17 @@ impl AudioPassthrough {
                             &ring_buffer,
                             &last_peak,
                             &last_rms,
+                            &taps,
                             &on_levels,
                         );
                     }
                 },
-                err_fn,
+                err_fn.clone(),
                 None,
             ),
             _ => {
-                return Err(Error::new(
-                    Status::GenericFailure,
+                return Err(error(
+                    StreamError::UnsupportedFormat,
                     format!(
                         "Unsupported input sample format: {:?}",
                         input_config.sample_format()
@@ -256,9 +299,10 @@ impl AudioPassthrough {
             }
         }
         .map_err(|e| {
-            Error::new(
-                Status::GenericFailure,
+            caused_by(
+                DevicesError::DeviceUnavailable,
                 format!("Failed to build input stream: {}", e),
+                e,
             )
         })?;
 
@@ -267,12 +311,18 @@ impl AudioPassthrough {
         let is_running_out = self.is_running.clone();
 
         // Build output stream with matching config
+        let output_config = rodio::cpal::StreamConfig {
+            channels: stream_config.channels,
+            sample_rate: rodio::cpal::SampleRate(stream_config.sample_rate),
+            buffer_size: rodio::cpal::BufferSize::Default,
+        };
+        let output_errors = self.errors.clone();
         let output_stream = output_device
             .build_output_stream(
-                &stream_config,
-                move |data: &mut [f32], _: &cpal::OutputCallbackInfo| {
+                &output_config,
+                move |data: &mut [f32], _: &rodio::cpal::OutputCallbackInfo| {
                     if is_running_out.load(Ordering::SeqCst) {
-                        let mut rb_guard = ring_buffer_out.lock().unwrap();
+                        let mut rb_guard = ring_buffer_out.lock_or_recover();
                         if let Some(rb) = rb_guard.as_mut() {
                             // Use pop_iter to get samples - it handles available samples internally
                             use ringbuf::traits::Consumer;
@@ -294,30 +344,34 @@ impl AudioPassthrough {
                         }
                     }
                 },
-                |err| {
-                    eprintln!("Output stream error: {}", err);
+                move |err| {
+                    let lost = matches!(err, rodio::cpal::StreamError::DeviceNotAvailable);
+                    output_errors.report(stream_failure(lost, &err));
                 },
                 None,
             )
             .map_err(|e| {
-                Error::new(
-                    Status::GenericFailure,
+                caused_by(
+                    DevicesError::DeviceUnavailable,
                     format!("Failed to build output stream: {}", e),
+                    e,
                 )
             })?;
 
         // Start both streams
         input_stream.play().map_err(|e| {
-            Error::new(
-                Status::GenericFailure,
+            caused_by(
+                DevicesError::DeviceUnavailable,
                 format!("Failed to start input stream: {}", e),
+                e,
             )
         })?;
 
         output_stream.play().map_err(|e| {
-            Error::new(
-                Status::GenericFailure,
+            caused_by(
+                DevicesError::DeviceUnavailable,
                 format!("Failed to start output stream: {}", e),
+                e,
             )
         })?;
 
@@ -343,7 +397,7 @@ impl AudioPassthrough {
 
         // Clear ring buffer
         {
-            let mut rb_guard = self.ring_buffer.lock().unwrap();
+            let mut rb_guard = self.ring_buffer.lock_or_recover();
             *rb_guard = None;
         }
 
@@ -360,11 +414,34 @@ impl AudioPassthrough {
     #[napi]
     pub fn get_levels(&self) -> AudioLevels {
         AudioLevels {
-            peak: *self.last_peak.lock().unwrap(),
-            rms: *self.last_rms.lock().unwrap(),
+            peak: *self.last_peak.lock_or_recover(),
+            rms: *self.last_rms.lock_or_recover(),
         }
     }
 
+    /// Get EBU R128 loudness of the input since `start` or `resetLoudness`
+    #[napi]
+    pub fn get_loudness(&self) -> LoudnessStats {
+        self.loudness.lock_or_recover().stats()
+    }
+
+    /// Restart loudness measurement, e.g. at the start of a programme
+    #[napi]
+    pub fn reset_loudness(&self) {
+        *self.loudness.lock_or_recover() = LoudnessMeter::new(self.sample_rate, self.channels);
+    }
+
+    /// Feed the input to `analyser`, replacing any analyser attached before
+    #[napi]
+    pub fn attach_analyser(&self, analyser: &AudioAnalyser) {
+        *self.analyser.lock_or_recover() = Some(analyser.shared());
+    }
+
+    #[napi]
+    pub fn detach_analyser(&self) {
+        *self.analyser.lock_or_recover() = None;
+    }
+
     /// Get the current sample rate
     #[napi]
     pub fn get_sample_rate(&self) -> u32 {
@@ -383,41 +460,17 @@ impl AudioPassthrough {
         crate::input::get_input_devices().unwrap_or_default()
     }
 
-    /// Get available output devices
+    /// Get available output devices, with the ids the audio engine uses
     #[napi]
     pub fn get_output_devices() -> Result<Vec<crate::types::AudioDeviceInfo>> {
-        let mut result = Vec::new();
-        let host = cpal::default_host();
-
-        // Get the default output device for comparison
-        let default_device = host.default_output_device();
-
-        if let Ok(devices) = host.output_devices() {
-            for (i, device) in devices.enumerate() {
-                if let Ok(desc) = device.description() {
-                    let name = desc.name();
-                    // Skip null/discard devices
-                    let name_lower = name.to_lowercase();
-                    if name_lower.contains("null") || name_lower.contains("discard") {
-                        continue;
-                    }
-
-                    // Check if this is the default device
-                    let is_default = default_device.as_ref().is_some_and(|d| {
-                        d.description().map(|dd| dd.name() == name).unwrap_or(false)
-                    });
-
-                    result.push(crate::types::AudioDeviceInfo {
-                        id: format!("{}:{}", host.id(), i),
-                        name: name.to_string(),
-                        host: format!("{:?}", host.id()),
-                        is_default,
-                    });
-                }
-            }
-        }
-
-        Ok(result)
+        // Skip null/discard devices
+        Ok(crate::engine::output_devices()
+            .into_iter()
+            .filter(|device| {
+                let name_lower = device.name.to_lowercase();
+                !name_lower.contains("null") && !name_lower.contains("discard")
+            })
+            .collect())
     }
 
     // Helper to get input device
@@ -428,28 +481,32 @@ impl AudioPassthrough {
                     let parts: Vec<&str> = id.split(DEVICE_ID_SEPARATOR).collect();
                     let host_name = parts[0];
                     let device_idx = parts[1].parse::<usize>().map_err(|_| {
-                        Error::new(Status::InvalidArg, format!("Invalid device index: {}", id))
+                        error(
+                            DevicesError::InvalidDevice,
+                            format!("Invalid device index: {}", id),
+                        )
                     })?;
 
                     let host_id = cpal::available_hosts()
                         .into_iter()
                         .find(|h| format!("{:?}", h).to_lowercase() == host_name.to_lowercase())
                         .ok_or_else(|| {
-                            Error::new(
-                                Status::InvalidArg,
+                            error(
+                                DevicesError::InvalidDevice,
                                 format!("Host '{}' not found", host_name),
                             )
                         })?;
 
-                    let host = cpal::host_from_id(host_id)
-                        .map_err(|e| Error::new(Status::GenericFailure, e.to_string()))?;
+                    let host = cpal::host_from_id(host_id).map_err(|e| {
+                        caused_by(DevicesError::DeviceUnavailable, e.to_string(), e)
+                    })?;
 
                     host.input_devices()
-                        .map_err(|e| Error::new(Status::GenericFailure, e.to_string()))?
+                        .map_err(|e| caused_by(DevicesError::DeviceUnavailable, e.to_string(), e))?
                         .nth(device_idx)
                         .ok_or_else(|| {
-                            Error::new(
-                                Status::InvalidArg,
+                            error(
+                                DevicesError::InvalidDevice,
                                 format!("Device at index {} not found", device_idx),
                             )
                         })
@@ -457,67 +514,24 @@ impl AudioPassthrough {
                     // Try to parse as index
                     let idx = id.parse::<usize>().unwrap_or(0);
                     host.input_devices()
-                        .map_err(|e| Error::new(Status::GenericFailure, e.to_string()))?
+                        .map_err(|e| caused_by(DevicesError::DeviceUnavailable, e.to_string(), e))?
                         .nth(idx)
-                        .ok_or_else(|| Error::new(Status::InvalidArg, "Device not found"))
+                        .ok_or_else(|| error(DevicesError::InvalidDevice, "Device not found"))
                 }
             }
             None => host
                 .default_input_device()
-                .ok_or_else(|| Error::new(Status::GenericFailure, "No default input device")),
+                .ok_or_else(|| error(DevicesError::NoDevicesFound, "No default input device")),
         }
     }
+}
 
-    // Helper to get output device
-    fn get_output_device(
-        &self,
-        host: &cpal::Host,
-        device_id: Option<&str>,
-    ) -> Result<cpal::Device> {
-        match device_id {
-            Some(id) => {
-                if id.contains(DEVICE_ID_SEPARATOR) {
-                    let parts: Vec<&str> = id.split(DEVICE_ID_SEPARATOR).collect();
-                    let host_name = parts[0];
-                    let device_idx = parts[1].parse::<usize>().map_err(|_| {
-                        Error::new(Status::InvalidArg, format!("Invalid device index: {}", id))
-                    })?;
-
-                    let host_id = cpal::available_hosts()
-                        .into_iter()
-                        .find(|h| format!("{:?}", h).to_lowercase() == host_name.to_lowercase())
-                        .ok_or_else(|| {
-                            Error::new(
-                                Status::InvalidArg,
-                                format!("Host '{}' not found", host_name),
-                            )
-                        })?;
-
-                    let host = cpal::host_from_id(host_id)
-                        .map_err(|e| Error::new(Status::GenericFailure, e.to_string()))?;
-
-                    host.output_devices()
-                        .map_err(|e| Error::new(Status::GenericFailure, e.to_string()))?
-                        .nth(device_idx)
-                        .ok_or_else(|| {
-                            Error::new(
-                                Status::InvalidArg,
-                                format!("Device at index {} not found", device_idx),
-                            )
-                        })
-                } else {
-                    let idx = id.parse::<usize>().unwrap_or(0);
-                    host.output_devices()
-                        .map_err(|e| Error::new(Status::GenericFailure, e.to_string()))?
-                        .nth(idx)
-                        .ok_or_else(|| Error::new(Status::InvalidArg, "Device not found"))
-                }
-            }
-            None => host
-                .default_output_device()
-                .ok_or_else(|| Error::new(Status::GenericFailure, "No default output device")),
-        }
-    }
+/// Meters fed from the input callback
+struct InputTaps {
+    loudness: Arc<Mutex<LoudnessMeter>>,
+    analyser: AnalyserSlot,
+    channels: u16,
+    sample_rate: u32,
 }
 
 /// Process input audio data - calculate levels and push to ring buffer
@@ -526,6 +540,7 @@ fn process_input_data(
     ring_buffer: &Arc<Mutex<Option<HeapRb<f32>>>>,
     last_peak: &Arc<Mutex<f64>>,
     last_rms: &Arc<Mutex<f64>>,
+    taps: &InputTaps,
     on_levels: &Arc<Mutex<Option<OnLevelsCallback>>>,
 ) {
     // Calculate peak and RMS
@@ -548,17 +563,24 @@ fn process_input_data(
 
     // Update last levels
     {
-        let mut peak_guard = last_peak.lock().unwrap();
+        let mut peak_guard = last_peak.lock_or_recover();
         *peak_guard = peak as f64;
     }
     {
-        let mut rms_guard = last_rms.lock().unwrap();
+        let mut rms_guard = last_rms.lock_or_recover();
         *rms_guard = rms as f64;
     }
 
+    taps.loudness
+        .lock_or_recover()
+        .add_samples(data.iter().copied());
+    if let Some(analyser) = taps.analyser.lock_or_recover().as_ref() {
+        analyser.push(data.iter().copied(), taps.channels, taps.sample_rate);
+    }
+
     // Emit callback
     {
-        let callback_guard = on_levels.lock().unwrap();
+        let callback_guard = on_levels.lock_or_recover();
         if let Some(cb) = callback_guard.as_ref() {
             cb(AudioLevels {
                 peak: peak as f64,
@@ -569,7 +591,7 @@ fn process_input_data(
 
     // Push to ring buffer
     {
-        let mut rb_guard = ring_buffer.lock().unwrap();
+        let mut rb_guard = ring_buffer.lock_or_recover();
         if let Some(rb) = rb_guard.as_mut() {
             use ringbuf::traits::Producer;
             // Use push_slice for more efficient bulk insert
@@ -594,3 +616,13 @@ pub fn start_passthrough(
     passthrough.start(input_device, output_device, latency_ms)?;
     Ok(passthrough)
 }
+
+/// Forward stream errors to the `onError` callback
+fn report_stream_errors(
+    errors: ErrorReporter,
+) -> impl FnMut(cpal::StreamError) + Clone + Send + 'static {
+    move |err| {
+        let lost = matches!(err, cpal::StreamError::DeviceNotAvailable);
+        errors.report(stream_failure(lost, &err));
+    }
+}
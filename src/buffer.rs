@@ -1,5 +1,9 @@
 //! Audio buffer types for sample data storage and manipulation
 
+use crate::conversions::pcm_to_i16;
+use crate::error::LockExt;
+use crate::loudness::{LoudnessMeter, LoudnessStats};
+use napi::bindgen_prelude::{Either, Float32Array, Int16Array, Uint8Array};
 use napi_derive::napi;
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
@@ -20,14 +24,23 @@ impl SamplesBuffer {
             samples: Arc::new(Mutex::new(samples)),
         }
     }
+
+    /// Copy of the samples for use on the Rust side
+    pub(crate) fn samples(&self) -> Vec<i16> {
+        self.samples.lock_or_recover().clone()
+    }
 }
 
 #[napi]
 impl SamplesBuffer {
-    /// Create a new samples buffer
+    /// Create a new samples buffer from an `Int16Array` or a `Float32Array` in [-1, 1]
     #[napi(constructor)]
-    pub fn create(channels: u32, sample_rate: u32, samples: Vec<i16>) -> Self {
-        Self::new(channels as u16, sample_rate, samples)
+    pub fn create(
+        channels: u32,
+        sample_rate: u32,
+        samples: Either<Int16Array, Float32Array>,
+    ) -> Self {
+        Self::new(channels as u16, sample_rate, pcm_to_i16(&samples))
     }
 
     /// Get the number of channels in this buffer (1=mono, 2=stereo)
@@ -45,7 +58,7 @@ impl SamplesBuffer {
     /// Get the number of samples in this buffer
     #[napi]
     pub fn get_len(&self) -> u32 {
-        let samples = self.samples.lock().unwrap();
+        let samples = self.samples.lock_or_recover();
         samples.len() as u32
     }
 
@@ -55,16 +68,15 @@ impl SamplesBuffer {
         self.get_len() as f64 / (self.sample_rate as f64 * self.channels as f64)
     }
 
-    /// Get a copy of the samples in this buffer
+    /// Get a copy of the samples in this buffer, handed to JS without a per-sample conversion
     #[napi]
-    pub fn get_samples(&self) -> Vec<i16> {
-        let samples = self.samples.lock().unwrap();
-        samples.clone()
+    pub fn get_samples(&self) -> Int16Array {
+        Int16Array::new(self.samples())
     }
 
-    /// Create a buffer from raw bytes (16-bit little-endian samples)
+    /// Create a buffer from raw bytes (16-bit little-endian samples) in a `Buffer` or `Uint8Array`
     #[napi(factory)]
-    pub fn from_bytes(bytes: Vec<u8>, channels: u32, sample_rate: u32) -> Self {
+    pub fn from_bytes(bytes: Uint8Array, channels: u32, sample_rate: u32) -> Self {
         let samples: Vec<i16> = bytes
             .chunks_exact(2)
             .map(|chunk| i16::from_le_bytes([chunk[0], chunk[1]]))
@@ -72,16 +84,21 @@ impl SamplesBuffer {
         Self::new(channels as u16, sample_rate, samples)
     }
 
-    /// Play this buffer with the given sink
+    /// Measure EBU R128 loudness and true peak of this buffer
     #[napi]
-    pub fn play(&self) -> napi::Result<()> {
-        use rodio::{OutputStreamBuilder, Sink, Source};
+    pub fn analyze_loudness(&self) -> LoudnessStats {
+        let mut meter = LoudnessMeter::new(self.sample_rate, self.channels);
+        let samples = self.samples.lock_or_recover();
+        meter.add_samples(samples.iter().map(|&s| s as f32 / 32768.0));
+        meter.stats()
+    }
 
-        let stream = OutputStreamBuilder::open_default_stream()
-            .map_err(|e| napi::Error::new(napi::Status::GenericFailure, e.to_string()))?;
+    /// Start playing this buffer on the default output and return immediately
+    #[napi]
+    pub fn play(&self) -> crate::error::Result<crate::engine::PlaybackHandle> {
+        use rodio::Source;
 
-        let sink = Sink::connect_new(stream.mixer());
-        let samples_i16 = self.samples.lock().unwrap().clone();
+        let samples_i16 = self.samples.lock_or_recover().clone();
 
         // Convert i16 samples to f32 for rodio
         let samples_f32: Vec<f32> = samples_i16
@@ -126,9 +143,10 @@ impl SamplesBuffer {
             }
 
             fn total_duration(&self) -> Option<Duration> {
-                Some(Duration::from_secs_f64(
+                Duration::try_from_secs_f64(
                     (self.samples.len() as f64) / (self.sample_rate as f64 * self.channels as f64),
-                ))
+                )
+                .ok()
             }
         }
 
@@ -139,13 +157,7 @@ impl SamplesBuffer {
             channels: self.channels,
         };
 
-        sink.append(source);
-        sink.play();
-
-        // Block until playback finishes to keep stream alive
-        sink.sleep_until_end();
-
-        Ok(())
+        crate::engine::play_voice(source)
     }
 }
 
@@ -158,9 +170,9 @@ pub struct StaticSamplesBuffer {
 #[napi]
 impl StaticSamplesBuffer {
     #[napi(constructor)]
-    pub fn new(channels: u32, sample_rate: u32, samples: Vec<i16>) -> Self {
+    pub fn new(channels: u32, sample_rate: u32, samples: Either<Int16Array, Float32Array>) -> Self {
         StaticSamplesBuffer {
-            inner: SamplesBuffer::new(channels as u16, sample_rate, samples),
+            inner: SamplesBuffer::new(channels as u16, sample_rate, pcm_to_i16(&samples)),
         }
     }
 
@@ -1,7 +1,20 @@
 //! Audio format conversion utilities
 
+use napi::bindgen_prelude::{Either, Float32Array, Int16Array, Int32Array};
 use napi_derive::napi;
 
+/// Copy PCM passed from JS into owned 16-bit samples.
+/// `Int16Array` data is copied as-is; `Float32Array` data in [-1, 1] is scaled and clamped.
+pub(crate) fn pcm_to_i16(data: &Either<Int16Array, Float32Array>) -> Vec<i16> {
+    match data {
+        Either::A(samples) => samples.to_vec(),
+        Either::B(samples) => samples
+            .iter()
+            .map(|&s| (s * 32768.0).clamp(i16::MIN as f32, i16::MAX as f32) as i16)
+            .collect(),
+    }
+}
+
 /// Parameters for channel count conversion
 #[napi(object)]
 pub struct ChannelCountConversion {
@@ -42,20 +55,23 @@ impl ChannelCountConverter {
 
     /// Convert audio samples from source channel count to target channel count
     #[napi]
-    pub fn convert(&self, samples: Vec<i16>) -> Vec<i16> {
+    pub fn convert(&self, samples: Int16Array) -> Int16Array {
         let src = self.source_channels as usize;
         let dst = self.target_channels as usize;
 
         if src == dst {
             return samples;
         }
+        if src == 0 || dst == 0 {
+            return Int16Array::new(Vec::new());
+        }
 
         let output_len = (samples.len() / src) * dst;
         let mut output = Vec::with_capacity(output_len);
 
         if src == 1 && dst == 2 {
             // Mono to stereo (duplicate samples)
-            for sample in samples {
+            for &sample in samples.iter() {
                 output.push(sample);
                 output.push(sample);
             }
@@ -71,7 +87,7 @@ impl ChannelCountConverter {
             // For other conversions, upsample by duplicating or downsample by averaging
             if dst > src {
                 // Upsample: duplicate samples
-                for sample in samples {
+                for &sample in samples.iter() {
                     for _ in 0..dst {
                         output.push(sample / src as i16);
                     }
@@ -87,7 +103,7 @@ impl ChannelCountConverter {
             }
         }
 
-        output
+        Int16Array::new(output)
     }
 
     #[napi]
@@ -120,10 +136,13 @@ impl SampleRateConverter {
 
     /// Convert audio samples from source rate to target rate using linear interpolation
     #[napi]
-    pub fn convert(&self, samples: Vec<i16>) -> Vec<i16> {
+    pub fn convert(&self, samples: Int16Array) -> Int16Array {
         if self.source_rate == self.target_rate {
             return samples;
         }
+        if self.source_rate == 0 || self.target_rate == 0 {
+            return Int16Array::new(Vec::new());
+        }
 
         let ratio = self.target_rate as f64 / self.source_rate as f64;
         let output_len = (samples.len() as f64 * ratio) as usize;
@@ -156,7 +175,7 @@ impl SampleRateConverter {
             }
         }
 
-        output
+        Int16Array::new(output)
     }
 
     #[napi]
@@ -189,8 +208,8 @@ impl SampleTypeConverter {
 
     /// Convert between different sample bit depths
     #[napi]
-    pub fn convert(&self, samples: Vec<i32>) -> Vec<i32> {
-        match (self.source_bits, self.target_bits) {
+    pub fn convert(&self, samples: Int32Array) -> Int32Array {
+        let converted: Vec<i32> = match (self.source_bits, self.target_bits) {
             // 24-bit to 16-bit (truncate and clamp)
             (24, 16) => samples
                 .iter()
@@ -207,7 +226,10 @@ impl SampleTypeConverter {
                 .collect(),
 
             // 8-bit unsigned to 16-bit signed
-            (8, 16) => samples.iter().map(|&s| (s - 128) * 256).collect(),
+            (8, 16) => samples
+                .iter()
+                .map(|&s| s.saturating_sub(128).saturating_mul(256))
+                .collect(),
 
             // 16-bit to 8-bit (truncate)
             (16, 8) => samples
@@ -228,7 +250,10 @@ impl SampleTypeConverter {
             (32, 24) => samples.iter().map(|&s| s >> 8).collect(),
 
             // 8-bit to 24-bit (pad and shift)
-            (8, 24) => samples.iter().map(|&s| (s - 128) << 16).collect(),
+            (8, 24) => samples
+                .iter()
+                .map(|&s| s.saturating_sub(128) << 16)
+                .collect(),
 
             // 24-bit to 8-bit (truncate)
             (24, 8) => samples.iter().map(|&s| (s >> 16) + 128).collect(),
@@ -244,8 +269,9 @@ impl SampleTypeConverter {
                 .collect(),
 
             // Return samples as-is for other conversions
-            _ => samples,
-        }
+            _ => return samples,
+        };
+        Int32Array::new(converted)
     }
 
     #[napi]
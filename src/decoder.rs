@@ -1,20 +1,63 @@
 //! Audio decoder for various audio formats
 
-use napi::{Error, Result, Status};
+use crate::debug_log;
+use crate::error::{
+    decode_failure, error, open_failure, AudioTask, CodedTask, ErrorCode, LockExt, Result,
+};
+use crate::loudness::{self, LoudnessStats};
+use crate::spectrogram::{self, Spectrogram, SpectrogramOptions};
+use crate::tempo::{self, TempoInfo, TempoOptions};
+use crate::types::{CancelToken, DecoderError};
+use crate::waveform::{self, PeaksOptions, WaveformPeaks};
+use napi::bindgen_prelude::{AbortSignal, AsyncTask, Int16Array};
+use napi::Env;
 use napi_derive::napi;
+use rodio::source::{SeekError, UniformSourceIterator};
 use rodio::{Decoder, Source};
 use std::fs::File;
 use std::io::{BufReader, Cursor};
+use std::sync::atomic::{AtomicU32, Ordering};
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Frames returned by `nextChunk`
+const DEFAULT_CHUNK_FRAMES: u32 = 4096;
+
+type BoxedSource = Box<dyn Source + Send>;
 
 /// Decoder for audio files in various formats (WAV, MP3, FLAC, OGG, etc.)
 #[napi]
 pub struct AudioDecoder {
-    data: Arc<Mutex<Option<Vec<u8>>>>,
+    data: Option<Arc<[u8]>>,
     file_path: Option<String>,
     sample_rate: u32,
     channels: u16,
     duration: f64,
+    // Set when the output rate or channel layout differs from the file's
+    convert: bool,
+    // Open decoder kept between `read` calls, created on first use
+    cursor: Mutex<Option<DecoderCursor>>,
+}
+
+/// Streaming read position over an open rodio decoder
+struct DecoderCursor {
+    source: BoxedSource,
+    frame: u64,
+}
+
+impl Clone for AudioDecoder {
+    /// Clones share the audio but start with their own read position
+    fn clone(&self) -> Self {
+        Self {
+            data: self.data.clone(),
+            file_path: self.file_path.clone(),
+            sample_rate: self.sample_rate,
+            channels: self.channels,
+            duration: self.duration,
+            convert: self.convert,
+            cursor: Mutex::new(None),
+        }
+    }
 }
 
 #[napi]
@@ -24,52 +67,22 @@ impl AudioDecoder {
     pub fn from_file(file_path: String) -> Result<Self> {
         let path = std::path::Path::new(&file_path);
         if !path.exists() {
-            return Err(Error::new(
-                Status::InvalidArg,
+            return Err(error(
+                DecoderError::FileNotFound,
                 format!("File not found: {}", file_path),
             ));
         }
 
-        let file = File::open(path)
-            .map_err(|e| Error::new(Status::InvalidArg, format!("Failed to open file: {}", e)))?;
-
-        let reader = BufReader::new(file);
-        let source = Decoder::new(reader).map_err(|e| {
-            Error::new(Status::InvalidArg, format!("Failed to decode audio: {}", e))
-        })?;
-
-        let sample_rate = source.sample_rate();
-        let channels = source.channels();
-        let duration = source.total_duration().map_or(0.0, |d| d.as_secs_f64());
-
-        Ok(Self {
-            data: Arc::new(Mutex::new(None)),
-            file_path: Some(file_path),
-            sample_rate,
-            channels,
-            duration,
-        })
+        let source = open_file(&file_path)?;
+        Ok(Self::probed(&source, None, Some(file_path)))
     }
 
     /// Create a decoder from raw audio data
     #[napi(factory)]
     pub fn from_data(data: Vec<u8>) -> Result<Self> {
-        let cursor = Cursor::new(data.clone());
-        let source = Decoder::new(cursor).map_err(|e| {
-            Error::new(Status::InvalidArg, format!("Failed to decode audio: {}", e))
-        })?;
-
-        let sample_rate = source.sample_rate();
-        let channels = source.channels();
-        let duration = source.total_duration().map_or(0.0, |d| d.as_secs_f64());
-
-        Ok(Self {
-            data: Arc::new(Mutex::new(Some(data))),
-            file_path: None,
-            sample_rate,
-            channels,
-            duration,
-        })
+        let data: Arc<[u8]> = data.into();
+        let source = open_data(&data)?;
+        Ok(Self::probed(&source, Some(data), None))
     }
 
     /// Get sample rate of decoded audio
@@ -93,79 +106,207 @@ impl AudioDecoder {
     /// Reset decoder to beginning
     #[napi]
     pub fn reset(&mut self) -> Result<()> {
-        match &self.file_path {
-            Some(path) => {
-                let path_clone = path.clone();
-                *self = AudioDecoder::from_file(path_clone)?;
-                Ok(())
-            }
-            None => {
-                let data = {
-                    let data_guard = self.data.lock().unwrap();
-                    data_guard.as_ref().cloned()
-                };
-                if let Some(data) = data {
-                    *self = AudioDecoder::from_data(data)?;
-                    Ok(())
-                } else {
-                    Err(Error::new(Status::InvalidArg, "No audio data to reset"))
-                }
-            }
-        }
+        *self.cursor.lock_or_recover() = None;
+        Ok(())
     }
 
-    /// Decode all audio samples into a vector
+    /// Read up to `frames` frames from the current position, keeping the decoder open
+    /// between calls. Returns an empty array once the end is reached.
     #[napi]
-    pub fn decode_to_samples(&self) -> Result<Vec<i16>> {
-        if let Some(file_path) = &self.file_path {
-            let file = File::open(file_path).map_err(|e| {
-                Error::new(Status::InvalidArg, format!("Failed to open file: {}", e))
-            })?;
-            let reader = BufReader::new(file);
-            let source = Decoder::new(reader).map_err(|e| {
-                Error::new(Status::InvalidArg, format!("Failed to decode audio: {}", e))
-            })?;
-
-            let samples: Vec<f32> = source.collect();
-            let samples_i16: Vec<i16> = samples.into_iter().map(|s| (s * 32767.0) as i16).collect();
-            Ok(samples_i16)
-        } else {
-            let data_guard = self.data.lock().unwrap();
-            if let Some(data) = data_guard.as_ref() {
-                let cursor = Cursor::new(data.clone());
-                let source = Decoder::new(cursor).map_err(|e| {
-                    Error::new(Status::InvalidArg, format!("Failed to decode audio: {}", e))
-                })?;
-
-                let samples: Vec<f32> = source.collect();
-                let samples_i16: Vec<i16> =
-                    samples.into_iter().map(|s| (s * 32767.0) as i16).collect();
-                Ok(samples_i16)
-            } else {
-                Err(Error::new(Status::InvalidArg, "No audio data to decode"))
-            }
-        }
+    pub fn read(&self, frames: u32) -> Result<Int16Array> {
+        self.read_samples(frames).map(Int16Array::new)
     }
 
-    /// Get a slice of decoded samples (limited by duration to prevent memory issues)
+    /// Read the next chunk of frames, or null once the end is reached
     #[napi]
-    pub fn decode_slice(&self, start_seconds: f64, end_seconds: f64) -> Result<Vec<i16>> {
-        let mut samples = self.decode_to_samples()?;
-        let start_idx = (start_seconds * self.sample_rate as f64 * self.channels as f64) as usize;
-        let end_idx = (end_seconds * self.sample_rate as f64 * self.channels as f64) as usize;
-        let end_idx = end_idx.min(samples.len());
+    pub fn next_chunk(&self) -> Result<Option<Int16Array>> {
+        let chunk = self.read(DEFAULT_CHUNK_FRAMES)?;
+        Ok(if chunk.is_empty() { None } else { Some(chunk) })
+    }
 
-        if start_idx >= samples.len() {
-            return Ok(Vec::new());
+    /// Move the read position to `seconds`, using the decoder's native seek where the
+    /// format supports it
+    #[napi]
+    pub fn seek(&self, seconds: f64) -> Result<()> {
+        if !seconds.is_finite() || seconds < 0.0 {
+            return Err(error(
+                crate::types::SeekError::InvalidPosition,
+                "Position must be a finite, non-negative number of seconds",
+            ));
         }
 
-        if start_idx >= end_idx {
-            return Ok(Vec::new());
+        let mut cursor = self.cursor.lock_or_recover();
+        let open = self.open_cursor(&mut cursor)?;
+        let frame = (seconds * self.sample_rate as f64) as u64;
+        if let Err(e) = seek_source(&mut open.source, seconds) {
+            debug_log!("Native seek failed ({}), decoding up to {}s", e, seconds);
+            // Formats without seeking support are reopened and decoded up to the target
+            let mut source = self.open_source()?;
+            skip_frames(&mut source, frame, self.channels, &CancelToken::default())?;
+            open.source = source;
         }
+        open.frame = frame;
+        Ok(())
+    }
 
-        samples.drain(end_idx..);
-        samples.drain(..start_idx);
-        Ok(samples)
+    /// Get the current read position in seconds
+    #[napi]
+    pub fn get_position(&self) -> f64 {
+        let cursor = self.cursor.lock_or_recover();
+        cursor
+            .as_ref()
+            .map_or(0.0, |c| c.frame as f64 / self.sample_rate.max(1) as f64)
+    }
+
+    /// Decode all audio samples into a vector
+    #[napi]
+    pub fn decode_to_samples(&self) -> Result<Int16Array> {
+        self.decode_with(&CancelToken::default())
+            .map(Int16Array::new)
+    }
+
+    /// Decode all audio samples without blocking the event loop.
+    /// Rejects with an abort error when `signal` fires before decoding completes.
+    #[napi(ts_return_type = "Promise<Int16Array>")]
+    pub fn decode_to_samples_async(
+        &self,
+        signal: Option<AbortSignal>,
+    ) -> AsyncTask<CodedTask<DecodeTask>> {
+        DecodeTask::spawn(self.clone(), DecodeJob::Full, signal)
+    }
+
+    /// Decode the samples between two positions, seeking to the start instead of
+    /// decoding the whole file. Does not move the `read` position.
+    #[napi]
+    pub fn decode_slice(&self, start_seconds: f64, end_seconds: f64) -> Result<Int16Array> {
+        self.slice_with(start_seconds, end_seconds, &CancelToken::default())
+            .map(Int16Array::new)
+    }
+
+    /// Get a slice of decoded samples without blocking the event loop
+    #[napi(ts_return_type = "Promise<Int16Array>")]
+    pub fn decode_slice_async(
+        &self,
+        start_seconds: f64,
+        end_seconds: f64,
+        signal: Option<AbortSignal>,
+    ) -> AsyncTask<CodedTask<DecodeTask>> {
+        DecodeTask::spawn(
+            self.clone(),
+            DecodeJob::Slice {
+                start_seconds,
+                end_seconds,
+            },
+            signal,
+        )
+    }
+
+    /// Reduce the audio to min/max/RMS peaks per bin and channel for drawing
+    /// waveforms, decoding in a single streaming pass. Does not move the `read` position.
+    #[napi]
+    pub fn compute_peaks(&self, options: PeaksOptions) -> Result<WaveformPeaks> {
+        self.peaks_with(&options, &CancelToken::default())
+    }
+
+    /// Compute waveform peaks without blocking the event loop
+    #[napi(ts_return_type = "Promise<WaveformPeaks>")]
+    pub fn compute_peaks_async(
+        &self,
+        options: PeaksOptions,
+        signal: Option<AbortSignal>,
+    ) -> AsyncTask<CodedTask<PeaksTask>> {
+        let cancel = CancelToken::from_signal(signal.as_ref());
+        CodedTask::spawn(
+            PeaksTask {
+                decoder: self.clone(),
+                options,
+                cancel,
+            },
+            signal,
+        )
+    }
+
+    /// Measure EBU R128 loudness and true peak over the whole input in one
+    /// streaming pass. Does not move the `read` position.
+    #[napi]
+    pub fn analyze_loudness(&self) -> Result<LoudnessStats> {
+        self.loudness_with(&CancelToken::default())
+    }
+
+    /// Measure loudness without blocking the event loop
+    #[napi(ts_return_type = "Promise<LoudnessStats>")]
+    pub fn analyze_loudness_async(
+        &self,
+        signal: Option<AbortSignal>,
+    ) -> AsyncTask<CodedTask<LoudnessTask>> {
+        let cancel = CancelToken::from_signal(signal.as_ref());
+        CodedTask::spawn(
+            LoudnessTask {
+                decoder: self.clone(),
+                cancel,
+            },
+            signal,
+        )
+    }
+
+    /// Compute an STFT magnitude matrix, optionally mel-scaled, in one streaming
+    /// pass over a mono downmix. Does not move the `read` position.
+    #[napi]
+    pub fn spectrogram(&self, options: Option<SpectrogramOptions>) -> Result<Spectrogram> {
+        self.spectrogram_with(&options.unwrap_or_default(), &CancelToken::default())
+    }
+
+    /// Compute a spectrogram without blocking the event loop
+    #[napi(ts_return_type = "Promise<Spectrogram>")]
+    pub fn spectrogram_async(
+        &self,
+        options: Option<SpectrogramOptions>,
+        signal: Option<AbortSignal>,
+    ) -> AsyncTask<CodedTask<SpectrogramTask>> {
+        let cancel = CancelToken::from_signal(signal.as_ref());
+        CodedTask::spawn(
+            SpectrogramTask {
+                decoder: self.clone(),
+                options: options.unwrap_or_default(),
+                cancel,
+            },
+            signal,
+        )
+    }
+
+    /// Estimate tempo, beat positions and downbeats from onset strength in one
+    /// streaming pass. Does not move the `read` position.
+    #[napi]
+    pub fn detect_tempo(&self, options: Option<TempoOptions>) -> Result<TempoInfo> {
+        self.tempo_with(&options.unwrap_or_default(), &CancelToken::default())
+    }
+
+    /// Detect tempo without blocking the event loop
+    #[napi(ts_return_type = "Promise<TempoInfo>")]
+    pub fn detect_tempo_async(
+        &self,
+        options: Option<TempoOptions>,
+        signal: Option<AbortSignal>,
+    ) -> AsyncTask<CodedTask<TempoTask>> {
+        let cancel = CancelToken::from_signal(signal.as_ref());
+        CodedTask::spawn(
+            TempoTask {
+                decoder: self.clone(),
+                options: options.unwrap_or_default(),
+                cancel,
+            },
+            signal,
+        )
+    }
+
+    /// Open and probe a file without blocking the event loop
+    #[napi(ts_return_type = "Promise<AudioDecoder>")]
+    pub fn from_file_async(
+        file_path: String,
+        signal: Option<AbortSignal>,
+    ) -> AsyncTask<CodedTask<OpenDecoderTask>> {
+        let cancel = CancelToken::from_signal(signal.as_ref());
+        CodedTask::spawn(OpenDecoderTask { file_path, cancel }, signal)
     }
 
     /// Check if this is a stereo file
@@ -181,11 +322,232 @@ impl AudioDecoder {
     }
 }
 
+impl AudioDecoder {
+    fn probed(source: &BoxedSource, data: Option<Arc<[u8]>>, file_path: Option<String>) -> Self {
+        Self {
+            data,
+            file_path,
+            sample_rate: source.sample_rate(),
+            channels: source.channels(),
+            duration: source.total_duration().map_or(0.0, |d| d.as_secs_f64()),
+            convert: false,
+            cursor: Mutex::new(None),
+        }
+    }
+
+    /// Convert the decoded audio to the given rate and channel count (unset keeps the file's)
+    pub(crate) fn with_output(mut self, sample_rate: Option<u32>, channels: Option<u16>) -> Self {
+        let sample_rate = sample_rate.unwrap_or(self.sample_rate);
+        let channels = channels.unwrap_or(self.channels);
+        if sample_rate != self.sample_rate || channels != self.channels {
+            self.sample_rate = sample_rate;
+            self.channels = channels;
+            self.convert = true;
+        }
+        self
+    }
+
+    /// Open a fresh decoder positioned at the start
+    fn open_source(&self) -> Result<BoxedSource> {
+        let source = match (&self.file_path, &self.data) {
+            (Some(file_path), _) => open_file(file_path)?,
+            (None, Some(data)) => open_data(data)?,
+            (None, None) => return Err(error(DecoderError::EmptyInput, "No audio data to decode")),
+        };
+        if self.convert {
+            Ok(Box::new(UniformSourceIterator::new(
+                source,
+                self.channels,
+                self.sample_rate,
+            )))
+        } else {
+            Ok(source)
+        }
+    }
+
+    /// Read up to `frames` frames from the streaming position
+    fn read_samples(&self, frames: u32) -> Result<Vec<i16>> {
+        let mut cursor = self.cursor.lock_or_recover();
+        let cursor = self.open_cursor(&mut cursor)?;
+        let samples = read_frames(&mut cursor.source, frames as u64, self.channels);
+        cursor.frame += (samples.len() / self.channels.max(1) as usize) as u64;
+        Ok(samples)
+    }
+
+    fn open_cursor<'a>(
+        &self,
+        cursor: &'a mut Option<DecoderCursor>,
+    ) -> Result<&'a mut DecoderCursor> {
+        let open = match cursor.take() {
+            Some(open) => open,
+            None => DecoderCursor {
+                source: self.open_source()?,
+                frame: 0,
+            },
+        };
+        Ok(cursor.insert(open))
+    }
+
+    /// Decode every sample, polling `cancel` between blocks
+    fn decode_with(&self, cancel: &CancelToken) -> Result<Vec<i16>> {
+        collect_samples(self.open_source()?, cancel)
+    }
+
+    /// Decode `[start_seconds, end_seconds)` on a decoder of its own
+    fn slice_with(
+        &self,
+        start_seconds: f64,
+        end_seconds: f64,
+        cancel: &CancelToken,
+    ) -> Result<Vec<i16>> {
+        let rate = self.sample_rate as f64;
+        let start_frame = (start_seconds.max(0.0) * rate) as u64;
+        let end_frame = (end_seconds.max(0.0) * rate) as u64;
+        if start_frame >= end_frame {
+            return Ok(Vec::new());
+        }
+
+        let mut source = self.open_source()?;
+        if start_frame > 0
+            && seek_source(&mut source, start_frame as f64 / rate).is_err()
+            && !skip_frames(&mut source, start_frame, self.channels, cancel)?
+        {
+            return Ok(Vec::new());
+        }
+
+        let wanted = ((end_frame - start_frame) as usize).saturating_mul(self.channels as usize);
+        collect_samples(source.take(wanted), cancel)
+    }
+
+    /// Waveform peaks on a decoder of its own
+    fn peaks_with(&self, options: &PeaksOptions, cancel: &CancelToken) -> Result<WaveformPeaks> {
+        let samples_per_bin =
+            waveform::bin_width(options, self.sample_rate, || self.count_frames(cancel))?;
+        waveform::compute_peaks(
+            self.open_source()?,
+            self.sample_rate,
+            self.channels,
+            samples_per_bin,
+            cancel,
+        )
+    }
+
+    /// Loudness measured on a decoder of its own
+    fn loudness_with(&self, cancel: &CancelToken) -> Result<LoudnessStats> {
+        loudness::measure(self.open_source()?, self.sample_rate, self.channels, cancel)
+    }
+
+    /// Spectrogram computed on a decoder of its own
+    fn spectrogram_with(
+        &self,
+        options: &SpectrogramOptions,
+        cancel: &CancelToken,
+    ) -> Result<Spectrogram> {
+        let plan = spectrogram::plan(options, self.sample_rate, || self.count_frames(cancel))?;
+        spectrogram::compute(
+            self.open_source()?,
+            self.channels,
+            self.sample_rate,
+            plan,
+            cancel,
+        )
+    }
+
+    /// Tempo detected on a decoder of its own
+    fn tempo_with(&self, options: &TempoOptions, cancel: &CancelToken) -> Result<TempoInfo> {
+        tempo::measure(
+            self.open_source()?,
+            self.sample_rate,
+            self.channels,
+            options,
+            cancel,
+        )
+    }
+
+    /// Frames in the input, decoding it when the headers do not declare a duration
+    fn count_frames(&self, cancel: &CancelToken) -> Result<u64> {
+        if self.duration > 0.0 {
+            return Ok((self.duration * self.sample_rate as f64).round() as u64);
+        }
+        let mut samples = 0u64;
+        for _ in self.open_source()? {
+            if samples.is_multiple_of(CANCEL_CHECK_INTERVAL as u64) {
+                cancel.check()?;
+            }
+            samples += 1;
+        }
+        Ok(samples / self.channels.max(1) as u64)
+    }
+}
+
+fn open_file(file_path: &str) -> Result<BoxedSource> {
+    let file = File::open(file_path).map_err(|e| open_failure(file_path, e))?;
+    let source = Decoder::new(BufReader::new(file))
+        .map_err(|e| decode_failure("Failed to decode audio", e))?;
+    Ok(Box::new(source))
+}
+
+fn open_data(data: &Arc<[u8]>) -> Result<BoxedSource> {
+    let source = Decoder::new(Cursor::new(data.clone()))
+        .map_err(|e| decode_failure("Failed to decode audio", e))?;
+    Ok(Box::new(source))
+}
+
+fn seek_source(source: &mut BoxedSource, seconds: f64) -> std::result::Result<(), SeekError> {
+    // Positions too large for a Duration seek to the end
+    source.try_seek(Duration::try_from_secs_f64(seconds).unwrap_or(Duration::MAX))
+}
+
+/// Pull up to `frames` whole frames from `source`
+/// Decode and discard `frames` frames without buffering them, checking `cancel`
+/// between blocks; returns false if the stream ended first
+fn skip_frames(
+    source: &mut BoxedSource,
+    frames: u64,
+    channels: u16,
+    cancel: &CancelToken,
+) -> Result<bool> {
+    let mut remaining = frames.saturating_mul(channels.max(1) as u64);
+    while remaining > 0 {
+        cancel.check()?;
+        let block = remaining.min(CANCEL_CHECK_INTERVAL as u64) as usize;
+        if source.by_ref().take(block).count() < block {
+            return Ok(false);
+        }
+        remaining -= block as u64;
+    }
+    Ok(true)
+}
+
+fn read_frames(source: &mut BoxedSource, frames: u64, channels: u16) -> Vec<i16> {
+    let wanted = (frames as usize).saturating_mul(channels.max(1) as usize);
+    let mut samples = Vec::with_capacity(wanted.min(1 << 20));
+    samples.extend(source.by_ref().take(wanted).map(|s| (s * 32767.0) as i16));
+    samples
+}
+
+/// Samples decoded between cancellation checks
+pub(crate) const CANCEL_CHECK_INTERVAL: usize = 1 << 16;
+
+fn collect_samples<I: Iterator<Item = f32>>(source: I, cancel: &CancelToken) -> Result<Vec<i16>> {
+    let mut samples = Vec::with_capacity(source.size_hint().0);
+    for (i, sample) in source.enumerate() {
+        if i % CANCEL_CHECK_INTERVAL == 0 {
+            cancel.check()?;
+        }
+        samples.push((sample * 32767.0) as i16);
+    }
+    cancel.check()?;
+    Ok(samples)
+}
+
 /// Looped decoder - decodes audio and repeats it indefinitely
 #[napi]
 pub struct LoopedDecoder {
     decoder: AudioDecoder,
     loop_count: u32,
+    // Passes finished by the streaming reader
+    loops_played: AtomicU32,
 }
 
 #[napi]
@@ -193,16 +555,10 @@ impl LoopedDecoder {
     /// Create a new looped decoder
     #[napi(constructor)]
     pub fn new(decoder: &AudioDecoder, loop_count: Option<u32>) -> Result<Self> {
-        let decoder_clone = AudioDecoder {
-            data: decoder.data.clone(),
-            file_path: decoder.file_path.clone(),
-            sample_rate: decoder.sample_rate,
-            channels: decoder.channels,
-            duration: decoder.duration,
-        };
         Ok(Self {
-            decoder: decoder_clone,
+            decoder: decoder.clone(),
             loop_count: loop_count.unwrap_or(u32::MAX),
+            loops_played: AtomicU32::new(0),
         })
     }
 
@@ -218,53 +574,269 @@ impl LoopedDecoder {
         self.loop_count = count;
     }
 
-    /// Decode with loops applied
+    /// Read up to `frames` frames, wrapping back to the start at the end of each pass so
+    /// that loop boundaries fall inside a chunk without a gap. Only one pass is decoded
+    /// at a time; an empty array means every loop has been played.
     #[napi]
-    pub fn decode_looped(&self) -> Result<Vec<i16>> {
-        let samples = self.decoder.decode_to_samples()?;
-        if samples.is_empty() || self.loop_count == 1 {
-            return Ok(samples);
+    pub fn read(&self, frames: u32) -> Result<Int16Array> {
+        let channels = self.decoder.channels.max(1) as usize;
+        let wanted = frames as usize * channels;
+        let mut samples = Vec::with_capacity(wanted.min(1 << 20));
+        if self.loop_count == 0 {
+            return Ok(Int16Array::new(samples));
         }
 
-        let loop_count = if self.loop_count == u32::MAX {
-            // For practical purposes, return 4 loops for safety
-            4
-        } else {
-            self.loop_count
-        };
+        while samples.len() < wanted {
+            let remaining = ((wanted - samples.len()) / channels) as u32;
+            let chunk = self.decoder.read_samples(remaining)?;
+            let exhausted = chunk.len() < remaining as usize * channels;
+            samples.extend(chunk);
+            if !exhausted {
+                break;
+            }
 
-        let mut result = Vec::with_capacity(samples.len() * loop_count as usize);
-        for _ in 0..loop_count {
-            result.extend_from_slice(&samples);
+            // End of a pass: stop after the last loop, or if the source produced nothing
+            let played = self.loops_played.load(Ordering::SeqCst).saturating_add(1);
+            let pass_empty = self.decoder.get_position() == 0.0;
+            if pass_empty || (self.loop_count != u32::MAX && played >= self.loop_count) {
+                self.loops_played
+                    .store(played.min(self.loop_count), Ordering::SeqCst);
+                break;
+            }
+            self.loops_played.store(played, Ordering::SeqCst);
+            self.decoder.seek(0.0)?;
         }
 
-        Ok(result)
+        Ok(Int16Array::new(samples))
+    }
+
+    /// Read the next chunk of looped frames, or null once every loop has been played
+    #[napi]
+    pub fn next_chunk(&self) -> Result<Option<Int16Array>> {
+        let chunk = self.read(DEFAULT_CHUNK_FRAMES)?;
+        Ok(if chunk.is_empty() { None } else { Some(chunk) })
+    }
+
+    /// Get the number of passes completed by `read`
+    #[napi]
+    pub fn get_loops_played(&self) -> u32 {
+        self.loops_played.load(Ordering::SeqCst)
+    }
+
+    /// Restart streaming from the first loop
+    #[napi]
+    pub fn reset(&mut self) -> Result<()> {
+        self.loops_played.store(0, Ordering::SeqCst);
+        self.decoder.reset()
+    }
+
+    /// Decode with loops applied
+    #[napi]
+    pub fn decode_looped(&self) -> Result<Int16Array> {
+        let cancel = CancelToken::default();
+        let samples = self.decoder.decode_with(&cancel)?;
+        repeat_samples(samples, self.loop_count, &cancel).map(Int16Array::new)
+    }
+
+    /// Decode with loops applied without blocking the event loop
+    #[napi(ts_return_type = "Promise<Int16Array>")]
+    pub fn decode_looped_async(
+        &self,
+        signal: Option<AbortSignal>,
+    ) -> AsyncTask<CodedTask<DecodeTask>> {
+        DecodeTask::spawn(
+            self.decoder.clone(),
+            DecodeJob::Looped(self.loop_count),
+            signal,
+        )
     }
 
     /// Get reference to inner decoder
     #[napi]
     pub fn get_decoder(&self) -> AudioDecoder {
         // Return a copy of the decoder
-        if let Some(path) = &self.decoder.file_path {
-            AudioDecoder::from_file(path.clone()).unwrap_or_else(|_| AudioDecoder {
-                data: Arc::new(Mutex::new(None)),
-                file_path: None,
-                sample_rate: 44100,
-                channels: 2,
-                duration: 0.0,
-            })
-        } else {
-            let data_guard = self.decoder.data.lock().unwrap();
-            let data = data_guard.as_ref().cloned().unwrap_or_default();
-            AudioDecoder::from_data(data).unwrap_or_else(|_| AudioDecoder {
-                data: Arc::new(Mutex::new(None)),
-                file_path: None,
-                sample_rate: 44100,
-                channels: 2,
-                duration: 0.0,
-            })
+        self.decoder.clone()
+    }
+}
+
+fn repeat_samples(samples: Vec<i16>, loop_count: u32, cancel: &CancelToken) -> Result<Vec<i16>> {
+    if samples.is_empty() || loop_count == 1 {
+        return Ok(samples);
+    }
+
+    let loop_count = if loop_count == u32::MAX {
+        // For practical purposes, return 4 loops for safety
+        4
+    } else {
+        loop_count
+    };
+
+    let mut result = Vec::with_capacity(samples.len() * loop_count as usize);
+    for _ in 0..loop_count {
+        cancel.check()?;
+        result.extend_from_slice(&samples);
+    }
+
+    Ok(result)
+}
+
+/// What a background decode produces from the decoded samples
+pub enum DecodeJob {
+    Full,
+    Slice {
+        start_seconds: f64,
+        end_seconds: f64,
+    },
+    Looped(u32),
+}
+
+/// Background decode of a whole file or buffer
+pub struct DecodeTask {
+    decoder: AudioDecoder,
+    job: DecodeJob,
+    cancel: CancelToken,
+}
+
+impl DecodeTask {
+    fn spawn(
+        decoder: AudioDecoder,
+        job: DecodeJob,
+        signal: Option<AbortSignal>,
+    ) -> AsyncTask<CodedTask<Self>> {
+        let cancel = CancelToken::from_signal(signal.as_ref());
+        CodedTask::spawn(
+            Self {
+                decoder,
+                job,
+                cancel,
+            },
+            signal,
+        )
+    }
+}
+
+impl AudioTask for DecodeTask {
+    type Output = Vec<i16>;
+    type JsValue = Int16Array;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        match self.job {
+            DecodeJob::Full => self.decoder.decode_with(&self.cancel),
+            DecodeJob::Slice {
+                start_seconds,
+                end_seconds,
+            } => self
+                .decoder
+                .slice_with(start_seconds, end_seconds, &self.cancel),
+            DecodeJob::Looped(loop_count) => {
+                let samples = self.decoder.decode_with(&self.cancel)?;
+                repeat_samples(samples, loop_count, &self.cancel)
+            }
         }
     }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+        Ok(Int16Array::new(output))
+    }
+}
+
+/// Background open of a decoder from a file
+pub struct OpenDecoderTask {
+    file_path: String,
+    cancel: CancelToken,
+}
+
+impl AudioTask for OpenDecoderTask {
+    type Output = AudioDecoder;
+    type JsValue = AudioDecoder;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        self.cancel.check()?;
+        AudioDecoder::from_file(self.file_path.clone())
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+        Ok(output)
+    }
+}
+
+/// Background waveform peak computation
+pub struct PeaksTask {
+    decoder: AudioDecoder,
+    options: PeaksOptions,
+    cancel: CancelToken,
+}
+
+impl AudioTask for PeaksTask {
+    type Output = WaveformPeaks;
+    type JsValue = WaveformPeaks;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        self.decoder.peaks_with(&self.options, &self.cancel)
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+        Ok(output)
+    }
+}
+
+/// Background loudness measurement
+pub struct LoudnessTask {
+    decoder: AudioDecoder,
+    cancel: CancelToken,
+}
+
+impl AudioTask for LoudnessTask {
+    type Output = LoudnessStats;
+    type JsValue = LoudnessStats;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        self.decoder.loudness_with(&self.cancel)
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+        Ok(output)
+    }
+}
+
+/// Background spectrogram computation
+pub struct SpectrogramTask {
+    decoder: AudioDecoder,
+    options: SpectrogramOptions,
+    cancel: CancelToken,
+}
+
+impl AudioTask for SpectrogramTask {
+    type Output = Spectrogram;
+    type JsValue = Spectrogram;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        self.decoder.spectrogram_with(&self.options, &self.cancel)
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+        Ok(output)
+    }
+}
+
+/// Background tempo detection
+pub struct TempoTask {
+    decoder: AudioDecoder,
+    options: TempoOptions,
+    cancel: CancelToken,
+}
+
+impl AudioTask for TempoTask {
+    type Output = TempoInfo;
+    type JsValue = TempoInfo;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        self.decoder.tempo_with(&self.options, &self.cancel)
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+        Ok(output)
+    }
 }
 
 /// Decoder builder for configuring decoder behavior
@@ -314,20 +886,58 @@ impl DecoderBuilder {
         self.channels = Some(channels);
     }
 
+    /// Build a decoder whose output is converted to the configured rate and channels
     #[napi]
     pub fn build_from_file(&self, file_path: String) -> Result<AudioDecoder> {
-        AudioDecoder::from_file(file_path)
+        self.validate()?;
+        Ok(AudioDecoder::from_file(file_path)?.with_output(self.sample_rate, self.channels))
     }
 
+    /// Build a decoder whose output is converted to the configured rate and channels
     #[napi]
     pub fn build_from_data(&self, data: Vec<u8>) -> Result<AudioDecoder> {
-        AudioDecoder::from_data(data)
+        self.validate()?;
+        Ok(AudioDecoder::from_data(data)?.with_output(self.sample_rate, self.channels))
     }
 
+    /// Build a looped decoder; plays once unless looping is enabled
     #[napi]
     pub fn build_looped(&self, file_path: String) -> Result<LoopedDecoder> {
-        let decoder = AudioDecoder::from_file(file_path)?;
-        LoopedDecoder::new(&decoder, Some(self.loop_count))
+        let decoder = self.build_from_file(file_path)?;
+        LoopedDecoder::new(&decoder, Some(self.looped_count()))
+    }
+
+    /// Build a looped decoder from raw audio data; plays once unless looping is enabled
+    #[napi]
+    pub fn build_looped_from_data(&self, data: Vec<u8>) -> Result<LoopedDecoder> {
+        let decoder = self.build_from_data(data)?;
+        LoopedDecoder::new(&decoder, Some(self.looped_count()))
+    }
+}
+
+impl DecoderBuilder {
+    fn validate(&self) -> Result<()> {
+        if self.sample_rate == Some(0) {
+            return Err(error(
+                ErrorCode::InvalidArgument,
+                "Sample rate must be greater than 0",
+            ));
+        }
+        if self.channels == Some(0) {
+            return Err(error(
+                ErrorCode::InvalidArgument,
+                "Channel count must be greater than 0",
+            ));
+        }
+        Ok(())
+    }
+
+    fn looped_count(&self) -> u32 {
+        if self.enable_looping {
+            self.loop_count
+        } else {
+            1
+        }
     }
 }
 
@@ -0,0 +1,286 @@
+//! Device capability queries through cpal
+
+use crate::engine::resolve_output_device;
+use crate::error::{caused_by, error, Result};
+use crate::types::{
+    AudioDeviceInfo, ChannelCount, DevicesError, SampleRate, SupportedStreamConfig,
+};
+use cpal::traits::{DeviceTrait, HostTrait};
+use napi_derive::napi;
+use rodio::cpal::traits::DeviceTrait as _;
+
+const DEVICE_ID_SEPARATOR: char = ':';
+
+/// Rates listed for each supported range by `supportedOutputConfigs`
+const COMMON_SAMPLE_RATES: [SampleRate; 5] = [44100, 48000, 88200, 96000, 192000];
+
+/// A range of stream configurations supported by a device
+#[napi(object)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeviceConfigRange {
+    pub channels: ChannelCount,
+    pub min_sample_rate: SampleRate,
+    pub max_sample_rate: SampleRate,
+    /// cpal sample format name, e.g. "i16" or "f32"
+    pub sample_format: String,
+    /// Smallest buffer in frames; unset when the platform does not report it
+    pub min_buffer_size: Option<u32>,
+    /// Largest buffer in frames; unset when the platform does not report it
+    pub max_buffer_size: Option<u32>,
+}
+
+/// Supported configurations of one device in one direction
+#[napi(object)]
+pub struct DeviceSupportedConfigs {
+    pub device: AudioDeviceInfo,
+    /// The configuration the device uses when none is requested
+    pub default_config: Option<DeviceConfigRange>,
+    pub configs: Vec<DeviceConfigRange>,
+}
+
+impl From<&cpal::SupportedStreamConfigRange> for DeviceConfigRange {
+    fn from(range: &cpal::SupportedStreamConfigRange) -> Self {
+        let (min_buffer_size, max_buffer_size) = buffer_size_range(range.buffer_size());
+        Self {
+            channels: range.channels(),
+            min_sample_rate: range.min_sample_rate(),
+            max_sample_rate: range.max_sample_rate(),
+            sample_format: range.sample_format().to_string(),
+            min_buffer_size,
+            max_buffer_size,
+        }
+    }
+}
+
+impl From<&cpal::SupportedStreamConfig> for DeviceConfigRange {
+    fn from(config: &cpal::SupportedStreamConfig) -> Self {
+        let (min_buffer_size, max_buffer_size) = buffer_size_range(config.buffer_size());
+        Self {
+            channels: config.channels(),
+            min_sample_rate: config.sample_rate(),
+            max_sample_rate: config.sample_rate(),
+            sample_format: config.sample_format().to_string(),
+            min_buffer_size,
+            max_buffer_size,
+        }
+    }
+}
+
+// Output devices are opened through rodio's cpal, so their ranges come from it too
+impl From<&rodio::cpal::SupportedStreamConfigRange> for DeviceConfigRange {
+    fn from(range: &rodio::cpal::SupportedStreamConfigRange) -> Self {
+        let (min_buffer_size, max_buffer_size) = match range.buffer_size() {
+            rodio::cpal::SupportedBufferSize::Range { min, max } => (Some(*min), Some(*max)),
+            rodio::cpal::SupportedBufferSize::Unknown => (None, None),
+        };
+        Self {
+            channels: range.channels(),
+            min_sample_rate: range.min_sample_rate().0,
+            max_sample_rate: range.max_sample_rate().0,
+            sample_format: range.sample_format().to_string(),
+            min_buffer_size,
+            max_buffer_size,
+        }
+    }
+}
+
+impl From<&rodio::cpal::SupportedStreamConfig> for DeviceConfigRange {
+    fn from(config: &rodio::cpal::SupportedStreamConfig) -> Self {
+        let (min_buffer_size, max_buffer_size) = match config.buffer_size() {
+            rodio::cpal::SupportedBufferSize::Range { min, max } => (Some(*min), Some(*max)),
+            rodio::cpal::SupportedBufferSize::Unknown => (None, None),
+        };
+        Self {
+            channels: config.channels(),
+            min_sample_rate: config.sample_rate().0,
+            max_sample_rate: config.sample_rate().0,
+            sample_format: config.sample_format().to_string(),
+            min_buffer_size,
+            max_buffer_size,
+        }
+    }
+}
+
+fn buffer_size_range(buffer_size: &cpal::SupportedBufferSize) -> (Option<u32>, Option<u32>) {
+    match buffer_size {
+        cpal::SupportedBufferSize::Range { min, max } => (Some(*min), Some(*max)),
+        cpal::SupportedBufferSize::Unknown => (None, None),
+    }
+}
+
+/// Get the supported output ranges of a device (`host:index` id, default device if omitted)
+#[napi]
+pub fn device_output_configs(device_id: Option<String>) -> Result<DeviceSupportedConfigs> {
+    let (info, device) = resolve_output_device(device_id.as_deref())?;
+    let configs = device.supported_output_configs().map_err(|e| {
+        caused_by(
+            DevicesError::DeviceUnavailable,
+            format!("Failed to query output configs of '{}': {}", info.name, e),
+            e,
+        )
+    })?;
+    Ok(DeviceSupportedConfigs {
+        default_config: device
+            .default_output_config()
+            .ok()
+            .as_ref()
+            .map(DeviceConfigRange::from),
+        configs: configs
+            .map(|range| DeviceConfigRange::from(&range))
+            .collect(),
+        device: info,
+    })
+}
+
+/// Get the supported input ranges of a device (`host:index` id, default device if omitted)
+#[napi]
+pub fn device_input_configs(device_id: Option<String>) -> Result<DeviceSupportedConfigs> {
+    let (host, index, device) = find_input_device(device_id.as_deref())?;
+    let name = device_name(&device);
+    let host_name = format!("{:?}", host.id());
+    let default_name = host.default_input_device().map(|d| device_name(&d));
+
+    let configs = device.supported_input_configs().map_err(|e| {
+        caused_by(
+            DevicesError::DeviceUnavailable,
+            format!("Failed to query input configs of '{}': {}", name, e),
+            e,
+        )
+    })?;
+
+    Ok(DeviceSupportedConfigs {
+        device: AudioDeviceInfo {
+            id: match index {
+                Some(index) => format!("{}{}{}", host_name, DEVICE_ID_SEPARATOR, index),
+                None => "default".to_string(),
+            },
+            is_default: default_name.as_deref() == Some(name.as_str()),
+            name,
+            host: host_name,
+        },
+        default_config: device
+            .default_input_config()
+            .ok()
+            .as_ref()
+            .map(DeviceConfigRange::from),
+        configs: configs
+            .map(|range| DeviceConfigRange::from(&range))
+            .collect(),
+    })
+}
+
+/// Flatten supported ranges into concrete configurations at common sample rates
+pub(crate) fn stream_configs(supported: &DeviceSupportedConfigs) -> Vec<SupportedStreamConfig> {
+    let mut result: Vec<SupportedStreamConfig> = Vec::new();
+    for range in &supported.configs {
+        // Format names are a type letter followed by the width, e.g. "i16" or "f32"
+        let sample_width = range.sample_format[1..].parse().unwrap_or(0);
+        for &sample_rate in &COMMON_SAMPLE_RATES {
+            let config = SupportedStreamConfig {
+                sample_rate,
+                channel_count: range.channels,
+                sample_width,
+            };
+            if (range.min_sample_rate..=range.max_sample_rate).contains(&sample_rate)
+                && !result.contains(&config)
+            {
+                result.push(config);
+            }
+        }
+    }
+    result
+}
+
+fn device_name(device: &cpal::Device) -> String {
+    device
+        .description()
+        .map(|desc| desc.name().to_string())
+        .unwrap_or_else(|_| "Unknown".to_string())
+}
+
+/// Resolve a `host:index` input device id, or the host's default input device when none is given
+fn find_input_device(device_id: Option<&str>) -> Result<(cpal::Host, Option<usize>, cpal::Device)> {
+    let Some(id) = device_id else {
+        let host = cpal::default_host();
+        let device = host.default_input_device().ok_or_else(|| {
+            error(
+                DevicesError::NoDevicesFound,
+                "No default input device available",
+            )
+        })?;
+        let name = device_name(&device);
+        let index = host
+            .input_devices()
+            .ok()
+            .and_then(|mut devices| devices.position(|d| device_name(&d) == name));
+        return Ok((host, index, device));
+    };
+
+    let (host, index) = match id.split_once(DEVICE_ID_SEPARATOR) {
+        Some((host_name, index)) => {
+            let host_id = cpal::available_hosts()
+                .into_iter()
+                .find(|h| format!("{:?}", h).eq_ignore_ascii_case(host_name))
+                .ok_or_else(|| {
+                    error(
+                        DevicesError::InvalidDevice,
+                        format!("Host '{}' not found", host_name),
+                    )
+                })?;
+            let host = cpal::host_from_id(host_id)
+                .map_err(|e| caused_by(DevicesError::DeviceUnavailable, e.to_string(), e))?;
+            (host, index)
+        }
+        None => (cpal::default_host(), id),
+    };
+
+    let index = index.parse::<usize>().map_err(|_| {
+        error(
+            DevicesError::InvalidDevice,
+            format!("Invalid device index: {}", id),
+        )
+    })?;
+
+    let device = host
+        .input_devices()
+        .map_err(|e| caused_by(DevicesError::DeviceUnavailable, e.to_string(), e))?
+        .nth(index)
+        .ok_or_else(|| {
+            error(
+                DevicesError::InvalidDevice,
+                format!("Device {} not found", id),
+            )
+        })?;
+    Ok((host, Some(index), device))
+}
+
+/// Describe the default host and devices with their default configurations
+pub(crate) fn describe_audio_system() -> String {
+    let host = cpal::default_host();
+    let mut lines = vec![format!("Audio system: rodio ({:?})", host.id())];
+
+    let output = host.default_output_device();
+    let output_config = output.as_ref().and_then(|d| d.default_output_config().ok());
+    let input = host.default_input_device();
+    let input_config = input.as_ref().and_then(|d| d.default_input_config().ok());
+
+    for (label, device, config) in [
+        ("Default output device", output, output_config),
+        ("Default input device", input, input_config),
+    ] {
+        match (device, config) {
+            (Some(device), Some(config)) => lines.push(format!(
+                "{}: {}\n  Channels: {}\n  Sample rate: {}\n  Sample format: {}",
+                label,
+                device_name(&device),
+                config.channels(),
+                config.sample_rate(),
+                config.sample_format()
+            )),
+            (Some(device), None) => lines.push(format!("{}: {}", label, device_name(&device))),
+            (None, _) => lines.push(format!("{}: none", label)),
+        }
+    }
+
+    lines.join("\n")
+}
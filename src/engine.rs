@@ -0,0 +1,599 @@
+//! Process-wide audio engine sharing one output stream per device
+
+use crate::error::{
+    caused_by, error, stream_failure, AudioError, ErrorCode, ErrorReporter, LockExt, Result,
+};
+use crate::types::{AudioDeviceInfo, DevicesError, OutputDevices, StreamOutputConfig};
+use napi_derive::napi;
+use rodio::cpal::traits::{DeviceTrait, HostTrait};
+use rodio::mixer::Mixer as OutputMixer;
+use rodio::source::{EmptyCallback, SeekError};
+use rodio::{cpal, OutputStream, OutputStreamBuilder, Sink, Source as RodioSource};
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc, Mutex, OnceLock, Weak};
+use std::task::{Context, Poll, Waker};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+type DeviceErrorListeners = Arc<Mutex<Vec<Weak<dyn DeviceErrorListener>>>>;
+
+const DEVICE_ID_SEPARATOR: char = ':';
+const DEFAULT_DEVICE_KEY: &str = "default";
+
+static OUTPUTS: OnceLock<Mutex<HashMap<String, EngineOutput>>> = OnceLock::new();
+static ACTIVE_VOICES: AtomicUsize = AtomicUsize::new(0);
+
+/// Notified of errors raised by the device behind a shared output; a lost
+/// device is reported with the `DeviceLost` code
+pub(crate) trait DeviceErrorListener: Send + Sync {
+    fn stream_error(&self, err: AudioError);
+}
+
+impl DeviceErrorListener for ErrorReporter {
+    fn stream_error(&self, err: AudioError) {
+        self.report(err);
+    }
+}
+
+/// An open device output. The `OutputStream` lives on its own thread (it is not
+/// `Send` on every platform); sinks connect through its mixer.
+struct EngineOutput {
+    mixer: OutputMixer,
+    config: StreamOutputConfig,
+    failed: Arc<AtomicBool>,
+    error_listeners: DeviceErrorListeners,
+    shutdown: mpsc::Sender<()>,
+    thread: Option<JoinHandle<()>>,
+    // Explicit ids that resolved to this output, e.g. "alsa:0" or "0"
+    aliases: Vec<String>,
+}
+
+impl EngineOutput {
+    fn open(device_id: Option<&str>) -> Result<Self> {
+        let failed = Arc::new(AtomicBool::new(false));
+        let error_listeners: DeviceErrorListeners = Arc::new(Mutex::new(Vec::new()));
+        let (ready_tx, ready_rx) = mpsc::channel();
+        let (shutdown, shutdown_rx) = mpsc::channel::<()>();
+
+        let device_id = device_id.map(str::to_string);
+        let on_error = {
+            let failed = failed.clone();
+            let listeners = error_listeners.clone();
+            move |error: cpal::StreamError| {
+                // Only a lost device ends the output; other errors (e.g. ALSA xruns) are transient
+                let lost = matches!(error, cpal::StreamError::DeviceNotAvailable);
+                if lost {
+                    failed.store(true, Ordering::SeqCst);
+                }
+                for listener in listeners.lock_or_recover().iter() {
+                    if let Some(listener) = listener.upgrade() {
+                        listener.stream_error(stream_failure(lost, &error));
+                    }
+                }
+            }
+        };
+
+        let thread = thread::Builder::new()
+            .name("audio-engine-output".to_string())
+            .spawn(move || {
+                let opened = open_output_stream(device_id.as_deref(), on_error);
+                match opened {
+                    Ok(stream) => {
+                        let config = output_config(&stream);
+                        if ready_tx.send(Ok((stream.mixer().clone(), config))).is_ok() {
+                            // Keep the stream alive until shutdown or the engine is dropped
+                            let _ = shutdown_rx.recv();
+                        }
+                    }
+                    Err(e) => {
+                        let _ = ready_tx.send(Err(e));
+                    }
+                }
+            })
+            .map_err(|e| {
+                error(
+                    ErrorCode::Internal,
+                    format!("Failed to start audio output thread: {}", e),
+                )
+            })?;
+
+        let (mixer, config) = ready_rx
+            .recv()
+            .map_err(|_| error(ErrorCode::Internal, "Audio output thread exited early"))??;
+
+        Ok(Self {
+            mixer,
+            config,
+            failed,
+            error_listeners,
+            shutdown,
+            thread: Some(thread),
+            aliases: Vec::new(),
+        })
+    }
+
+    fn close(mut self) {
+        let _ = self.shutdown.send(());
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn open_output_stream<E>(device_id: Option<&str>, on_error: E) -> Result<OutputStream>
+where
+    E: FnMut(cpal::StreamError) + Clone + Send + 'static,
+{
+    let opened = match device_id {
+        Some(id) => OutputStreamBuilder::from_device(find_output_device(Some(id))?)
+            .map(|builder| builder.with_error_callback(on_error))
+            .and_then(|builder| builder.open_stream_or_fallback()),
+        None => OutputStreamBuilder::from_default_device()
+            .and_then(|builder| {
+                builder
+                    .with_error_callback(on_error)
+                    .open_stream_or_fallback()
+            })
+            .or_else(|_| OutputStreamBuilder::open_default_stream()),
+    };
+
+    opened.map_err(|e| {
+        caused_by(
+            DevicesError::DeviceUnavailable,
+            format!("Failed to create output stream: {}", e),
+            e,
+        )
+    })
+}
+
+fn outputs() -> &'static Mutex<HashMap<String, EngineOutput>> {
+    OUTPUTS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Open (or reuse) the output of a device and return its key, mixer and configuration.
+/// Outputs are keyed by the resolved `host:index` id, so "default" and the explicit
+/// id of the same device share one stream.
+fn ensure_output(device_id: Option<&str>) -> Result<(String, OutputMixer, StreamOutputConfig)> {
+    let mut outputs = outputs().lock_or_recover();
+    // Explicit ids seen before skip the device enumeration; the default device is
+    // resolved every time so a changed system default is followed
+    let cached = device_id.and_then(|id| {
+        outputs
+            .iter()
+            .find(|(key, output)| *key == id || output.aliases.iter().any(|a| a == id))
+            .map(|(key, _)| key.clone())
+    });
+    let mut key = match cached {
+        Some(key) => key,
+        None => resolve_output_device(device_id)?.0.id,
+    };
+
+    // A device that disappeared is reopened on next use, under the id that resolves now
+    if outputs
+        .get(&key)
+        .is_some_and(|output| output.failed.load(Ordering::SeqCst))
+    {
+        if let Some(output) = outputs.remove(&key) {
+            output.close();
+        }
+        key = resolve_output_device(device_id)?.0.id;
+    }
+
+    let output = match outputs.entry(key.clone()) {
+        Entry::Occupied(entry) => entry.into_mut(),
+        Entry::Vacant(entry) => {
+            // Open by the resolved id so the stream matches its key
+            let resolved = Some(key.as_str()).filter(|key| *key != DEFAULT_DEVICE_KEY);
+            entry.insert(EngineOutput::open(resolved)?)
+        }
+    };
+    if let Some(id) = device_id.filter(|id| *id != key) {
+        if !output.aliases.iter().any(|a| a == id) {
+            output.aliases.push(id.to_string());
+        }
+    }
+    Ok((key, output.mixer.clone(), output.config.clone()))
+}
+
+/// Create a sink on the shared output of a device (the default device if `None`).
+/// `listener` is notified if the device is lost while the output is open.
+pub(crate) fn connect_sink(
+    device_id: Option<&str>,
+    listener: Option<Weak<dyn DeviceErrorListener>>,
+) -> Result<(Sink, StreamOutputConfig)> {
+    let (key, mixer, config) = ensure_output(device_id)?;
+    if let Some(listener) = listener {
+        if let Some(output) = outputs().lock_or_recover().get(&key) {
+            let mut listeners = output.error_listeners.lock_or_recover();
+            listeners.retain(|l| l.strong_count() > 0);
+            listeners.push(listener);
+        }
+    }
+    Ok((Sink::connect_new(&mixer), config))
+}
+
+/// Wrap a source so it counts as an active voice until it finishes or is dropped
+pub(crate) fn voice<S>(source: S) -> Voice<S>
+where
+    S: RodioSource<Item = f32>,
+{
+    ACTIVE_VOICES.fetch_add(1, Ordering::SeqCst);
+    Voice { source }
+}
+
+/// Source wrapper counted by `AudioEngine.getActiveVoices`
+pub(crate) struct Voice<S> {
+    source: S,
+}
+
+impl<S> Drop for Voice<S> {
+    fn drop(&mut self) {
+        ACTIVE_VOICES.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl<S> Iterator for Voice<S>
+where
+    S: RodioSource<Item = f32>,
+{
+    type Item = f32;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.source.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.source.size_hint()
+    }
+}
+
+impl<S> RodioSource for Voice<S>
+where
+    S: RodioSource<Item = f32>,
+{
+    fn current_span_len(&self) -> Option<usize> {
+        self.source.current_span_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.source.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.source.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.source.total_duration()
+    }
+
+    fn try_seek(&mut self, pos: Duration) -> std::result::Result<(), SeekError> {
+        self.source.try_seek(pos)
+    }
+}
+
+/// Completion of a voice played through a `PlaybackHandle`
+#[derive(Default)]
+struct PlaybackEnd {
+    finished: bool,
+    wakers: Vec<Waker>,
+}
+
+#[derive(Default)]
+pub(crate) struct PlaybackShared {
+    end: Mutex<PlaybackEnd>,
+}
+
+impl PlaybackShared {
+    pub(crate) fn finish(&self) {
+        let wakers = {
+            let mut end = self.end.lock_or_recover();
+            end.finished = true;
+            std::mem::take(&mut end.wakers)
+        };
+        wakers.into_iter().for_each(Waker::wake);
+    }
+
+    pub(crate) fn is_finished(&self) -> bool {
+        self.end.lock_or_recover().finished
+    }
+}
+
+/// Resolves once the voice has finished or was stopped
+pub(crate) struct PlaybackEnded(pub(crate) Arc<PlaybackShared>);
+
+impl Future for PlaybackEnded {
+    type Output = napi::Result<()>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut end = self.0.end.lock_or_recover();
+        if end.finished {
+            Poll::Ready(Ok(()))
+        } else {
+            end.wakers.push(cx.waker().clone());
+            Poll::Pending
+        }
+    }
+}
+
+/// Play a source on its own sink of the shared default output without blocking
+pub(crate) fn play_voice<S>(source: S) -> Result<PlaybackHandle>
+where
+    S: RodioSource<Item = f32> + Send + 'static,
+{
+    let (sink, _) = connect_sink(None, None)?;
+    let shared = Arc::new(PlaybackShared::default());
+
+    sink.append(voice(source));
+    let ended = shared.clone();
+    sink.append(EmptyCallback::new(Box::new(move || ended.finish())));
+    sink.play();
+
+    Ok(PlaybackHandle {
+        sink: Mutex::new(Some(sink)),
+        shared,
+    })
+}
+
+/// Handle to a sound started with `SamplesBuffer.play` or `testTone`.
+/// Playback continues in the background for as long as the handle is alive.
+#[napi]
+pub struct PlaybackHandle {
+    sink: Mutex<Option<Sink>>,
+    shared: Arc<PlaybackShared>,
+}
+
+#[napi]
+impl PlaybackHandle {
+    /// Stop playback; the ended promise resolves
+    #[napi]
+    pub fn stop(&self) {
+        if let Some(sink) = self.sink.lock_or_recover().take() {
+            sink.stop();
+        }
+        self.shared.finish();
+    }
+
+    #[napi]
+    pub fn pause(&self) {
+        if let Some(sink) = self.sink.lock_or_recover().as_ref() {
+            sink.pause();
+        }
+    }
+
+    #[napi]
+    pub fn resume(&self) {
+        if let Some(sink) = self.sink.lock_or_recover().as_ref() {
+            sink.play();
+        }
+    }
+
+    #[napi]
+    pub fn is_paused(&self) -> bool {
+        self.sink
+            .lock_or_recover()
+            .as_ref()
+            .is_some_and(|sink| sink.is_paused())
+    }
+
+    /// Set the volume (0.0 to 1.0)
+    #[napi]
+    pub fn set_volume(&self, volume: f64) -> Result<()> {
+        if !(0.0..=1.0).contains(&volume) {
+            return Err(error(
+                ErrorCode::InvalidArgument,
+                "Volume must be between 0.0 and 1.0",
+            ));
+        }
+        if let Some(sink) = self.sink.lock_or_recover().as_ref() {
+            sink.set_volume(volume as f32);
+        }
+        Ok(())
+    }
+
+    #[napi]
+    pub fn get_volume(&self) -> f64 {
+        self.sink
+            .lock_or_recover()
+            .as_ref()
+            .map_or(0.0, |sink| sink.volume() as f64)
+    }
+
+    /// Check if playback has finished or was stopped
+    #[napi]
+    pub fn is_finished(&self) -> bool {
+        self.shared.is_finished()
+    }
+
+    /// Resolves when playback has finished or was stopped
+    #[napi]
+    pub async fn ended(&self) -> napi::Result<()> {
+        PlaybackEnded(self.shared.clone()).await
+    }
+}
+
+/// Describe the configuration an output stream was opened with
+pub(crate) fn output_config(stream: &OutputStream) -> StreamOutputConfig {
+    let config = stream.config();
+    StreamOutputConfig {
+        sample_rate: Some(config.sample_rate()),
+        channels: Some(config.channel_count()),
+        buffer_size: match config.buffer_size() {
+            cpal::BufferSize::Fixed(frames) => Some(*frames),
+            cpal::BufferSize::Default => None,
+        },
+    }
+}
+
+/// Resolve a `host:index` device id, or the default output device when none is given
+pub(crate) fn find_output_device(device_id: Option<&str>) -> Result<cpal::Device> {
+    resolve_output_device(device_id).map(|(_, device)| device)
+}
+
+/// Resolve a device id like `find_output_device`, along with its description.
+/// The default device gets its `host:index` id when it can be found in the list.
+pub(crate) fn resolve_output_device(
+    device_id: Option<&str>,
+) -> Result<(AudioDeviceInfo, cpal::Device)> {
+    let Some(id) = device_id else {
+        let host = cpal::default_host();
+        let device = host
+            .default_output_device()
+            .ok_or_else(|| error(DevicesError::NoDevicesFound, "No default output device"))?;
+        let name = device_name(&device);
+        let index = host
+            .output_devices()
+            .ok()
+            .and_then(|mut devices| devices.position(|d| device_name(&d) == name));
+        let host_name = format!("{:?}", host.id());
+        let info = AudioDeviceInfo {
+            id: match index {
+                Some(index) => format!("{}{}{}", host_name, DEVICE_ID_SEPARATOR, index),
+                None => DEFAULT_DEVICE_KEY.to_string(),
+            },
+            name,
+            host: host_name,
+            is_default: true,
+        };
+        return Ok((info, device));
+    };
+
+    let (host, index) = match id.split_once(DEVICE_ID_SEPARATOR) {
+        Some((host_name, index)) => {
+            let host_id = cpal::available_hosts()
+                .into_iter()
+                .find(|h| format!("{:?}", h).eq_ignore_ascii_case(host_name))
+                .ok_or_else(|| {
+                    error(
+                        DevicesError::InvalidDevice,
+                        format!("Host '{}' not found", host_name),
+                    )
+                })?;
+            let host = cpal::host_from_id(host_id)
+                .map_err(|e| caused_by(DevicesError::DeviceUnavailable, e.to_string(), e))?;
+            (host, index)
+        }
+        None => (cpal::default_host(), id),
+    };
+
+    let index = index.parse::<usize>().map_err(|_| {
+        error(
+            DevicesError::InvalidDevice,
+            format!("Invalid device index: {}", id),
+        )
+    })?;
+
+    let device = host
+        .output_devices()
+        .map_err(|e| caused_by(DevicesError::DeviceUnavailable, e.to_string(), e))?
+        .nth(index)
+        .ok_or_else(|| {
+            error(
+                DevicesError::InvalidDevice,
+                format!("Output device {} not found", id),
+            )
+        })?;
+    let name = device_name(&device);
+    let host_name = format!("{:?}", host.id());
+    let info = AudioDeviceInfo {
+        id: format!("{}{}{}", host_name, DEVICE_ID_SEPARATOR, index),
+        is_default: host.default_output_device().map(|d| device_name(&d)) == Some(name.clone()),
+        name,
+        host: host_name,
+    };
+    Ok((info, device))
+}
+
+fn device_name(device: &cpal::Device) -> String {
+    device.name().unwrap_or_else(|_| "Unknown".to_string())
+}
+
+/// List the output devices of every host with the `host:index` ids that
+/// `find_output_device` resolves. Enumerated through rodio's cpal so the indices
+/// match the devices the engine opens.
+pub(crate) fn output_devices() -> OutputDevices {
+    let mut result = Vec::new();
+    for host_id in cpal::available_hosts() {
+        let Ok(host) = cpal::host_from_id(host_id) else {
+            continue;
+        };
+        let Ok(devices) = host.output_devices() else {
+            continue;
+        };
+        let host_name = format!("{:?}", host_id);
+        let default_name = host.default_output_device().and_then(|d| d.name().ok());
+        for (index, device) in devices.enumerate() {
+            let Ok(name) = device.name() else {
+                continue;
+            };
+            result.push(AudioDeviceInfo {
+                id: format!("{}{}{}", host_name, DEVICE_ID_SEPARATOR, index),
+                is_default: default_name.as_deref() == Some(name.as_str()),
+                name,
+                host: host_name.clone(),
+            });
+        }
+    }
+    result
+}
+
+/// Process-wide audio engine. Players, streams, buffers and the mixer share one
+/// output stream per device instead of opening their own; outputs are opened on
+/// first use, or up front with `init`.
+#[napi]
+pub struct AudioEngine {}
+
+#[napi]
+impl AudioEngine {
+    /// Open the output of a device (`host:index` id, default device if omitted)
+    /// ahead of time and return its configuration
+    #[napi]
+    pub fn init(device_id: Option<String>) -> Result<StreamOutputConfig> {
+        ensure_output(device_id.as_deref()).map(|(_, _, config)| config)
+    }
+
+    /// List the output devices whose ids `init` and the players accept
+    #[napi]
+    pub fn get_output_devices() -> OutputDevices {
+        output_devices()
+    }
+
+    /// Close every device output. Playing voices go silent; new playback
+    /// reopens the outputs it needs.
+    #[napi]
+    pub fn shutdown() {
+        let closed: Vec<EngineOutput> = outputs()
+            .lock_or_recover()
+            .drain()
+            .map(|(_, output)| output)
+            .collect();
+        for output in closed {
+            output.close();
+        }
+    }
+
+    /// Check if any device output is open
+    #[napi]
+    pub fn is_initialized() -> bool {
+        !outputs().lock_or_recover().is_empty()
+    }
+
+    /// Get the `host:index` ids of the devices with an open output; the default device
+    /// is listed under its own id ("default" only when its host does not list it)
+    #[napi]
+    pub fn get_open_devices() -> Vec<String> {
+        let mut devices: Vec<String> = outputs().lock_or_recover().keys().cloned().collect();
+        devices.sort();
+        devices
+    }
+
+    /// Get the number of sources currently playing through the engine
+    #[napi]
+    pub fn get_active_voices() -> u32 {
+        ACTIVE_VOICES.load(Ordering::SeqCst) as u32
+    }
+}
@@ -0,0 +1,235 @@
+//! Crate-wide error type - every failure carries a stable `code` for JS
+
+use crate::types::{DecoderError, DevicesError, PlayError, SeekError, StreamError};
+use napi::bindgen_prelude::{
+    AbortSignal, AsyncTask, FromNapiValue, ToNapiValue, TypeName, Unknown,
+};
+use napi::threadsafe_function::{ThreadsafeFunction, ThreadsafeFunctionCallMode};
+use napi::{Env, JsError, Status, Task};
+use std::fmt::Display;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+/// Stable error code, surfaced to JS as the `code` property of thrown errors.
+/// Codes are the variant names of the error enums in `types`, plus a few generic ones.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ErrorCode {
+    Decoder(DecoderError),
+    Seek(SeekError),
+    Stream(StreamError),
+    Play(PlayError),
+    Devices(DevicesError),
+    /// An argument failed validation
+    InvalidArgument,
+    /// The operation was aborted through its AbortSignal
+    Cancelled,
+    /// An unexpected failure inside the runtime
+    Internal,
+}
+
+impl AsRef<str> for ErrorCode {
+    fn as_ref(&self) -> &str {
+        match self {
+            ErrorCode::Decoder(code) => code.as_ref(),
+            ErrorCode::Seek(code) => code.as_ref(),
+            ErrorCode::Stream(code) => code.as_ref(),
+            ErrorCode::Play(code) => code.as_ref(),
+            ErrorCode::Devices(code) => code.as_ref(),
+            ErrorCode::InvalidArgument => "InvalidArgument",
+            ErrorCode::Cancelled => "Cancelled",
+            ErrorCode::Internal => "Internal",
+        }
+    }
+}
+
+impl From<Status> for ErrorCode {
+    fn from(status: Status) -> Self {
+        match status {
+            Status::InvalidArg => ErrorCode::InvalidArgument,
+            Status::Cancelled => ErrorCode::Cancelled,
+            _ => ErrorCode::Internal,
+        }
+    }
+}
+
+macro_rules! impl_from_code {
+    ($($variant:ident($ty:ty)),*) => {
+        $(impl From<$ty> for ErrorCode {
+            fn from(code: $ty) -> Self {
+                ErrorCode::$variant(code)
+            }
+        })*
+    };
+}
+
+impl_from_code!(
+    Decoder(DecoderError),
+    Seek(SeekError),
+    Stream(StreamError),
+    Play(PlayError),
+    Devices(DevicesError)
+);
+
+/// Error with a code, a message and an optional underlying cause
+pub type AudioError = napi::Error<ErrorCode>;
+
+pub type Result<T> = std::result::Result<T, AudioError>;
+
+/// Create an error with the given code and message
+pub fn error(code: impl Into<ErrorCode>, message: impl ToString) -> AudioError {
+    AudioError::new(code.into(), message)
+}
+
+/// Create an error that records the underlying failure as its `cause`
+pub fn caused_by(
+    code: impl Into<ErrorCode>,
+    message: impl ToString,
+    cause: impl Display,
+) -> AudioError {
+    let mut err = error(code, message);
+    err.set_cause(napi::Error::from_reason(cause.to_string()));
+    err
+}
+
+/// A background task whose failures keep their error code when the promise rejects
+pub trait AudioTask: Send + Sized {
+    type Output: Send + Sized + 'static;
+    type JsValue: ToNapiValue + TypeName;
+
+    /// Runs on the libuv thread pool
+    fn compute(&mut self) -> Result<Self::Output>;
+
+    /// Runs on the JS thread once `compute` succeeded
+    fn resolve(&mut self, env: Env, output: Self::Output) -> Result<Self::JsValue>;
+}
+
+/// Adapts an `AudioTask` to napi's `Task`, whose errors can only carry a `Status`
+pub struct CodedTask<T> {
+    task: T,
+    error: Option<AudioError>,
+}
+
+impl<T: AudioTask> CodedTask<T> {
+    /// Queue `task` on the thread pool, cancelled when `signal` aborts
+    pub fn spawn(task: T, signal: Option<AbortSignal>) -> AsyncTask<Self> {
+        AsyncTask::with_optional_signal(Self { task, error: None }, signal)
+    }
+}
+
+impl<T: AudioTask> Task for CodedTask<T> {
+    type Output = T::Output;
+    type JsValue = T::JsValue;
+
+    fn compute(&mut self) -> napi::Result<Self::Output> {
+        self.task.compute().map_err(|err| {
+            let status = napi::Error::new(Status::GenericFailure, err.reason.clone());
+            self.error = Some(err);
+            status
+        })
+    }
+
+    fn resolve(&mut self, env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+        self.task
+            .resolve(env, output)
+            .map_err(|err| self.reject_coded(&env, err))
+    }
+
+    fn reject(&mut self, env: Env, err: napi::Error) -> napi::Result<Self::JsValue> {
+        match self.error.take() {
+            Some(coded) => Err(self.reject_coded(&env, coded)),
+            None => Err(err),
+        }
+    }
+}
+
+impl<T> CodedTask<T> {
+    /// Build the JS error with its code, wrapped so napi rejects with it unchanged
+    fn reject_coded(&self, env: &Env, err: AudioError) -> napi::Error {
+        let value = unsafe { JsError::from(err).into_value(env.raw()) };
+        match unsafe { Unknown::from_napi_value(env.raw(), value) } {
+            Ok(value) => napi::Error::from(value),
+            Err(err) => err,
+        }
+    }
+}
+
+/// Wrap an I/O failure, coding a missing file as `FileNotFound`
+pub(crate) fn io_failure(message: impl ToString, err: std::io::Error) -> AudioError {
+    let code = match err.kind() {
+        std::io::ErrorKind::NotFound => DecoderError::FileNotFound,
+        _ => DecoderError::IoError,
+    };
+    caused_by(code, message, err)
+}
+
+/// Wrap a failure to open `path`
+pub(crate) fn open_failure(path: &str, err: std::io::Error) -> AudioError {
+    match err.kind() {
+        std::io::ErrorKind::NotFound => io_failure(format!("File not found: {}", path), err),
+        _ => io_failure(format!("Failed to open file: {}", err), err),
+    }
+}
+
+/// Wrap a rodio decoder failure, prefixing its message with `context`
+pub(crate) fn decode_failure(context: &str, err: rodio::decoder::DecoderError) -> AudioError {
+    use rodio::decoder::DecoderError as Rodio;
+
+    let code = match &err {
+        Rodio::UnrecognizedFormat => DecoderError::InvalidFormat,
+        Rodio::IoError(..) => DecoderError::IoError,
+        Rodio::NoStreams => DecoderError::NoAudioTrack,
+        _ => DecoderError::CorruptedData,
+    };
+    caused_by(code, format!("{}: {}", context, err), err)
+}
+
+/// Wrap an error reported by an audio device stream. Only a lost device is
+/// fatal; anything else (e.g. an ALSA xrun) is reported as `Internal`.
+pub(crate) fn stream_failure(device_lost: bool, err: impl Display) -> AudioError {
+    if device_lost {
+        caused_by(
+            DevicesError::DeviceLost,
+            "Audio device is no longer available",
+            err,
+        )
+    } else {
+        caused_by(
+            ErrorCode::Internal,
+            format!("Audio stream error: {}", err),
+            err,
+        )
+    }
+}
+
+/// Locking that recovers the data of a poisoned mutex instead of panicking
+pub(crate) trait LockExt<T> {
+    fn lock_or_recover(&self) -> MutexGuard<'_, T>;
+}
+
+impl<T> LockExt<T> for Mutex<T> {
+    fn lock_or_recover(&self) -> MutexGuard<'_, T> {
+        self.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// JS callback receiving errors raised on audio threads
+pub type ErrorCallback = ThreadsafeFunction<(), Unknown<'static>, (), ErrorCode>;
+
+/// Delivers audio-thread failures to an `onError` callback; they are logged
+/// to stderr until one is set
+#[derive(Clone, Default)]
+pub(crate) struct ErrorReporter(Arc<Mutex<Option<ErrorCallback>>>);
+
+impl ErrorReporter {
+    pub(crate) fn set(&self, callback: ErrorCallback) {
+        *self.0.lock_or_recover() = Some(callback);
+    }
+
+    pub(crate) fn report(&self, err: AudioError) {
+        match self.0.lock_or_recover().as_ref() {
+            Some(callback) => {
+                callback.call(Err(err), ThreadsafeFunctionCallMode::NonBlocking);
+            }
+            None => eprintln!("[miniaudio-node] {}: {}", err.status.as_ref(), err.reason),
+        }
+    }
+}
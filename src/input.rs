@@ -1,8 +1,14 @@
+use crate::analyser::{AnalyserSlot, AudioAnalyser};
 use crate::buffer::SamplesBuffer;
-use crate::types::AudioDeviceInfo;
+use crate::error::{
+    caused_by, error, stream_failure, ErrorCallback, ErrorReporter, LockExt, Result,
+};
+use crate::loudness::{LoudnessMeter, LoudnessStats};
+use crate::tempo::{TempoInfo, TempoOptions, TempoTracker, LIVE_WINDOW_SECONDS};
+use crate::types::{AudioDeviceInfo, DevicesError, PlayError, StreamError};
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use napi::bindgen_prelude::Int16Array;
 use napi::threadsafe_function::{ThreadsafeFunction, ThreadsafeFunctionCallMode};
-use napi::{Error, Result, Status};
 use napi_derive::napi;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
@@ -79,18 +85,18 @@ pub fn get_input_devices_by_host(host_name: String) -> Result<Vec<AudioDeviceInf
         .into_iter()
         .find(|h| format!("{:?}", h) == host_name)
         .ok_or_else(|| {
-            Error::new(
-                Status::InvalidArg,
+            error(
+                DevicesError::InvalidDevice,
                 format!("Host '{}' not found", host_name),
             )
         })?;
 
     let host = cpal::host_from_id(host_id)
-        .map_err(|e| Error::new(Status::GenericFailure, e.to_string()))?;
+        .map_err(|e| caused_by(DevicesError::DeviceUnavailable, e.to_string(), e))?;
 
     let devices = host
         .input_devices()
-        .map_err(|e| Error::new(Status::GenericFailure, e.to_string()))?;
+        .map_err(|e| caused_by(DevicesError::DeviceUnavailable, e.to_string(), e))?;
 
     let default_name = host
         .default_input_device()
@@ -162,6 +168,10 @@ pub struct AudioRecorder {
     channels: u16,
     last_peak: Arc<Mutex<f64>>,
     last_rms: Arc<Mutex<f64>>,
+    loudness: Arc<Mutex<LoudnessMeter>>,
+    tempo: Arc<Mutex<TempoTracker>>,
+    analyser: AnalyserSlot,
+    errors: ErrorReporter,
 }
 
 impl Default for AudioRecorder {
@@ -190,19 +200,38 @@ impl AudioRecorder {
             channels: DEFAULT_CHANNELS,
             last_peak: Arc::new(Mutex::new(0.0)),
             last_rms: Arc::new(Mutex::new(0.0)),
+            loudness: Arc::new(Mutex::new(LoudnessMeter::new(
+                DEFAULT_SAMPLE_RATE,
+                DEFAULT_CHANNELS,
+            ))),
+            tempo: Arc::new(Mutex::new(TempoTracker::windowed(
+                DEFAULT_SAMPLE_RATE,
+                DEFAULT_CHANNELS,
+                LIVE_WINDOW_SECONDS,
+            ))),
+            analyser: Arc::new(Mutex::new(None)),
+            errors: ErrorReporter::default(),
         }
     }
 
     #[napi]
-    pub fn set_on_data(&self, callback: ThreadsafeFunction<Vec<i16>>) -> Result<()> {
+    pub fn set_on_data(&self, callback: ThreadsafeFunction<Int16Array>) -> Result<()> {
         let cb = Box::new(move |data: Vec<i16>| {
             callback.call(
-                Ok::<_, Error>(data),
+                Ok::<_, napi::Error>(Int16Array::new(data)),
                 ThreadsafeFunctionCallMode::NonBlocking,
             );
         });
 
-        *self.on_data_callback.lock().unwrap() = Some(cb);
+        *self.on_data_callback.lock_or_recover() = Some(cb);
+        Ok(())
+    }
+
+    /// Called with errors raised on the audio thread. Recording stops when
+    /// the error is `DeviceLost`.
+    #[napi(ts_args_type = "callback: (err: Error) => void")]
+    pub fn set_on_error(&self, callback: ErrorCallback) -> Result<()> {
+        self.errors.set(callback);
         Ok(())
     }
 
@@ -210,14 +239,14 @@ impl AudioRecorder {
     pub fn set_ring_buffer_size(&self, size_samples: u32) {
         use ringbuf::HeapRb;
         let rb = HeapRb::<i16>::new(size_samples as usize);
-        *self.ring_buffer.lock().unwrap() = Some(rb);
+        *self.ring_buffer.lock_or_recover() = Some(rb);
     }
 
     #[napi]
     pub fn start(&mut self, device_id: Option<String>) -> Result<()> {
         if self.is_recording.load(Ordering::SeqCst) {
-            return Err(Error::new(
-                Status::GenericFailure,
+            return Err(error(
+                PlayError::AlreadyPlaying,
                 "Recording is already in progress",
             ));
         }
@@ -228,8 +257,8 @@ impl AudioRecorder {
                 let parts: Vec<&str> = id.split(DEVICE_ID_SEPARATOR).collect();
                 let host_name = parts[0];
                 let device_idx = parts[1].parse::<usize>().map_err(|_| {
-                    Error::new(
-                        Status::InvalidArg,
+                    error(
+                        DevicesError::InvalidDevice,
                         format!("Invalid device index in ID: {}", id),
                     )
                 })?;
@@ -238,21 +267,21 @@ impl AudioRecorder {
                     .into_iter()
                     .find(|h| format!("{:?}", h) == host_name)
                     .ok_or_else(|| {
-                        Error::new(
-                            Status::InvalidArg,
+                        error(
+                            DevicesError::InvalidDevice,
                             format!("Host '{}' not found", host_name),
                         )
                     })?;
 
                 let host = cpal::host_from_id(host_id)
-                    .map_err(|e| Error::new(Status::GenericFailure, e.to_string()))?;
+                    .map_err(|e| caused_by(DevicesError::DeviceUnavailable, e.to_string(), e))?;
 
                 host.input_devices()
-                    .map_err(|e| Error::new(Status::GenericFailure, e.to_string()))?
+                    .map_err(|e| caused_by(DevicesError::DeviceUnavailable, e.to_string(), e))?
                     .nth(device_idx)
                     .ok_or_else(|| {
-                        Error::new(
-                            Status::InvalidArg,
+                        error(
+                            DevicesError::InvalidDevice,
                             format!(
                                 "Device at index {} not found on host {}",
                                 device_idx, host_name
@@ -262,22 +291,29 @@ impl AudioRecorder {
             } else {
                 // Fallback for old numeric IDs or simple IDs
                 host.input_devices()
-                    .map_err(|e| Error::new(Status::GenericFailure, e.to_string()))?
+                    .map_err(|e| caused_by(DevicesError::DeviceUnavailable, e.to_string(), e))?
                     .nth(id.parse::<usize>().unwrap_or(0))
                     .ok_or_else(|| {
-                        Error::new(Status::InvalidArg, format!("Device ID {} not found", id))
+                        error(
+                            DevicesError::InvalidDevice,
+                            format!("Device ID {} not found", id),
+                        )
                     })?
             }
         } else {
             host.default_input_device().ok_or_else(|| {
-                Error::new(Status::GenericFailure, "No default input device available")
+                error(
+                    DevicesError::NoDevicesFound,
+                    "No default input device available",
+                )
             })?
         };
 
         let config = device.default_input_config().map_err(|e| {
-            Error::new(
-                Status::GenericFailure,
+            caused_by(
+                DevicesError::DeviceUnavailable,
                 format!("Failed to get default input config: {}", e),
+                e,
             )
         })?;
 
@@ -290,18 +326,32 @@ impl AudioRecorder {
         let is_recording = self.is_recording.clone();
         let last_peak = self.last_peak.clone();
         let last_rms = self.last_rms.clone();
+        *self.loudness.lock_or_recover() = LoudnessMeter::new(self.sample_rate, self.channels);
+        let loudness = self.loudness.clone();
+        self.reset_tempo();
+        let tempo = self.tempo.clone();
+        let analyser = self.analyser.clone();
+        let (sample_rate, channels) = (self.sample_rate, self.channels);
 
         // Reserve for 10 seconds of audio by default.
         {
-            let mut samples = recorded_samples.lock().unwrap();
+            let mut samples = recorded_samples.lock_or_recover();
             samples.clear();
             let reserve_size =
                 (self.sample_rate * self.channels as u32 * DEFAULT_RESERVE_SECONDS) as usize;
             samples.reserve(reserve_size);
         }
 
-        let err_fn = move |err| {
-            eprintln!("Audio stream error: {}", err);
+        let err_fn = {
+            let is_recording = self.is_recording.clone();
+            let errors = self.errors.clone();
+            move |err: cpal::StreamError| {
+                let lost = matches!(err, cpal::StreamError::DeviceNotAvailable);
+                if lost {
+                    is_recording.store(false, Ordering::SeqCst);
+                }
+                errors.report(stream_failure(lost, &err));
+            }
         };
 
         let mut stream_config: cpal::StreamConfig = config.clone().into();
@@ -331,21 +381,36 @@ impl AudioRecorder {
                 }
 
                 {
-                    *last_peak.lock().unwrap() = peak as f64;
+                    *last_peak.lock_or_recover() = peak as f64;
                     if !data.is_empty() {
-                        *last_rms.lock().unwrap() = (sum_sq / data.len() as f64).sqrt();
+                        *last_rms.lock_or_recover() = (sum_sq / data.len() as f64).sqrt();
                     }
                 }
 
+                loudness
+                    .lock_or_recover()
+                    .add_samples(data.iter().map(|&s| s as f32 / I16_MAX_F32));
+                tempo
+                    .lock_or_recover()
+                    .add_samples(data.iter().map(|&s| s as f32 / I16_MAX_F32));
+
+                if let Some(analyser) = analyser.lock_or_recover().as_ref() {
+                    analyser.push(
+                        data.iter().map(|&s| s as f32 / I16_MAX_F32),
+                        channels,
+                        sample_rate,
+                    );
+                }
+
                 // Fill full history
                 {
-                    let mut samples = recorded_samples.lock().unwrap();
+                    let mut samples = recorded_samples.lock_or_recover();
                     samples.extend_from_slice(data);
                 }
 
                 // Fill ring buffer
                 {
-                    let mut rb_guard = ring_buffer.lock().unwrap();
+                    let mut rb_guard = ring_buffer.lock_or_recover();
                     if let Some(rb) = rb_guard.as_mut() {
                         use ringbuf::traits::Producer;
                         let _ = rb.push_slice(data);
@@ -354,7 +419,7 @@ impl AudioRecorder {
 
                 // Emit callback
                 {
-                    let callback_guard = on_data.lock().unwrap();
+                    let callback_guard = on_data.lock_or_recover();
                     if let Some(cb) = callback_guard.as_ref() {
                         cb(data.to_vec());
                     }
@@ -408,23 +473,25 @@ impl AudioRecorder {
                 None,
             ),
             _ => {
-                return Err(Error::new(
-                    Status::GenericFailure,
+                return Err(error(
+                    StreamError::UnsupportedFormat,
                     format!("Unsupported sample format: {:?}", config.sample_format()),
                 ))
             }
         }
         .map_err(|e| {
-            Error::new(
-                Status::GenericFailure,
+            caused_by(
+                DevicesError::DeviceUnavailable,
                 format!("Failed to build input stream: {}", e),
+                e,
             )
         })?;
 
         stream.play().map_err(|e| {
-            Error::new(
-                Status::GenericFailure,
+            caused_by(
+                DevicesError::DeviceUnavailable,
                 format!("Failed to start input stream: {}", e),
+                e,
             )
         })?;
 
@@ -453,29 +520,25 @@ impl AudioRecorder {
 
     #[napi]
     pub fn get_buffer(&self) -> Result<SamplesBuffer> {
-        let samples = self.recorded_samples.lock().unwrap().clone();
-        Ok(SamplesBuffer::create(
-            self.channels as u32,
-            self.sample_rate,
-            samples,
-        ))
+        let samples = self.recorded_samples.lock_or_recover().clone();
+        Ok(SamplesBuffer::new(self.channels, self.sample_rate, samples))
     }
 
     #[napi]
-    pub fn get_ring_buffer_samples(&self) -> Result<Vec<i16>> {
+    pub fn get_ring_buffer_samples(&self) -> Result<Int16Array> {
         use ringbuf::traits::Consumer;
-        let mut rb_guard = self.ring_buffer.lock().unwrap();
+        let mut rb_guard = self.ring_buffer.lock_or_recover();
         if let Some(rb) = rb_guard.as_mut() {
             let samples: Vec<i16> = rb.pop_iter().collect();
-            Ok(samples)
+            Ok(Int16Array::new(samples))
         } else {
-            Ok(Vec::new())
+            Ok(Int16Array::new(Vec::new()))
         }
     }
 
     #[napi]
     pub fn clear(&mut self) {
-        self.recorded_samples.lock().unwrap().clear();
+        self.recorded_samples.lock_or_recover().clear();
     }
 
     #[napi]
@@ -490,8 +553,47 @@ impl AudioRecorder {
     #[napi]
     pub fn get_levels(&self) -> AudioLevels {
         AudioLevels {
-            peak: *self.last_peak.lock().unwrap(),
-            rms: *self.last_rms.lock().unwrap(),
+            peak: *self.last_peak.lock_or_recover(),
+            rms: *self.last_rms.lock_or_recover(),
         }
     }
+
+    /// Get EBU R128 loudness of the recording since `start` or `resetLoudness`
+    #[napi]
+    pub fn get_loudness(&self) -> LoudnessStats {
+        self.loudness.lock_or_recover().stats()
+    }
+
+    /// Restart loudness measurement, e.g. at the start of a programme
+    #[napi]
+    pub fn reset_loudness(&self) {
+        *self.loudness.lock_or_recover() = LoudnessMeter::new(self.sample_rate, self.channels);
+    }
+
+    /// Estimate tempo and beats from the latest 30 s of input; beat times are
+    /// relative to `start` or `resetTempo`
+    #[napi]
+    pub fn get_tempo(&self, options: Option<TempoOptions>) -> Result<TempoInfo> {
+        // The audio callback feeds the tracker; only hold it while copying
+        let envelope = self.tempo.lock_or_recover().envelope();
+        envelope.estimate(&options.unwrap_or_default())
+    }
+
+    /// Restart tempo tracking, e.g. when the music changes
+    #[napi]
+    pub fn reset_tempo(&self) {
+        *self.tempo.lock_or_recover() =
+            TempoTracker::windowed(self.sample_rate, self.channels, LIVE_WINDOW_SECONDS);
+    }
+
+    /// Feed the recorded input to `analyser`, replacing any analyser attached before
+    #[napi]
+    pub fn attach_analyser(&self, analyser: &AudioAnalyser) {
+        *self.analyser.lock_or_recover() = Some(analyser.shared());
+    }
+
+    #[napi]
+    pub fn detach_analyser(&self) {
+        *self.analyser.lock_or_recover() = None;
+    }
 }
@@ -4,34 +4,57 @@
 #![allow(clippy::arc_with_non_send_sync)]
 
 // Declare the top-level modules
+pub mod analyser;
 pub mod audio_passthrough;
 pub mod buffer;
 pub mod conversions;
 pub mod decoder;
+pub mod devices;
+pub mod engine;
+pub mod error;
 pub mod input;
+pub mod loudness;
 pub mod math;
 pub mod mixer;
 pub mod noise;
 pub mod player;
+pub mod playlist;
+pub mod png;
+pub mod probe;
 pub mod queue;
+pub mod replaygain;
+pub mod spectrogram;
 pub mod stream;
+pub mod tempo;
 pub mod types;
 pub mod utils;
+pub mod waveform;
 
 // Re-export all the contents at the crate root level for flat NAPI export
+pub use analyser::*;
 pub use audio_passthrough::*;
 pub use buffer::*;
 pub use conversions::*;
 pub use decoder::*;
+pub use devices::*;
+pub use engine::*;
+pub use error::*;
 pub use input::*;
+pub use loudness::*;
 pub use math::*;
 pub use mixer::*;
 pub use noise::*;
 pub use player::*;
+pub use playlist::*;
+pub use probe::*;
 pub use queue::*;
+pub use replaygain::*;
+pub use spectrogram::*;
 pub use stream::*;
+pub use tempo::*;
 pub use types::*;
 pub use utils::*;
+pub use waveform::*;
 
 #[cfg(test)]
 mod tests {
@@ -40,7 +63,11 @@ mod tests {
     #[test]
     fn test_formats() {
         assert!(is_format_supported("mp3".to_string()));
+        assert!(is_format_supported("M4A".to_string()));
+        assert!(is_format_supported("aac".to_string()));
         assert!(!is_format_supported("xyz".to_string()));
+        // No Opus decoder is compiled in
+        assert!(!get_supported_formats().contains(&"opus".to_string()));
     }
 
     #[test]
@@ -78,31 +105,43 @@ mod tests {
 
     #[test]
     fn test_buffer_creation() {
-        let buffer = buffer::SamplesBuffer::create(2, 44100, vec![0i16; 4410]);
+        use napi::bindgen_prelude::{Either, Float32Array, Int16Array};
+
+        let buffer =
+            buffer::SamplesBuffer::create(2, 44100, Either::A(Int16Array::new(vec![0i16; 4410])));
         assert_eq!(buffer.get_channels(), 2);
         assert_eq!(buffer.get_sample_rate(), 44100);
         assert_eq!(buffer.get_len(), 4410);
+
+        // Float input is scaled to 16-bit and clamped to full scale
+        let floats = Float32Array::new(vec![0.0, 0.5, -1.0, 2.0]);
+        let buffer = buffer::SamplesBuffer::create(1, 44100, Either::B(floats));
+        assert_eq!(&*buffer.get_samples(), &[0, 16384, -32768, 32767]);
     }
 
     #[test]
     fn test_channel_conversion() {
+        use napi::bindgen_prelude::Int16Array;
+
         let converter = conversions::ChannelCountConverter::new(1, 2);
         assert_eq!(converter.source_channels(), 1);
         assert_eq!(converter.target_channels(), 2);
 
         let mono = vec![1000i16, 2000i16, 3000i16];
-        let stereo = converter.convert(mono);
+        let stereo = converter.convert(Int16Array::new(mono));
         assert_eq!(stereo.len(), 6); // mono * 2
     }
 
     #[test]
     fn test_sample_rate_conversion() {
+        use napi::bindgen_prelude::Int16Array;
+
         let converter = conversions::SampleRateConverter::new(44100, 48000);
         assert_eq!(converter.source_rate(), 44100);
         assert_eq!(converter.target_rate(), 48000);
 
         let samples = vec![1000i16; 1000];
-        let converted = converter.convert(samples);
+        let converted = converter.convert(Int16Array::new(samples));
         assert!(!converted.is_empty());
     }
 
@@ -113,16 +152,300 @@ mod tests {
         assert_eq!(mixer.get_master_volume(), 1.0);
     }
 
+    #[test]
+    fn test_mixer_metering() {
+        let mixer = mixer::Mixer::new();
+        let mut kick = mixer::MixerSource::new("kick".to_string(), vec![24000i16; 8820], 44100, 1);
+        kick.set_bus(Some("drums".to_string()));
+        let snare = mixer::MixerSource::new("snare".to_string(), vec![16000i16; 8820], 44100, 1);
+        mixer.add_source(&kick).unwrap();
+        mixer.add_source(&snare).unwrap();
+
+        // 100 ms is enough to close a default 50 ms metering window
+        let mixed = mixer.render_block(0, 4410);
+        assert_eq!(mixed.len(), 4410 * 2);
+        assert_eq!(mixed[0], i16::MAX);
+
+        let kick_levels = kick.get_levels();
+        assert!((kick_levels.peak - 24000.0 / 32768.0).abs() < 0.001);
+        assert!(!kick_levels.clipped);
+
+        let drums = mixer.get_bus_levels("drums".to_string()).unwrap();
+        assert!((drums.peak - kick_levels.peak).abs() < 0.001);
+
+        let master = mixer.get_master_levels();
+        assert!(master.peak > 1.0);
+        assert!(master.clipped);
+
+        mixer.reset_levels();
+        assert!(!mixer.get_master_levels().clipped);
+        assert!(mixer.get_bus_levels("drums".to_string()).is_err());
+
+        // Positive full scale is a clip, even though it is just below 1.0
+        let full = mixer::MixerSource::new("full".to_string(), vec![i16::MAX; 8820], 44100, 1);
+        let mixer = mixer::Mixer::new();
+        mixer.add_source(&full).unwrap();
+        mixer.render_block(0, 4410);
+        assert!(full.get_levels().clipped);
+    }
+
+    #[test]
+    fn test_mixer_render_task() {
+        use error::AudioTask;
+
+        let mixer = mixer::Mixer::new();
+        let tone = mixer::MixerSource::new("tone".to_string(), vec![8000i16; 44100], 44100, 1);
+        mixer.add_source(&tone).unwrap();
+
+        // Rendered block by block, the task matches a single synchronous render
+        let mut task = mixer.render_task(100, 2000, types::CancelToken::default());
+        assert_eq!(
+            &task.compute().unwrap()[..],
+            &*mixer.render_block(100, 2000)
+        );
+
+        let cancel = types::CancelToken::default();
+        let mut task = mixer.render_task(0, 2000, cancel.clone());
+        cancel.cancel();
+        assert!(cancel.is_cancelled());
+        let err = task.compute().unwrap_err();
+        assert_eq!(err.status, ErrorCode::Cancelled);
+    }
+
     #[test]
     fn test_queue_creation() {
         let q = queue::AudioSourceQueue::new();
         assert_eq!(q.get_length(), 0);
     }
 
+    #[test]
+    fn test_queue_playback_modes() {
+        let mut q = queue::AudioSourceQueue::new();
+        for i in 0..3 {
+            q.add_source(format!("track{}.mp3", i), None).unwrap();
+        }
+
+        // Repeat off stops at the end, repeat all wraps around
+        assert!(q.next().is_some());
+        assert!(q.next().is_some());
+        assert!(q.next().is_none());
+        q.set_playback_mode(QueuePlaybackMode::RepeatAll, None);
+        assert_eq!(q.next().unwrap().file_path.unwrap(), "track0.mp3");
+        q.set_playback_mode(QueuePlaybackMode::RepeatOne, None);
+        assert_eq!(q.next().unwrap().file_path.unwrap(), "track0.mp3");
+        assert_eq!(q.previous().unwrap().file_path.unwrap(), "track2.mp3");
+    }
+
+    #[test]
+    fn test_queue_ids_and_reorder() {
+        let mut q = queue::AudioSourceQueue::new();
+        let a = q.add_source("a.mp3".to_string(), None).unwrap();
+        let b = q.add_source("b.mp3".to_string(), None).unwrap();
+        let c = q.add_source("c.mp3".to_string(), None).unwrap();
+        q.set_current_index(1).unwrap();
+
+        // IDs are never reused after a removal
+        q.remove_source(a.clone()).unwrap();
+        let d = q.add_source("d.mp3".to_string(), None).unwrap();
+        assert!(![a, b.clone(), c.clone()].contains(&d));
+        assert_eq!(q.get_current().unwrap().source_id, b);
+
+        // The current index follows its item through every mutation
+        q.insert_at(0, "e.mp3".to_string(), None).unwrap();
+        assert_eq!(q.get_current().unwrap().source_id, b);
+        q.move_item(b.clone(), 3).unwrap();
+        assert_eq!(q.get_current_index(), 3);
+        assert_eq!(q.get_current().unwrap().source_id, b);
+        q.swap(b.clone(), c.clone()).unwrap();
+        assert_eq!(q.get_current().unwrap().source_id, b);
+
+        // Removing the current item moves on to the one that followed it
+        let order: Vec<String> = q.get_sources().into_iter().map(|s| s.source_id).collect();
+        let current = q.get_current_index() as usize;
+        q.remove_source(b).unwrap();
+        assert_eq!(q.get_current().unwrap().source_id, order[current + 1]);
+    }
+
+    #[test]
+    fn test_connected_sources_queue() {
+        use napi::bindgen_prelude::{Either, Int16Array};
+
+        let queue::SourcesQueuePair { input, output } = queue::sources_queue();
+        assert!(!output.has_next());
+        assert!(output.pop_blocking(Some(10)).is_none());
+
+        let id = input.push_file("a.mp3".to_string()).unwrap();
+        assert_eq!(output.get_remaining(), 1);
+        assert_eq!(output.pop().unwrap().source_id, id);
+        // Popped items are no longer held by the queue
+        assert!(input.set_title(id, "gone".to_string()).is_err());
+
+        // A blocked consumer wakes up when the producer pushes
+        let producer = std::thread::spawn(move || {
+            std::thread::sleep(std::time::Duration::from_millis(20));
+            input
+                .push_buffer(Either::A(Int16Array::new(vec![0i16; 16])))
+                .unwrap();
+            input.close();
+        });
+        let item = output.pop_blocking(Some(5000)).unwrap();
+        assert!(item.buffer.is_some());
+        producer.join().unwrap();
+        assert!(output.pop_blocking(None).is_none());
+
+        // Dropping the input without closing it still releases a waiting consumer
+        let queue::SourcesQueuePair { input, output } = queue::sources_queue();
+        let consumer = std::thread::spawn(move || output.pop_blocking(None));
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        drop(input);
+        assert!(consumer.join().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_playlist_import_export() {
+        let dir = std::env::temp_dir().join(format!("miniaudio_playlist_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let m3u = dir.join("list.m3u8");
+        std::fs::write(
+            &m3u,
+            "#EXTM3U\n#EXTINF:123,Artist - Song\nmusic/song.mp3\n/abs/other.flac\n",
+        )
+        .unwrap();
+        let mut q = queue::AudioSourceQueue::new();
+        q.load_playlist(m3u.to_string_lossy().into_owned(), None)
+            .unwrap();
+        let items = q.get_sources();
+        assert_eq!(items.len(), 2);
+        assert_eq!(
+            items[0].file_path.as_deref(),
+            Some(dir.join("music/song.mp3").to_str().unwrap())
+        );
+        assert_eq!(items[0].title.as_deref(), Some("Artist - Song"));
+        assert_eq!(items[0].duration, Some(123.0));
+        assert_eq!(items[1].file_path.as_deref(), Some("/abs/other.flac"));
+
+        // CUE tracks become sub-ranges of one file
+        let cue = dir.join("album.cue");
+        std::fs::write(
+            &cue,
+            "FILE \"album.flac\" WAVE\n  TRACK 01 AUDIO\n    TITLE \"One\"\n    INDEX 01 00:00:00\n  TRACK 02 AUDIO\n    TITLE \"Two\"\n    INDEX 01 03:30:15\n",
+        )
+        .unwrap();
+        let mut q = queue::AudioSourceQueue::new();
+        q.load_playlist(cue.to_string_lossy().into_owned(), None)
+            .unwrap();
+        let tracks = q.get_sources();
+        assert_eq!(tracks[0].file_path, tracks[1].file_path);
+        assert_eq!(tracks[0].end_time, Some(210.2));
+        assert_eq!(tracks[1].start_time, Some(210.2));
+        assert_eq!(tracks[1].end_time, None);
+
+        // Saving and loading again keeps locations, titles and durations
+        for (name, format) in [
+            ("out.pls", PlaylistFormat::Pls),
+            ("out.xspf", PlaylistFormat::Xspf),
+            ("out.cue", PlaylistFormat::Cue),
+        ] {
+            let path = dir.join(name).to_string_lossy().into_owned();
+            assert_eq!(q.save_playlist(path.clone(), format).unwrap(), 2);
+            let mut reloaded = queue::AudioSourceQueue::new();
+            reloaded.load_playlist(path, None).unwrap();
+            let reloaded = reloaded.get_sources();
+            assert_eq!(reloaded.len(), 2);
+            assert_eq!(reloaded[0].file_path, tracks[0].file_path);
+            assert_eq!(reloaded[1].title.as_deref(), Some("Two"));
+        }
+
+        // An escape at the very end of a location is decoded too
+        let xspf = dir.join("tail.xspf");
+        std::fs::write(
+            &xspf,
+            "<playlist><trackList><track><location>/abs/song%20</location></track></trackList></playlist>",
+        )
+        .unwrap();
+        let mut q = queue::AudioSourceQueue::new();
+        q.load_playlist(xspf.to_string_lossy().into_owned(), None)
+            .unwrap();
+        assert_eq!(q.get_sources()[0].file_path.as_deref(), Some("/abs/song "));
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_queue_shuffle() {
+        let shuffled_order = |seed| {
+            let mut q = queue::AudioSourceQueue::new();
+            for i in 0..8 {
+                q.add_source(format!("track{}.mp3", i), None).unwrap();
+            }
+            q.set_playback_mode(QueuePlaybackMode::Shuffle, Some(seed));
+            let mut order = vec![q.get_current_index()];
+            for _ in 0..7 {
+                q.next().unwrap();
+                order.push(q.get_current_index());
+            }
+            // Previous walks back through what was actually played
+            for expected in order.iter().rev().skip(1) {
+                q.previous().unwrap();
+                assert_eq!(q.get_current_index(), *expected);
+            }
+            order
+        };
+
+        let order = shuffled_order(42);
+        let mut sorted = order.clone();
+        sorted.sort();
+        assert_eq!(sorted, (0..8).collect::<Vec<u32>>());
+        assert_eq!(order, shuffled_order(42));
+    }
+
     #[test]
     fn test_stream() {
-        let configs = stream::supported_output_configs().unwrap();
-        assert!(!configs.is_empty());
+        // Machines without an output device report an error instead of made-up configs
+        if let Ok(supported) = devices::device_output_configs(None) {
+            assert!(!supported.configs.is_empty());
+        }
+        let flattened = devices::stream_configs(&devices::DeviceSupportedConfigs {
+            device: types::AudioDeviceInfo {
+                id: "test:0".to_string(),
+                name: "test".to_string(),
+                host: "test".to_string(),
+                is_default: true,
+            },
+            default_config: None,
+            configs: vec![devices::DeviceConfigRange {
+                channels: 2,
+                min_sample_rate: 44100,
+                max_sample_rate: 48000,
+                sample_format: "i16".to_string(),
+                min_buffer_size: None,
+                max_buffer_size: None,
+            }],
+        });
+        assert_eq!(
+            flattened
+                .iter()
+                .map(|c| (c.sample_rate, c.channel_count, c.sample_width))
+                .collect::<Vec<_>>(),
+            vec![(44100, 2, 16), (48000, 2, 16)]
+        );
+
+        let range = cpal::SupportedStreamConfigRange::new(
+            2,
+            44100,
+            96000,
+            cpal::SupportedBufferSize::Range { min: 64, max: 8192 },
+            cpal::SampleFormat::F32,
+        );
+        let info = devices::DeviceConfigRange::from(&range);
+        assert_eq!(info.channels, 2);
+        assert_eq!((info.min_sample_rate, info.max_sample_rate), (44100, 96000));
+        assert_eq!(info.sample_format, "f32");
+        assert_eq!(
+            (info.min_buffer_size, info.max_buffer_size),
+            (Some(64), Some(8192))
+        );
     }
 
     #[test]
@@ -133,6 +456,754 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_error_codes() {
+        use decoder::AudioDecoder;
+
+        let err = AudioDecoder::from_file("/nonexistent/audio.wav".to_string())
+            .err()
+            .unwrap();
+        assert_eq!(err.status, ErrorCode::Decoder(DecoderError::FileNotFound));
+        assert_eq!(err.status.as_ref(), "FileNotFound");
+
+        // Decoder failures keep the underlying error as their cause
+        let err = AudioDecoder::from_data(vec![7u8; 64]).err().unwrap();
+        assert!(matches!(err.status, ErrorCode::Decoder(_)));
+        assert!(err.reason.starts_with("Failed to decode audio"));
+        assert!(err.cause.is_some());
+
+        let mut player = player::AudioPlayer::new().unwrap();
+        let err = player.set_volume(2.0).unwrap_err();
+        assert_eq!(err.status, ErrorCode::InvalidArgument);
+        assert_eq!(err.reason, "Volume must be between 0.0 and 1.0");
+        let err = player.play().unwrap_err();
+        assert_eq!(err.status.as_ref(), "NotLoaded");
+
+        let mut writer = stream::push_channel(
+            &stream::PushStreamOptions {
+                sample_rate: 1000,
+                channels: 2,
+                format: stream::PcmSampleFormat::F32,
+                buffer_ms: Some(10),
+                high_water_ms: None,
+                prebuffer_ms: None,
+            },
+            stream::PushCallbacks::default(),
+        )
+        .unwrap()
+        .0;
+        let err = writer.write_samples(&[0.0; 3]).unwrap_err();
+        assert_eq!(err.status, ErrorCode::InvalidArgument);
+        let err = writer.write_samples(&[0.0; 1000]).unwrap_err();
+        assert_eq!(err.status, ErrorCode::Stream(StreamError::Overflow));
+    }
+
+    /// Encode 16-bit PCM as an in-memory WAV file
+    fn wav_bytes(samples: &[i16], sample_rate: u32, channels: u16) -> Vec<u8> {
+        let mut wav = Vec::new();
+        wav.extend_from_slice(b"RIFF");
+        wav.extend_from_slice(&(36 + samples.len() as u32 * 2).to_le_bytes());
+        wav.extend_from_slice(b"WAVEfmt ");
+        wav.extend_from_slice(&16u32.to_le_bytes());
+        wav.extend_from_slice(&1u16.to_le_bytes());
+        wav.extend_from_slice(&channels.to_le_bytes());
+        wav.extend_from_slice(&sample_rate.to_le_bytes());
+        wav.extend_from_slice(&(sample_rate * channels as u32 * 2).to_le_bytes());
+        wav.extend_from_slice(&(channels * 2).to_le_bytes());
+        wav.extend_from_slice(&16u16.to_le_bytes());
+        wav.extend_from_slice(b"data");
+        wav.extend_from_slice(&(samples.len() as u32 * 2).to_le_bytes());
+        for s in samples {
+            wav.extend_from_slice(&s.to_le_bytes());
+        }
+        wav
+    }
+
+    /// Minimal M4A: mono AAC-LC at 44.1 kHz made of `frames` silent 1024-sample frames
+    fn m4a_bytes(frames: u32) -> Vec<u8> {
+        fn mp4_box(kind: &[u8; 4], payload: &[u8]) -> Vec<u8> {
+            let mut out = (8 + payload.len() as u32).to_be_bytes().to_vec();
+            out.extend_from_slice(kind);
+            out.extend_from_slice(payload);
+            out
+        }
+        fn full_box(kind: &[u8; 4], flags: u32, payload: &[u8]) -> Vec<u8> {
+            let mut body = flags.to_be_bytes().to_vec();
+            body.extend_from_slice(payload);
+            mp4_box(kind, &body)
+        }
+        fn words(values: &[u32]) -> Vec<u8> {
+            values.iter().flat_map(|v| v.to_be_bytes()).collect()
+        }
+        // A single channel element with no spectral data, then the end element
+        const SILENT_FRAME: [u8; 4] = [0x00, 0x00, 0x00, 0x07];
+        const MATRIX: [u32; 9] = [0x10000, 0, 0, 0, 0x10000, 0, 0, 0, 0x4000_0000];
+        let (rate, duration) = (44100u32, frames * 1024);
+
+        let moov = |chunk_offset: u32| {
+            let mut mvhd = words(&[0, 0, rate, duration, 0x10000]);
+            mvhd.extend_from_slice(&[0x01, 0x00, 0, 0]);
+            mvhd.extend_from_slice(&[0; 8]);
+            mvhd.extend_from_slice(&words(&MATRIX));
+            mvhd.extend_from_slice(&[0; 24]);
+            mvhd.extend_from_slice(&words(&[2]));
+
+            let mut tkhd = words(&[0, 0, 1, 0, duration, 0, 0, 0]);
+            tkhd.extend_from_slice(&[0x01, 0x00, 0, 0]);
+            tkhd.extend_from_slice(&words(&MATRIX));
+            tkhd.extend_from_slice(&words(&[0, 0]));
+
+            let mut mdhd = words(&[0, 0, rate, duration]);
+            mdhd.extend_from_slice(&[0x55, 0xC4, 0, 0]);
+            let mut hdlr = words(&[0]);
+            hdlr.extend_from_slice(b"soun");
+            hdlr.extend_from_slice(&[0; 13]);
+
+            // AudioSpecificConfig: AAC-LC, 44.1 kHz, one channel
+            let esds = full_box(
+                b"esds",
+                0,
+                &[
+                    0x03, 25, 0, 1, 0, // ES descriptor
+                    0x04, 17, 0x40, 0x15, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, // decoder config
+                    0x05, 2, 0x12, 0x08, // decoder specific info
+                    0x06, 1, 0x02, // SL config
+                ],
+            );
+            let mut mp4a = vec![0; 6];
+            mp4a.extend_from_slice(&[0, 1]);
+            mp4a.extend_from_slice(&[0; 8]);
+            mp4a.extend_from_slice(&[0, 1, 0, 16, 0, 0, 0, 0]);
+            mp4a.extend_from_slice(&words(&[rate << 16]));
+            mp4a.extend_from_slice(&esds);
+            let mut stsd = words(&[1]);
+            stsd.extend_from_slice(&mp4_box(b"mp4a", &mp4a));
+
+            let mut stsz = words(&[0, frames]);
+            stsz.extend(words(&vec![SILENT_FRAME.len() as u32; frames as usize]));
+            let stbl = [
+                full_box(b"stsd", 0, &stsd),
+                full_box(b"stts", 0, &words(&[1, frames, 1024])),
+                full_box(b"stsc", 0, &words(&[1, 1, frames, 1])),
+                full_box(b"stsz", 0, &stsz),
+                full_box(b"stco", 0, &words(&[1, chunk_offset])),
+            ]
+            .concat();
+            let mut dref = words(&[1]);
+            dref.extend_from_slice(&full_box(b"url ", 1, &[]));
+            let minf = [
+                full_box(b"smhd", 0, &[0; 4]),
+                mp4_box(b"dinf", &full_box(b"dref", 0, &dref)),
+                mp4_box(b"stbl", &stbl),
+            ]
+            .concat();
+            let mdia = [
+                full_box(b"mdhd", 0, &mdhd),
+                full_box(b"hdlr", 0, &hdlr),
+                mp4_box(b"minf", &minf),
+            ]
+            .concat();
+            let trak = [full_box(b"tkhd", 7, &tkhd), mp4_box(b"mdia", &mdia)].concat();
+            mp4_box(
+                b"moov",
+                &[full_box(b"mvhd", 0, &mvhd), mp4_box(b"trak", &trak)].concat(),
+            )
+        };
+
+        let ftyp = mp4_box(b"ftyp", b"M4A \0\0\0\0M4A isommp42");
+        let header_len = (ftyp.len() + moov(0).len() + 8) as u32;
+        let mdat = mp4_box(b"mdat", &SILENT_FRAME.repeat(frames as usize));
+        [ftyp, moov(header_len), mdat].concat()
+    }
+
+    #[test]
+    fn test_decoder_streaming() {
+        use decoder::AudioDecoder;
+
+        // One second of a 16-bit mono ramp at 8 kHz
+        let samples: Vec<i16> = (0..8000).map(|i| (i * 4) as i16).collect();
+        let wav = wav_bytes(&samples, 8000, 1);
+
+        let decoder = AudioDecoder::from_data(wav).unwrap();
+        let full = decoder.decode_to_samples().unwrap().to_vec();
+        assert_eq!(full.len(), 8000);
+
+        assert_eq!(&*decoder.read(1000).unwrap(), &full[..1000]);
+        assert_eq!(&*decoder.next_chunk().unwrap().unwrap(), &full[1000..5096]);
+        assert!((decoder.get_position() - 5096.0 / 8000.0).abs() < 1e-9);
+
+        decoder.seek(0.5).unwrap();
+        assert_eq!(&*decoder.read(10).unwrap(), &full[4000..4010]);
+        assert!(decoder.seek(f64::NAN).is_err());
+
+        // Slices seek on their own decoder and leave the read position alone
+        assert_eq!(
+            &*decoder.decode_slice(0.25, 0.5).unwrap(),
+            &full[2000..4000]
+        );
+        assert!(decoder.decode_slice(2.0, 3.0).unwrap().is_empty());
+        assert_eq!(
+            &*decoder.decode_slice(0.0, f64::INFINITY).unwrap(),
+            &full[..]
+        );
+        assert_eq!(&*decoder.read(5).unwrap(), &full[4010..4015]);
+
+        decoder.seek(0.9).unwrap();
+        assert_eq!(decoder.next_chunk().unwrap().unwrap().len(), 800);
+        assert!(decoder.next_chunk().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_waveform_peaks() {
+        use decoder::AudioDecoder;
+        use waveform::PeaksOptions;
+
+        // Half a second of silence then half a second of a square wave, stereo at 8 kHz
+        let samples: Vec<i16> = (0..8000)
+            .flat_map(|i| {
+                let s = if i < 4000 {
+                    0
+                } else if i % 2 == 0 {
+                    16384
+                } else {
+                    -16384
+                };
+                [s, s / 2]
+            })
+            .collect();
+        let decoder = AudioDecoder::from_data(wav_bytes(&samples, 8000, 2)).unwrap();
+
+        let peaks = decoder
+            .compute_peaks(PeaksOptions {
+                bins_per_second: Some(4.0),
+                total_bins: None,
+            })
+            .unwrap();
+        assert_eq!(peaks.get_samples_per_bin(), 2000);
+        assert_eq!(peaks.get_length(), 4);
+        let max = peaks.get_max(0).unwrap();
+        let rms = peaks.get_rms(1).unwrap();
+        assert_eq!(max[0], 0.0);
+        assert!((max[3] - 0.5).abs() < 1e-3);
+        assert!((rms[3] - 0.25).abs() < 1e-3);
+        assert!(peaks.get_min(2).is_err());
+
+        // Bins are sized from the duration and cover the whole input
+        let peaks = decoder
+            .compute_peaks(PeaksOptions {
+                bins_per_second: None,
+                total_bins: Some(3),
+            })
+            .unwrap();
+        assert_eq!(peaks.get_samples_per_bin(), 2667);
+        assert_eq!(peaks.get_length(), 3);
+        assert!(decoder.compute_peaks(PeaksOptions::default()).is_err());
+
+        // audiowaveform v2: header then min/max pairs per bin and channel
+        let json = peaks.to_json(Some(8)).unwrap();
+        assert!(json.starts_with(
+            "{\"version\":2,\"channels\":2,\"sample_rate\":8000,\"samples_per_pixel\":2667,\"bits\":8,\"length\":3,\"data\":[0,0,0,0,"
+        ));
+        let dat = peaks.to_dat(None).unwrap();
+        assert_eq!(dat.len(), 24 + 3 * 2 * 2 * 2);
+        assert_eq!(&dat[4..8], &0u32.to_le_bytes());
+        assert_eq!(&dat[20..24], &2u32.to_le_bytes());
+        assert!(peaks.to_dat(Some(24)).is_err());
+    }
+
+    #[test]
+    fn test_loudness() {
+        use loudness::LoudnessMeter;
+
+        // EBU Tech 3341 case 1: a 1 kHz stereo sine at -23 dBFS reads -23 LUFS
+        let rate = 48000;
+        let amplitude = 10f32.powf(-23.0 / 20.0);
+        let sine: Vec<f32> = (0..rate * 5)
+            .flat_map(|i| {
+                let s = amplitude
+                    * (2.0 * std::f32::consts::PI * 1000.0 * i as f32 / rate as f32).sin();
+                [s, s]
+            })
+            .collect();
+        let mut meter = LoudnessMeter::new(rate, 2);
+        meter.add_samples(sine.iter().copied());
+        let stats = meter.stats();
+        assert!((stats.integrated + 23.0).abs() < 0.1, "{:?}", stats);
+        assert!((stats.momentary + 23.0).abs() < 0.1);
+        assert!((stats.max_short_term + 23.0).abs() < 0.1);
+        assert!(stats.loudness_range < 0.1);
+        assert!((stats.sample_peak + 23.0).abs() < 0.1);
+        assert!(stats.true_peak >= stats.sample_peak && stats.true_peak < -22.8);
+
+        // Inter-sample peaks: a sine at a quarter of the rate, sampled off its crests
+        let mut meter = LoudnessMeter::new(rate, 1);
+        meter.add_samples((0..4800).map(|i| {
+            (std::f32::consts::FRAC_PI_2 * i as f32 + std::f32::consts::FRAC_PI_4).sin() * 0.5
+        }));
+        let stats = meter.stats();
+        assert!((stats.sample_peak - 20.0 * (0.5f64 * 0.5f64.sqrt()).log10()).abs() < 0.01);
+        assert!(stats.true_peak > stats.sample_peak + 2.5, "{:?}", stats);
+
+        // EBU Tech 3342 case 1: 10 dB steps read an LRA of 10 LU
+        let mut meter = LoudnessMeter::new(rate, 1);
+        for db in [-20.0f32, -30.0] {
+            let amplitude = 10f32.powf(db / 20.0);
+            meter.add_samples((0..rate * 10).map(|i| {
+                amplitude * (2.0 * std::f32::consts::PI * 1000.0 * i as f32 / rate as f32).sin()
+            }));
+        }
+        let stats = meter.stats();
+        assert!((stats.loudness_range - 10.0).abs() < 1.0, "{:?}", stats);
+
+        // Silence stays below the gates; buffers measure the same way
+        let silent = buffer::SamplesBuffer::new(2, 8000, vec![0; 16000]).analyze_loudness();
+        assert_eq!(silent.integrated, f64::NEG_INFINITY);
+        assert_eq!(silent.loudness_range, 0.0);
+    }
+
+    #[test]
+    fn test_replay_gain() {
+        use error::AudioTask;
+        use replaygain::{NormalizationConfig, NormalizationMode, ReplayGainInfo};
+        use symphonia::core::meta::{MetadataBuilder, StandardTagKey, Tag, Value};
+        use types::CancelToken;
+
+        let mut builder = MetadataBuilder::new();
+        builder
+            .add_tag(Tag::new(
+                Some(StandardTagKey::ReplayGainTrackGain),
+                "REPLAYGAIN_TRACK_GAIN",
+                Value::from("-6.50 dB"),
+            ))
+            .add_tag(Tag::new(
+                None,
+                "----:com.apple.iTunes:replaygain_track_peak",
+                Value::from("1.2"),
+            ))
+            .add_tag(Tag::new(None, "R128_ALBUM_GAIN", Value::from("-2560")));
+        let mut info = ReplayGainInfo::default();
+        replaygain::apply_tags(&mut info, &builder.metadata());
+        assert_eq!(info.track_gain, Some(-6.5));
+        assert_eq!(info.track_peak, Some(1.2));
+        // R128 gains are relative to -23 LUFS, ReplayGain to -18 LUFS
+        assert_eq!(info.album_gain, Some(-5.0));
+
+        let mut config = NormalizationConfig {
+            mode: NormalizationMode::Track,
+            pre_amp_db: None,
+            prevent_clipping: None,
+        };
+        assert!((info.gain(&config) - 10f32.powf(-6.5 / 20.0)).abs() < 1e-6);
+        config.pre_amp_db = Some(10.0);
+        assert!((info.gain(&config) - 1.0 / 1.2).abs() < 1e-6);
+        config.prevent_clipping = Some(false);
+        assert!((info.gain(&config) - 10f32.powf(3.5 / 20.0)).abs() < 1e-5);
+        config.mode = NormalizationMode::Album;
+        config.pre_amp_db = None;
+        assert!((info.gain(&config) - 10f32.powf(-5.0 / 20.0)).abs() < 1e-6);
+        config.mode = NormalizationMode::Off;
+        assert_eq!(info.gain(&config), 1.0);
+
+        // Untagged sources are scanned: a -23 dBFS stereo sine needs +5 dB
+        let amplitude = 32767.0 * 10f32.powf(-23.0 / 20.0);
+        let samples: Vec<i16> = (0..48000)
+            .flat_map(|i| {
+                let s = (amplitude * (std::f32::consts::TAU * 1000.0 * i as f32 / 48000.0).sin())
+                    as i16;
+                [s, s]
+            })
+            .collect();
+        let mut player = player::AudioPlayer::new().unwrap();
+        player.load_buffer(wav_bytes(&samples, 48000, 2)).unwrap();
+        assert_eq!(player.get_replay_gain(), None);
+        assert_eq!(player.get_normalization_gain(), 0.0);
+        player
+            .set_normalization(NormalizationConfig {
+                mode: NormalizationMode::Track,
+                pre_amp_db: None,
+                prevent_clipping: None,
+            })
+            .unwrap();
+        // Setting the mode must not decode the file; the scan is a separate task
+        assert_eq!(player.get_replay_gain(), None);
+        assert_eq!(player.get_normalization_gain(), 0.0);
+        let mut task = player.scan_gain_task(CancelToken::default());
+        let result = task.compute().unwrap();
+        task.apply(&result);
+        let scanned = player.get_replay_gain().unwrap();
+        assert!(scanned.scanned);
+        assert!(
+            (scanned.track_gain.unwrap() - 5.0).abs() < 0.2,
+            "{:?}",
+            scanned
+        );
+        assert!((player.get_normalization_gain() - 5.0).abs() < 0.2);
+        assert_eq!(player.get_volume().unwrap(), 1.0);
+        assert!(player
+            .set_normalization(NormalizationConfig {
+                mode: NormalizationMode::Track,
+                pre_amp_db: Some(f64::NAN),
+                prevent_clipping: None,
+            })
+            .is_err());
+    }
+
+    #[test]
+    fn test_analyser() {
+        use analyser::{AnalyserOptions, AudioAnalyser, MagnitudeScale, Tapped, WindowFunction};
+        use std::sync::{Arc, Mutex};
+
+        let analyser = AudioAnalyser::new(Some(AnalyserOptions {
+            fft_size: Some(1024),
+            window: Some(WindowFunction::Hann),
+            smoothing: Some(0.0),
+            scale: Some(MagnitudeScale::Linear),
+        }))
+        .unwrap();
+        assert_eq!(analyser.get_frequency_bin_count(), 512);
+
+        // A 1 kHz sine at 8 kHz lands on bin 128, played through a tapped source
+        let sine: Vec<f32> = (0..4096)
+            .flat_map(|i| {
+                let s = 0.5 * (std::f32::consts::TAU * 1000.0 * i as f32 / 8000.0).sin();
+                [s, s]
+            })
+            .collect();
+        let slot = Arc::new(Mutex::new(Some(analyser.shared())));
+        let source = rodio::buffer::SamplesBuffer::new(2, 8000, sine.clone());
+        assert_eq!(Tapped::new(source, slot).count(), sine.len());
+        assert_eq!(analyser.get_sample_rate(), 8000);
+        assert_eq!(
+            &*analyser.get_time_domain_data(),
+            &sine[sine.len() - 2048..]
+                .iter()
+                .step_by(2)
+                .copied()
+                .collect::<Vec<_>>()[..]
+        );
+
+        let spectrum = analyser.get_frequency_data();
+        let peak = (0..spectrum.len())
+            .max_by(|&a, &b| spectrum[a].total_cmp(&spectrum[b]))
+            .unwrap();
+        assert_eq!(peak, 128);
+        assert!((spectrum[128] - 0.5).abs() < 0.01);
+        assert!(spectrum[300] < 1e-3);
+
+        // Log bands hold the peak of their bins; dB reads -6 for a half-scale sine
+        analyser
+            .set_options(AnalyserOptions {
+                scale: Some(MagnitudeScale::Decibels),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(analyser.get_fft_size(), 1024);
+        let slot = Arc::new(Mutex::new(Some(analyser.shared())));
+        let source = rodio::buffer::SamplesBuffer::new(2, 8000, sine);
+        Tapped::new(source, slot).for_each(drop);
+        let bands = analyser.get_bands(10, Some(100.0), Some(4000.0)).unwrap();
+        let loudest = (0..bands.len())
+            .max_by(|&a, &b| bands[a].total_cmp(&bands[b]))
+            .unwrap();
+        assert_eq!(loudest, 6);
+        assert!((bands[loudest] + 6.02).abs() < 0.2, "{:?}", &*bands);
+
+        assert!(analyser.get_bands(0, None, None).is_err());
+        assert!(AudioAnalyser::new(Some(AnalyserOptions {
+            fft_size: Some(1000),
+            ..Default::default()
+        }))
+        .is_err());
+    }
+
+    #[test]
+    fn test_spectrogram() {
+        use analyser::MagnitudeScale;
+        use decoder::AudioDecoder;
+        use spectrogram::{SpectrogramImageOptions, SpectrogramOptions};
+
+        // Two seconds of a half-scale 1 kHz sine at 8 kHz
+        let samples: Vec<i16> = (0..16000)
+            .map(|i| (16384.0 * (std::f32::consts::TAU * 1000.0 * i as f32 / 8000.0).sin()) as i16)
+            .collect();
+        let decoder = AudioDecoder::from_data(wav_bytes(&samples, 8000, 1)).unwrap();
+        let options = SpectrogramOptions {
+            fft_size: Some(256),
+            hop_size: Some(128),
+            scale: Some(MagnitudeScale::Linear),
+            ..Default::default()
+        };
+
+        let spectrogram = decoder.spectrogram(Some(options.clone())).unwrap();
+        assert_eq!(spectrogram.get_rows(), 128);
+        assert_eq!(spectrogram.get_columns(), 124);
+        assert_eq!(spectrogram.get_seconds_per_column(), 0.016);
+        assert_eq!(spectrogram.get_frequencies()[32], 1000.0);
+        let column = spectrogram.get_column(60).unwrap();
+        let peak = (0..column.len())
+            .max_by(|&a, &b| column[a].total_cmp(&column[b]))
+            .unwrap();
+        assert_eq!(peak, 32);
+        assert!((column[32] - 0.5).abs() < 0.01);
+        assert!(spectrogram.get_column(124).is_err());
+
+        // Mel bands peak at the band centred nearest the tone
+        let mel = decoder
+            .spectrogram(Some(SpectrogramOptions {
+                mel_bands: Some(40),
+                ..options.clone()
+            }))
+            .unwrap();
+        let column = mel.get_column(60).unwrap();
+        let peak = (0..column.len())
+            .max_by(|&a, &b| column[a].total_cmp(&column[b]))
+            .unwrap();
+        let frequencies = mel.get_frequencies();
+        assert!(
+            (frequencies[peak] - 1000.0).abs() < 100.0,
+            "{}",
+            frequencies[peak]
+        );
+
+        // A tight budget folds several transforms into each column
+        let pooled = decoder
+            .spectrogram(Some(SpectrogramOptions {
+                memory_budget_mb: Some(0.01),
+                ..options.clone()
+            }))
+            .unwrap();
+        assert_eq!(pooled.get_columns(), 18);
+        assert_eq!(pooled.get_seconds_per_column(), 0.112);
+
+        // Plot plus axis margins
+        let png = spectrogram
+            .to_png(Some(SpectrogramImageOptions {
+                width: Some(100),
+                height: Some(64),
+                ..Default::default()
+            }))
+            .unwrap();
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+        assert_eq!(&png[12..16], b"IHDR");
+        assert_eq!(png[16..20], 130u32.to_be_bytes());
+        assert_eq!(png[20..24], 78u32.to_be_bytes());
+
+        assert!(decoder
+            .spectrogram(Some(SpectrogramOptions {
+                hop_size: Some(512),
+                ..options
+            }))
+            .is_err());
+    }
+
+    #[test]
+    fn test_tempo() {
+        use decoder::AudioDecoder;
+        use tempo::{TempoOptions, TempoTracker};
+
+        // 12 s of 120 BPM clicks from 0.25 s at 8 kHz, every fourth from the second one accented
+        let rate = 8000;
+        let clicks: Vec<f64> = (0..24).map(|i| 0.25 + i as f64 * 0.5).collect();
+        let samples: Vec<i16> = (0..rate * 12)
+            .map(|i| {
+                let t = i as f64 / rate as f64;
+                clicks
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, &c)| t >= c && t < c + 0.01)
+                    .map(|(n, &c)| {
+                        let gain = if n % 4 == 1 { 24000.0 } else { 6000.0 };
+                        gain * (std::f64::consts::TAU * 1500.0 * (t - c)).sin()
+                    })
+                    .sum::<f64>() as i16
+            })
+            .collect();
+        let decoder = AudioDecoder::from_data(wav_bytes(&samples, rate, 1)).unwrap();
+
+        let info = decoder.detect_tempo(None).unwrap();
+        assert!((info.bpm - 120.0).abs() < 1.0, "{}", info.bpm);
+        assert!(info.confidence > 0.5, "{}", info.confidence);
+        assert!(info.beats.len() >= 20, "{:?}", info.beats);
+        for beat in &info.beats {
+            let nearest = clicks
+                .iter()
+                .map(|c| (c - beat).abs())
+                .fold(f64::MAX, f64::min);
+            assert!(nearest < 0.03, "beat at {} misses the clicks", beat);
+        }
+        assert!(
+            (info.downbeats[0] - 0.75).abs() < 0.03,
+            "{:?}",
+            info.downbeats
+        );
+        assert!(info
+            .downbeats
+            .windows(2)
+            .all(|w| (w[1] - w[0] - 2.0).abs() < 0.03));
+
+        // Fed in blocks as a recording would be, the tracker agrees
+        let mut tracker = TempoTracker::windowed(rate, 1, 30);
+        for block in samples.chunks(441) {
+            tracker.add_samples(block.iter().map(|&s| s as f32 / 32768.0));
+        }
+        assert_eq!(tracker.estimate(&TempoOptions::default()).unwrap(), info);
+
+        // Silence has no pulse
+        let silence = AudioDecoder::from_data(wav_bytes(&vec![0; 8000 * 5], rate, 1)).unwrap();
+        let none = silence.detect_tempo(None).unwrap();
+        assert_eq!((none.bpm, none.beats.len()), (0.0, 0));
+        assert!(decoder
+            .detect_tempo(Some(TempoOptions {
+                min_bpm: Some(150.0),
+                max_bpm: Some(100.0),
+                beats_per_bar: None,
+            }))
+            .is_err());
+        assert!(tracker
+            .estimate(&TempoOptions {
+                min_bpm: Some(1e-20),
+                ..Default::default()
+            })
+            .is_err());
+    }
+
+    #[test]
+    fn test_decode_m4a() {
+        use decoder::AudioDecoder;
+
+        let m4a = m4a_bytes(20);
+        let info = probe::probe_bytes(m4a.clone()).unwrap();
+        assert_eq!(
+            (info.container.as_str(), info.codec.as_str()),
+            ("mp4", "aac")
+        );
+        assert_eq!((info.sample_rate, info.channels), (44100, 1));
+
+        let decoder = AudioDecoder::from_data(m4a).unwrap();
+        assert_eq!(decoder.get_sample_rate(), 44100);
+        assert_eq!(decoder.get_channels(), 1);
+        let samples = decoder.decode_to_samples().unwrap().to_vec();
+        assert!(samples.len() >= 19 * 1024, "{} samples", samples.len());
+        assert!(samples.iter().all(|&s| s == 0));
+    }
+
+    #[test]
+    fn test_probe() {
+        use types::DecoderError;
+
+        let samples: Vec<i16> = (0..8000).map(|i| (i * 4) as i16).collect();
+        let info = probe::probe_bytes(wav_bytes(&samples, 8000, 1)).unwrap();
+        assert_eq!(info.container, "wav");
+        assert_eq!(info.codec, "pcm_s16le");
+        assert_eq!((info.sample_rate, info.channels), (8000, 1));
+        assert_eq!(info.bit_depth, Some(16));
+        assert_eq!(info.bitrate, Some(128_000));
+        assert_eq!(info.duration, Some(1.0));
+        assert!(info.seekable);
+        assert!(!info.is_vbr);
+
+        let code = |result: error::Result<probe::AudioProbe>| match result.unwrap_err().status {
+            ErrorCode::Decoder(code) => code,
+            other => panic!("unexpected error code {:?}", other),
+        };
+        assert_eq!(
+            code(probe::probe_bytes(Vec::new())),
+            DecoderError::EmptyInput
+        );
+        assert_eq!(
+            code(probe::probe_bytes(vec![7u8; 64])),
+            DecoderError::InvalidFormat
+        );
+        assert_eq!(
+            code(probe::probe_bytes(b"FORM\0\0\0\0AIFFCOMM".to_vec())),
+            DecoderError::UnsupportedFormat
+        );
+        let mut truncated = wav_bytes(&samples, 8000, 1);
+        truncated.truncate(30);
+        assert_eq!(
+            code(probe::probe_bytes(truncated)),
+            DecoderError::CorruptedData
+        );
+        assert_eq!(
+            code(probe::probe_file("/nonexistent/audio.wav")),
+            DecoderError::FileNotFound
+        );
+
+        // An ID3v2.4 tag with a footer, larger than the sniffed window, before silent MP3 frames
+        let tag_size: usize = 70 * 1024;
+        let syncsafe = [21, 14, 7, 0].map(|shift| ((tag_size >> shift) & 0x7F) as u8);
+        let mut mp3 = b"ID3\x04\x00\x10".to_vec();
+        mp3.extend_from_slice(&syncsafe);
+        mp3.resize(10 + tag_size, 0);
+        mp3.extend_from_slice(b"3DI\x04\x00\x10");
+        mp3.extend_from_slice(&syncsafe);
+        for _ in 0..20 {
+            // MPEG-1 Layer III, 128 kbps, 44.1 kHz: 417 byte frames
+            let mut frame = vec![0u8; 417];
+            frame[..4].copy_from_slice(&[0xFF, 0xFB, 0x90, 0x00]);
+            mp3.extend_from_slice(&frame);
+        }
+        let info = probe::probe_bytes(mp3.clone()).unwrap();
+        assert_eq!(info.container, "mp3");
+        assert_eq!((info.sample_rate, info.channels), (44100, 2));
+        let path = std::env::temp_dir().join("miniaudio_node_probe_id3.mp3");
+        std::fs::write(&path, &mp3).unwrap();
+        let info = probe::probe_file(path.to_str().unwrap());
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(info.unwrap().container, "mp3");
+    }
+
+    #[test]
+    fn test_decoder_builder_settings() {
+        use decoder::{AudioDecoder, DecoderBuilder};
+
+        let samples: Vec<i16> = (0..8000).map(|i| (i * 4) as i16).collect();
+        let wav = wav_bytes(&samples, 8000, 1);
+        let full = AudioDecoder::from_data(wav.clone())
+            .unwrap()
+            .decode_to_samples()
+            .unwrap()
+            .to_vec();
+
+        let mut builder = DecoderBuilder::new();
+        builder.set_sample_rate(16000);
+        builder.set_channels(2);
+        let decoder = builder.build_from_data(wav.clone()).unwrap();
+        assert_eq!(decoder.get_sample_rate(), 16000);
+        assert_eq!(decoder.get_channels(), 2);
+        let converted = decoder.decode_to_samples().unwrap();
+        // The resampler may drop a few frames of tail
+        assert!((converted.len() as i64 - 32000).abs() <= 32);
+        assert_eq!(converted[100], converted[101]);
+
+        builder.set_channels(0);
+        assert!(builder.build_from_data(wav.clone()).is_err());
+
+        // Loops are streamed pass by pass, with boundaries falling inside chunks
+        let mut builder = DecoderBuilder::new();
+        builder.set_loop_count(3);
+        assert_eq!(
+            builder
+                .build_looped_from_data(wav.clone())
+                .unwrap()
+                .get_loop_count(),
+            1
+        );
+        builder.set_loop_enabled(true);
+        let looped = builder.build_looped_from_data(wav).unwrap();
+        let mut streamed = Vec::new();
+        loop {
+            let chunk = looped.read(3000).unwrap();
+            if chunk.is_empty() {
+                break;
+            }
+            streamed.extend_from_slice(&chunk);
+        }
+        assert_eq!(streamed, full.repeat(3));
+        assert_eq!(looped.get_loops_played(), 3);
+        assert!(looped.next_chunk().unwrap().is_none());
+    }
+
     // Tests for seek_to function fixes
     #[test]
     fn test_seek_position_validation_nan() {
@@ -318,4 +1389,228 @@ mod tests {
         let skip_bytes2 = (position2 * bytes_per_second) as usize;
         assert_eq!(skip_bytes2, 88200);
     }
+
+    #[test]
+    fn test_push_stream() {
+        use std::sync::atomic::{AtomicU32, Ordering};
+        use std::sync::Arc;
+
+        // 1 ms = 2 samples at 1 kHz stereo
+        let options = stream::PushStreamOptions {
+            sample_rate: 1000,
+            channels: 2,
+            format: stream::PcmSampleFormat::F32,
+            buffer_ms: Some(100),
+            high_water_ms: Some(50),
+            prebuffer_ms: Some(10),
+        };
+        let callbacks = stream::PushCallbacks::default();
+        let drains = Arc::new(AtomicU32::new(0));
+        let ended = Arc::new(AtomicU32::new(0));
+        let d = drains.clone();
+        *callbacks.on_drain.lock().unwrap() = Some(Box::new(move |_| {
+            d.fetch_add(1, Ordering::SeqCst);
+        }));
+        let e = ended.clone();
+        *callbacks.on_ended.lock().unwrap() = Some(Box::new(move |_| {
+            e.fetch_add(1, Ordering::SeqCst);
+        }));
+        let (mut writer, mut source) = stream::push_channel(&options, callbacks).unwrap();
+
+        // Silence until the prebuffer is filled
+        assert_eq!(source.next(), Some(0.0));
+        assert_eq!(source.next(), Some(0.0));
+        assert!(writer.write_samples(&[0.5; 10]).unwrap());
+        assert_eq!(source.next(), Some(0.0));
+        assert_eq!(source.next(), Some(0.0));
+        assert!(writer.write_samples(&[0.5; 10]).unwrap());
+        assert_eq!(writer.buffered_ms(), 10.0);
+        assert!((0..20).all(|_| source.next() == Some(0.5)));
+
+        // Running dry is an underrun and goes back to buffering
+        assert_eq!(source.next(), Some(0.0));
+        assert_eq!(writer.underrun_count(), 1);
+
+        // Partial frames and overflowing writes are rejected
+        assert!(writer.write_samples(&[0.1; 3]).is_err());
+        assert!(writer.write_samples(&[0.1; 201]).is_err());
+
+        // Reaching the high-water mark applies backpressure until drained
+        assert!(!writer.write_samples(&[0.25; 100]).unwrap());
+        source.next();
+        assert!((0..49).all(|_| source.next() == Some(0.25)));
+        assert_eq!(drains.load(Ordering::SeqCst), 0);
+        source.next();
+        assert_eq!(drains.load(Ordering::SeqCst), 1);
+
+        // Ending plays out what is queued, then finishes
+        writer.end();
+        assert!(writer.write_samples(&[0.1; 2]).is_err());
+        assert_eq!(source.by_ref().count(), 50);
+        assert_eq!(ended.load(Ordering::SeqCst), 1);
+        assert_eq!(writer.underrun_count(), 1);
+    }
+
+    #[test]
+    fn test_stream_builder_config() {
+        use rodio::cpal::{
+            SampleFormat, SampleRate, SupportedBufferSize, SupportedStreamConfigRange,
+        };
+
+        let range = SupportedStreamConfigRange::new(
+            2,
+            SampleRate(44100),
+            SampleRate(48000),
+            SupportedBufferSize::Range { min: 64, max: 4096 },
+            SampleFormat::F32,
+        );
+        assert!(stream::config_range_supports(&range, 48000, 2, None));
+        assert!(stream::config_range_supports(&range, 44100, 2, Some(256)));
+        assert!(!stream::config_range_supports(&range, 96000, 2, None));
+        assert!(!stream::config_range_supports(&range, 48000, 1, None));
+        assert!(!stream::config_range_supports(&range, 48000, 2, Some(32)));
+
+        // Invalid settings are rejected before any device is touched
+        let mut builder = stream::AudioStreamBuilder::new();
+        builder.set_config(types::StreamOutputConfig {
+            sample_rate: Some(48000),
+            channels: Some(0),
+            buffer_size: None,
+        });
+        assert!(builder.build().is_err());
+    }
+
+    #[test]
+    fn test_stream_state_tracking() {
+        use std::sync::{Arc, Mutex};
+        use types::StreamState;
+
+        let tracker = stream::StreamStateTracker::new();
+        let changes = Arc::new(Mutex::new(Vec::new()));
+        let c = changes.clone();
+        *tracker.on_change.lock().unwrap() = Some(Box::new(move |state| {
+            c.lock().unwrap().push(state);
+        }));
+        assert_eq!(tracker.get(), StreamState::Idle);
+
+        // The stream only ends once every queued source was played out
+        tracker.source_queued();
+        tracker.source_queued();
+        tracker.source_finished();
+        assert_eq!(tracker.get(), StreamState::Playing);
+        tracker.pause();
+        tracker.resume();
+        tracker.source_finished();
+        assert_eq!(tracker.get(), StreamState::Ended);
+        tracker.source_finished();
+
+        // Ending a push stream drains it, pausing keeps the draining state
+        tracker.source_queued();
+        tracker.end_input();
+        tracker.pause();
+        tracker.resume();
+        assert_eq!(tracker.get(), StreamState::Draining);
+        tracker.source_finished();
+        tracker.reset();
+
+        assert_eq!(
+            *changes.lock().unwrap(),
+            vec![
+                StreamState::Playing,
+                StreamState::Paused,
+                StreamState::Playing,
+                StreamState::Ended,
+                StreamState::Playing,
+                StreamState::Draining,
+                StreamState::Paused,
+                StreamState::Draining,
+                StreamState::Ended,
+                StreamState::Idle,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_recoverable_failures() {
+        use error::LockExt;
+        use napi::bindgen_prelude::{Int16Array, Int32Array};
+        use std::sync::{Arc, Mutex};
+
+        // A panic while holding a lock must not take later callers down
+        let shared = Arc::new(Mutex::new(1));
+        let s = shared.clone();
+        let _ = std::thread::spawn(move || {
+            let _guard = s.lock().unwrap();
+            panic!("poison");
+        })
+        .join();
+        assert!(shared.is_poisoned());
+        assert_eq!(*shared.lock_or_recover(), 1);
+
+        // Degenerate converter settings produce no output instead of panicking
+        let samples = vec![1i16, 2, 3, 4];
+        assert!(conversions::ChannelCountConverter::new(0, 2)
+            .convert(Int16Array::new(samples.clone()))
+            .is_empty());
+        assert!(conversions::SampleRateConverter::new(44100, 0)
+            .convert(Int16Array::new(samples))
+            .is_empty());
+        assert_eq!(
+            &*conversions::SampleTypeConverter::new(8, 16).convert(Int32Array::new(vec![i32::MIN])),
+            &[i32::MIN]
+        );
+
+        // Losing the device fails the stream, other errors are transient
+        let tracker = stream::StreamStateTracker::new();
+        tracker.source_queued();
+        engine::DeviceErrorListener::stream_error(&tracker, error::stream_failure(false, "xrun"));
+        assert_eq!(tracker.get(), types::StreamState::Playing);
+        let err = error::stream_failure(true, "gone");
+        assert_eq!(err.status.as_ref(), "DeviceLost");
+        engine::DeviceErrorListener::stream_error(&tracker, err);
+        assert_eq!(tracker.get(), types::StreamState::Error);
+    }
+
+    #[test]
+    fn test_engine_voices() {
+        use engine::AudioEngine;
+        use rodio::Source;
+
+        let before = AudioEngine::get_active_voices();
+        let tone =
+            rodio::source::SineWave::new(440.0).take_duration(std::time::Duration::from_millis(1));
+        let mut voice = engine::voice(tone);
+        assert_eq!(AudioEngine::get_active_voices(), before + 1);
+
+        // The wrapper passes the source through untouched
+        assert_eq!(voice.sample_rate(), 48000);
+        assert_eq!(voice.by_ref().count(), 48);
+        drop(voice);
+        assert_eq!(AudioEngine::get_active_voices(), before);
+
+        AudioEngine::shutdown();
+        assert!(!AudioEngine::is_initialized());
+        assert!(AudioEngine::get_open_devices().is_empty());
+    }
+
+    #[test]
+    fn test_playback_ended() {
+        use std::future::Future;
+        use std::sync::Arc;
+        use std::task::{Context, Poll, Waker};
+
+        let shared = Arc::new(engine::PlaybackShared::default());
+        let mut ended = Box::pin(engine::PlaybackEnded(shared.clone()));
+        let mut cx = Context::from_waker(Waker::noop());
+
+        assert!(ended.as_mut().poll(&mut cx).is_pending());
+        assert!(!shared.is_finished());
+
+        // Finishing (end of source or stop) resolves every waiting promise
+        shared.finish();
+        assert!(shared.is_finished());
+        assert!(matches!(ended.as_mut().poll(&mut cx), Poll::Ready(Ok(()))));
+        let mut late = Box::pin(engine::PlaybackEnded(shared));
+        assert!(matches!(late.as_mut().poll(&mut cx), Poll::Ready(Ok(()))));
+    }
 }
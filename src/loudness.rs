@@ -0,0 +1,407 @@
+//! Loudness measurement per ITU-R BS.1770-4 / EBU R128 with 4x oversampled true peak
+
+use crate::decoder::CANCEL_CHECK_INTERVAL;
+use crate::error::Result;
+use crate::types::CancelToken;
+use napi_derive::napi;
+use std::collections::VecDeque;
+use std::f64::consts::PI;
+use std::sync::OnceLock;
+
+/// Gating blocks advance in 100 ms steps
+const SUBBLOCKS_PER_SECOND: u32 = 10;
+/// Momentary loudness window (400 ms)
+const MOMENTARY_SUBBLOCKS: usize = 4;
+/// Short-term loudness window (3 s)
+const SHORT_TERM_SUBBLOCKS: usize = 30;
+/// Blocks quieter than this never count towards integrated loudness or LRA
+const ABSOLUTE_GATE_LUFS: f64 = -70.0;
+const INTEGRATED_RELATIVE_GATE_LU: f64 = -10.0;
+const RANGE_RELATIVE_GATE_LU: f64 = -20.0;
+/// Gated blocks are binned at 0.1 LU between the absolute gate and +5 LUFS, as in libebur128
+const HISTOGRAM_BINS_PER_LU: f64 = 10.0;
+const HISTOGRAM_BINS: usize = 750;
+/// True peak interpolation: 4x oversampling with a 48-tap windowed-sinc filter
+const OVERSAMPLING: usize = 4;
+const TAPS_PER_PHASE: usize = 12;
+
+static FILTER: OnceLock<[[f64; TAPS_PER_PHASE]; OVERSAMPLING]> = OnceLock::new();
+
+/// Loudness statistics: levels in LUFS, range in LU, peaks in dBFS/dBTP.
+/// Levels are `-Infinity` until enough audio above the gates was measured.
+#[napi(object)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoudnessStats {
+    /// Gated loudness of everything measured so far
+    pub integrated: f64,
+    /// Loudness of the latest 400 ms
+    pub momentary: f64,
+    /// Loudness of the latest 3 s
+    pub short_term: f64,
+    pub max_momentary: f64,
+    pub max_short_term: f64,
+    /// Loudness range (LRA) between the 10th and 95th percentile of short-term loudness
+    pub loudness_range: f64,
+    /// Highest inter-sample peak, in dBTP
+    pub true_peak: f64,
+    /// Highest sample value, in dBFS
+    pub sample_peak: f64,
+}
+
+/// Streaming BS.1770 meter fed with interleaved samples in [-1, 1]
+pub struct LoudnessMeter {
+    channels: usize,
+    weights: Vec<f64>,
+    filters: Vec<KWeighting>,
+    peaks: Vec<TruePeak>,
+    // Channel the next sample belongs to, so frames may span calls
+    next_channel: usize,
+    subblock_frames: usize,
+    filled_frames: usize,
+    subblock_sum: Vec<f64>,
+    // Energies of the latest sub-blocks, enough for a short-term window
+    recent: VecDeque<f64>,
+    // Every 400 ms gating block and 3 s short-term window above the absolute gate
+    blocks: EnergyHistogram,
+    short_terms: EnergyHistogram,
+    max_momentary: f64,
+    max_short_term: f64,
+    sample_peak: f64,
+}
+
+impl LoudnessMeter {
+    pub fn new(sample_rate: u32, channels: u16) -> Self {
+        let channels = channels.max(1) as usize;
+        let sample_rate = sample_rate.max(1);
+        Self {
+            channels,
+            weights: (0..channels)
+                .map(|ch| channel_weight(ch, channels))
+                .collect(),
+            filters: (0..channels)
+                .map(|_| KWeighting::new(sample_rate))
+                .collect(),
+            peaks: (0..channels).map(|_| TruePeak::new()).collect(),
+            next_channel: 0,
+            subblock_frames: (sample_rate / SUBBLOCKS_PER_SECOND).max(1) as usize,
+            filled_frames: 0,
+            subblock_sum: vec![0.0; channels],
+            recent: VecDeque::with_capacity(SHORT_TERM_SUBBLOCKS),
+            blocks: EnergyHistogram::new(),
+            short_terms: EnergyHistogram::new(),
+            max_momentary: f64::NEG_INFINITY,
+            max_short_term: f64::NEG_INFINITY,
+            sample_peak: 0.0,
+        }
+    }
+
+    /// Measure interleaved samples
+    pub fn add_samples<I: IntoIterator<Item = f32>>(&mut self, samples: I) {
+        for sample in samples {
+            let ch = self.next_channel;
+            let sample = sample as f64;
+            self.sample_peak = self.sample_peak.max(sample.abs());
+            self.peaks[ch].add(sample);
+            let weighted = self.filters[ch].process(sample);
+            self.subblock_sum[ch] += weighted * weighted;
+
+            self.next_channel += 1;
+            if self.next_channel == self.channels {
+                self.next_channel = 0;
+                self.filled_frames += 1;
+                if self.filled_frames == self.subblock_frames {
+                    self.finish_subblock();
+                }
+            }
+        }
+    }
+
+    pub fn stats(&self) -> LoudnessStats {
+        LoudnessStats {
+            integrated: loudness(self.blocks.gated_mean(INTEGRATED_RELATIVE_GATE_LU)),
+            momentary: loudness(self.window_energy(MOMENTARY_SUBBLOCKS)),
+            short_term: loudness(self.window_energy(SHORT_TERM_SUBBLOCKS)),
+            max_momentary: self.max_momentary,
+            max_short_term: self.max_short_term,
+            loudness_range: self.loudness_range(),
+            true_peak: to_db(
+                self.peaks
+                    .iter()
+                    .fold(self.sample_peak, |peak, tp| peak.max(tp.peak)),
+            ),
+            sample_peak: to_db(self.sample_peak),
+        }
+    }
+
+    fn finish_subblock(&mut self) {
+        let frames = self.filled_frames as f64;
+        let energy = self
+            .subblock_sum
+            .iter_mut()
+            .zip(&self.weights)
+            .map(|(sum, weight)| weight * std::mem::take(sum) / frames)
+            .sum();
+        self.filled_frames = 0;
+
+        if self.recent.len() == SHORT_TERM_SUBBLOCKS {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(energy);
+
+        let momentary = self.window_energy(MOMENTARY_SUBBLOCKS);
+        if momentary.is_finite() {
+            self.blocks.add(momentary);
+            self.max_momentary = self.max_momentary.max(loudness(momentary));
+        }
+        let short_term = self.window_energy(SHORT_TERM_SUBBLOCKS);
+        if short_term.is_finite() {
+            self.short_terms.add(short_term);
+            self.max_short_term = self.max_short_term.max(loudness(short_term));
+        }
+    }
+
+    /// Mean energy of the latest `subblocks`, or NaN before the window is full
+    fn window_energy(&self, subblocks: usize) -> f64 {
+        if self.recent.len() < subblocks {
+            return f64::NAN;
+        }
+        self.recent.iter().rev().take(subblocks).sum::<f64>() / subblocks as f64
+    }
+
+    /// EBU Tech 3342: spread of the gated short-term loudness distribution
+    fn loudness_range(&self) -> f64 {
+        let histogram = &self.short_terms;
+        let first = histogram.gate_bin(RANGE_RELATIVE_GATE_LU);
+        let total: u64 = histogram.counts[first..].iter().sum();
+        if total == 0 {
+            return 0.0;
+        }
+        // Loudness of the bin holding the block at `p` of the gated, sorted distribution
+        let percentile = |p: f64| {
+            let rank = ((total - 1) as f64 * p).round() as u64;
+            let mut seen = 0;
+            for bin in first..HISTOGRAM_BINS {
+                seen += histogram.counts[bin];
+                if seen > rank {
+                    return loudness(histogram.sums[bin] / histogram.counts[bin] as f64);
+                }
+            }
+            f64::NEG_INFINITY
+        };
+        percentile(0.95) - percentile(0.10)
+    }
+}
+
+/// Measure interleaved samples in one pass, polling `cancel` between blocks
+pub(crate) fn measure<I: Iterator<Item = f32>>(
+    samples: I,
+    sample_rate: u32,
+    channels: u16,
+    cancel: &CancelToken,
+) -> Result<LoudnessStats> {
+    let mut meter = LoudnessMeter::new(sample_rate, channels);
+    let mut samples = samples.peekable();
+    while samples.peek().is_some() {
+        cancel.check()?;
+        meter.add_samples(samples.by_ref().take(CANCEL_CHECK_INTERVAL));
+    }
+    cancel.check()?;
+    Ok(meter.stats())
+}
+
+/// Counts and summed energies of blocks above the absolute gate, in 0.1 LU bins, so memory
+/// and gating cost stay constant however long the meter runs
+struct EnergyHistogram {
+    counts: Vec<u64>,
+    sums: Vec<f64>,
+}
+
+impl EnergyHistogram {
+    fn new() -> Self {
+        Self {
+            counts: vec![0; HISTOGRAM_BINS],
+            sums: vec![0.0; HISTOGRAM_BINS],
+        }
+    }
+
+    fn add(&mut self, energy: f64) {
+        let level = loudness(energy);
+        if level > ABSOLUTE_GATE_LUFS {
+            let bin = Self::bin(level);
+            self.counts[bin] += 1;
+            self.sums[bin] += energy;
+        }
+    }
+
+    fn bin(level: f64) -> usize {
+        (((level - ABSOLUTE_GATE_LUFS) * HISTOGRAM_BINS_PER_LU) as usize).min(HISTOGRAM_BINS - 1)
+    }
+
+    /// First bin at or above a gate `relative_lu` below the mean of all blocks
+    fn gate_bin(&self, relative_lu: f64) -> usize {
+        let count: u64 = self.counts.iter().sum();
+        if count == 0 {
+            return 0;
+        }
+        let mean = self.sums.iter().sum::<f64>() / count as f64;
+        let gate = loudness(mean) + relative_lu;
+        if gate > ABSOLUTE_GATE_LUFS {
+            Self::bin(gate)
+        } else {
+            0
+        }
+    }
+
+    /// Mean energy of the blocks passing the relative gate, or 0 if none do
+    fn gated_mean(&self, relative_lu: f64) -> f64 {
+        let first = self.gate_bin(relative_lu);
+        let count: u64 = self.counts[first..].iter().sum();
+        if count == 0 {
+            0.0
+        } else {
+            self.sums[first..].iter().sum::<f64>() / count as f64
+        }
+    }
+}
+
+/// Loudness in LUFS of a weighted mean square; NaN and silence map to -Infinity
+fn loudness(energy: f64) -> f64 {
+    if energy > 0.0 {
+        -0.691 + 10.0 * energy.log10()
+    } else {
+        f64::NEG_INFINITY
+    }
+}
+
+fn to_db(amplitude: f64) -> f64 {
+    20.0 * amplitude.log10()
+}
+
+/// BS.1770 channel weights: surrounds of a 5.1 layout count +1.5 dB and the LFE is skipped
+fn channel_weight(channel: usize, channels: usize) -> f64 {
+    match (channels, channel) {
+        (6, 3) => 0.0,
+        (6, 4 | 5) => 1.41,
+        _ => 1.0,
+    }
+}
+
+/// K-weighting: the BS.1770 high-shelf pre-filter followed by the RLB high-pass,
+/// with coefficients derived for any sample rate
+struct KWeighting {
+    stages: [Biquad; 2],
+}
+
+impl KWeighting {
+    fn new(sample_rate: u32) -> Self {
+        let rate = sample_rate as f64;
+
+        let k = (PI * 1681.974450955533 / rate).tan();
+        let q = 0.7071752369554196;
+        let vh = 10f64.powf(3.999843853973347 / 20.0);
+        let vb = vh.powf(0.4996667741545416);
+        let a0 = 1.0 + k / q + k * k;
+        let shelf = Biquad::new(
+            [
+                (vh + vb * k / q + k * k) / a0,
+                2.0 * (k * k - vh) / a0,
+                (vh - vb * k / q + k * k) / a0,
+            ],
+            [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        );
+
+        let k = (PI * 38.13547087602444 / rate).tan();
+        let q = 0.5003270373238773;
+        let a0 = 1.0 + k / q + k * k;
+        let high_pass = Biquad::new(
+            [1.0, -2.0, 1.0],
+            [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        );
+
+        Self {
+            stages: [shelf, high_pass],
+        }
+    }
+
+    fn process(&mut self, sample: f64) -> f64 {
+        self.stages
+            .iter_mut()
+            .fold(sample, |x, stage| stage.process(x))
+    }
+}
+
+/// Direct form II transposed biquad
+struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+    z: [f64; 2],
+}
+
+impl Biquad {
+    fn new(b: [f64; 3], a: [f64; 2]) -> Self {
+        Self { b, a, z: [0.0; 2] }
+    }
+
+    fn process(&mut self, x: f64) -> f64 {
+        let y = self.b[0] * x + self.z[0];
+        self.z[0] = self.b[1] * x - self.a[0] * y + self.z[1];
+        self.z[1] = self.b[2] * x - self.a[1] * y;
+        y
+    }
+}
+
+/// Inter-sample peak detector using polyphase interpolation
+struct TruePeak {
+    history: [f64; TAPS_PER_PHASE],
+    // Index of the newest sample in `history`
+    newest: usize,
+    peak: f64,
+}
+
+impl TruePeak {
+    fn new() -> Self {
+        Self {
+            history: [0.0; TAPS_PER_PHASE],
+            newest: 0,
+            peak: 0.0,
+        }
+    }
+
+    fn add(&mut self, sample: f64) {
+        self.newest = (self.newest + 1) % TAPS_PER_PHASE;
+        self.history[self.newest] = sample;
+        for phase in interpolation_filter() {
+            let y: f64 = phase
+                .iter()
+                .enumerate()
+                .map(|(j, h)| h * self.history[(self.newest + TAPS_PER_PHASE - j) % TAPS_PER_PHASE])
+                .sum();
+            self.peak = self.peak.max(y.abs());
+        }
+    }
+}
+
+/// Hann-windowed sinc low-pass at the original Nyquist frequency, split into phases
+fn interpolation_filter() -> &'static [[f64; TAPS_PER_PHASE]; OVERSAMPLING] {
+    FILTER.get_or_init(|| {
+        let taps = TAPS_PER_PHASE * OVERSAMPLING;
+        let center = (taps - 1) as f64 / 2.0;
+        let mut phases = [[0.0; TAPS_PER_PHASE]; OVERSAMPLING];
+        for n in 0..taps {
+            let t = (n as f64 - center) / OVERSAMPLING as f64;
+            let sinc = if t == 0.0 {
+                1.0
+            } else {
+                (PI * t).sin() / (PI * t)
+            };
+            let window = 0.5 - 0.5 * (2.0 * PI * (n as f64 + 1.0) / (taps as f64 + 1.0)).cos();
+            phases[n % OVERSAMPLING][n / OVERSAMPLING] = sinc * window;
+        }
+        // Unity gain for every phase
+        for phase in &mut phases {
+            let sum: f64 = phase.iter().sum();
+            phase.iter_mut().for_each(|h| *h /= sum);
+        }
+        phases
+    })
+}
@@ -1,8 +1,329 @@
 //! Audio mixer - blend multiple audio sources together
 
-use napi::{Error, Result, Status};
+use crate::conversions::pcm_to_i16;
+use crate::error::{error, AudioTask, CodedTask, ErrorCode, LockExt, Result};
+use crate::types::{CancelToken, PlayError};
+use napi::bindgen_prelude::{AbortSignal, AsyncTask, Either, Float32Array, Int16Array};
+use napi::threadsafe_function::{ThreadsafeFunction, ThreadsafeFunctionCallMode};
+use napi::Env;
 use napi_derive::napi;
+use rodio::{Sink, Source as RodioSource};
+use std::collections::BTreeMap;
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Callback type for mixer level updates
+type OnLevelsCallback = Arc<dyn Fn(MixerLevels) + Send + Sync>;
+
+const DEFAULT_LEVELS_INTERVAL_MS: u32 = 50;
+const RENDER_BLOCK_FRAMES: usize = 512;
+const I16_MAX_F32: f32 = 32768.0;
+/// Positive full scale of i16 audio, the loudest level that is not clipped
+const CLIP_LEVEL: f32 = 32767.0 / 32768.0;
+const MASTER_METER_ID: &str = "master";
+
+/// Peak, RMS and clip reading for a source, a bus or the master output
+#[napi(object)]
+#[derive(Clone)]
+pub struct MixerMeterLevels {
+    pub id: String,
+    pub peak: f64,
+    pub rms: f64,
+    /// Latched when a sample reached full scale, until `reset_levels` is called
+    pub clipped: bool,
+}
+
+/// Snapshot of every meter in a mixer
+#[napi(object)]
+pub struct MixerLevels {
+    pub master: MixerMeterLevels,
+    pub sources: Vec<MixerMeterLevels>,
+    pub buses: Vec<MixerMeterLevels>,
+}
+
+/// Accumulates peak and RMS over a metering window
+#[derive(Default)]
+struct LevelMeter {
+    peak: f32,
+    sum_sq: f64,
+    count: u64,
+    clipped: bool,
+    last_peak: f64,
+    last_rms: f64,
+}
+
+impl LevelMeter {
+    fn accumulate(&mut self, samples: &[f32]) {
+        for &sample in samples {
+            let abs_sample = sample.abs();
+            if abs_sample > self.peak {
+                self.peak = abs_sample;
+            }
+            self.sum_sq += (sample as f64) * (sample as f64);
+        }
+        self.count += samples.len() as u64;
+        if self.peak >= CLIP_LEVEL {
+            self.clipped = true;
+        }
+    }
+
+    /// Close the current window and make its values the readable levels
+    fn publish(&mut self) {
+        self.last_peak = self.peak as f64;
+        self.last_rms = if self.count > 0 {
+            (self.sum_sq / self.count as f64).sqrt()
+        } else {
+            0.0
+        };
+        self.peak = 0.0;
+        self.sum_sq = 0.0;
+        self.count = 0;
+    }
+
+    fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    fn levels(&self, id: &str) -> MixerMeterLevels {
+        MixerMeterLevels {
+            id: id.to_string(),
+            peak: self.last_peak,
+            rms: self.last_rms,
+            clipped: self.clipped,
+        }
+    }
+}
+
+/// Meter state shared between a mixer and its real-time output
+#[derive(Clone)]
+struct MixerMeters {
+    master: Arc<Mutex<LevelMeter>>,
+    buses: Arc<Mutex<BTreeMap<String, LevelMeter>>>,
+    on_levels: Arc<Mutex<Option<OnLevelsCallback>>>,
+    interval_ms: Arc<Mutex<u32>>,
+    pending_frames: Arc<Mutex<u64>>,
+}
+
+impl MixerMeters {
+    fn new() -> Self {
+        Self {
+            master: Arc::new(Mutex::new(LevelMeter::default())),
+            buses: Arc::new(Mutex::new(BTreeMap::new())),
+            on_levels: Arc::new(Mutex::new(None)),
+            interval_ms: Arc::new(Mutex::new(DEFAULT_LEVELS_INTERVAL_MS)),
+            pending_frames: Arc::new(Mutex::new(0)),
+        }
+    }
+
+    fn snapshot(&self, sources: &[MixerSource]) -> MixerLevels {
+        MixerLevels {
+            master: self.master.lock_or_recover().levels(MASTER_METER_ID),
+            sources: sources.iter().map(|s| s.get_levels()).collect(),
+            buses: self
+                .buses
+                .lock_or_recover()
+                .iter()
+                .map(|(bus, meter)| meter.levels(bus))
+                .collect(),
+        }
+    }
+}
+
+/// Mix `frames` frames of every enabled source starting at `start_frame`,
+/// feeding the source, bus and master meters along the way
+fn render_mix(
+    sources: &Arc<Mutex<Vec<MixerSource>>>,
+    volume: &Arc<Mutex<f32>>,
+    meters: &MixerMeters,
+    sample_rate: u32,
+    channels: u16,
+    start_frame: u64,
+    frames: usize,
+) -> Vec<f32> {
+    let channels = channels.max(1) as usize;
+    let mut master = vec![0.0f32; frames * channels];
+    let mut scratch = vec![0.0f32; frames * channels];
+    let mut buses: BTreeMap<String, Vec<f32>> = BTreeMap::new();
+
+    let sources = sources.lock_or_recover();
+    for source in sources.iter().filter(|s| s.is_enabled()) {
+        source.render_into(start_frame, sample_rate, channels, &mut scratch);
+        source.meter.lock_or_recover().accumulate(&scratch);
+
+        if let Some(bus) = source.get_bus() {
+            let bus_buffer = buses
+                .entry(bus)
+                .or_insert_with(|| vec![0.0; frames * channels]);
+            for (out, &s) in bus_buffer.iter_mut().zip(&scratch) {
+                *out += s;
+            }
+        }
+
+        for (out, &s) in master.iter_mut().zip(&scratch) {
+            *out += s;
+        }
+    }
+
+    {
+        let mut bus_meters = meters.buses.lock_or_recover();
+        for (bus, buffer) in &buses {
+            bus_meters
+                .entry(bus.clone())
+                .or_default()
+                .accumulate(buffer);
+        }
+    }
+
+    let master_volume = *volume.lock_or_recover();
+    for s in master.iter_mut() {
+        *s *= master_volume;
+    }
+    meters.master.lock_or_recover().accumulate(&master);
+    for s in master.iter_mut() {
+        *s = s.clamp(-1.0, 1.0);
+    }
+
+    // Close the metering window once enough audio has been rendered
+    let interval_frames =
+        (*meters.interval_ms.lock_or_recover() as u64 * sample_rate as u64) / 1000;
+    let mut pending = meters.pending_frames.lock_or_recover();
+    *pending += frames as u64;
+    if *pending >= interval_frames.max(1) {
+        *pending = 0;
+        for source in sources.iter() {
+            source.meter.lock_or_recover().publish();
+        }
+        for meter in meters.buses.lock_or_recover().values_mut() {
+            meter.publish();
+        }
+        meters.master.lock_or_recover().publish();
+
+        // Copy everything out so the callback runs without any meter lock held
+        let levels = meters.snapshot(&sources);
+        drop(pending);
+        drop(sources);
+        let callback = meters.on_levels.lock_or_recover().clone();
+        if let Some(cb) = callback {
+            cb(levels);
+        }
+    }
+
+    master
+}
+
+fn to_i16(sample: f32) -> i16 {
+    (sample * I16_MAX_F32).clamp(i16::MIN as f32, i16::MAX as f32) as i16
+}
+
+impl Mixer {
+    pub(crate) fn render_task(&self, time_ms: u32, frames: u32, cancel: CancelToken) -> RenderTask {
+        RenderTask {
+            sources: self.sources.clone(),
+            volume: self.volume.clone(),
+            meters: self.meters.clone(),
+            sample_rate: self.sample_rate,
+            channels: self.channels,
+            start_frame: time_ms as u64 * self.sample_rate as u64 / 1000,
+            frames: frames as usize,
+            cancel,
+        }
+    }
+}
+
+/// Background offline render of a mixer, one block at a time
+pub struct RenderTask {
+    sources: Arc<Mutex<Vec<MixerSource>>>,
+    volume: Arc<Mutex<f32>>,
+    meters: MixerMeters,
+    sample_rate: u32,
+    channels: u16,
+    start_frame: u64,
+    frames: usize,
+    cancel: CancelToken,
+}
+
+impl AudioTask for RenderTask {
+    type Output = Vec<i16>;
+    type JsValue = Int16Array;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        let mut output = Vec::with_capacity(self.frames * self.channels.max(1) as usize);
+        let mut rendered = 0;
+        while rendered < self.frames {
+            self.cancel.check()?;
+            let block = (self.frames - rendered).min(RENDER_BLOCK_FRAMES);
+            let mixed = render_mix(
+                &self.sources,
+                &self.volume,
+                &self.meters,
+                self.sample_rate,
+                self.channels,
+                self.start_frame + rendered as u64,
+                block,
+            );
+            output.extend(mixed.into_iter().map(to_i16));
+            rendered += block;
+        }
+        Ok(output)
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+        Ok(Int16Array::new(output))
+    }
+}
+
+/// Real-time rodio source that pulls blocks from the mixer
+struct MixerOutput {
+    sources: Arc<Mutex<Vec<MixerSource>>>,
+    volume: Arc<Mutex<f32>>,
+    meters: MixerMeters,
+    sample_rate: u32,
+    channels: u16,
+    position: u64,
+    block: Vec<f32>,
+    index: usize,
+}
+
+impl Iterator for MixerOutput {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index >= self.block.len() {
+            self.block = render_mix(
+                &self.sources,
+                &self.volume,
+                &self.meters,
+                self.sample_rate,
+                self.channels,
+                self.position,
+                RENDER_BLOCK_FRAMES,
+            );
+            self.position += RENDER_BLOCK_FRAMES as u64;
+            self.index = 0;
+        }
+        let sample = self.block[self.index];
+        self.index += 1;
+        Some(sample)
+    }
+}
+
+impl RodioSource for MixerOutput {
+    fn current_span_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
 
 /// A mixer that combines multiple audio sources into a single output stream
 #[napi]
@@ -12,6 +333,8 @@ pub struct Mixer {
     sample_rate: u32,
     channels: u16,
     volume: Arc<Mutex<f32>>,
+    meters: MixerMeters,
+    sink: Option<Sink>,
 }
 
 impl Default for Mixer {
@@ -37,16 +360,18 @@ impl Mixer {
             sample_rate,
             channels,
             volume: Arc::new(Mutex::new(1.0)),
+            meters: MixerMeters::new(),
+            sink: None,
         }
     }
 
     /// Add an audio source to the mixer
     #[napi]
     pub fn add_source(&self, source: &MixerSource) -> Result<()> {
-        let mut sources = self.sources.lock().unwrap();
+        let mut sources = self.sources.lock_or_recover();
         if sources.len() >= self.max_sources {
-            return Err(Error::new(
-                Status::GenericFailure,
+            return Err(error(
+                ErrorCode::InvalidArgument,
                 format!("Mixer at capacity (max {} sources)", self.max_sources),
             ));
         }
@@ -57,40 +382,40 @@ impl Mixer {
     /// Remove a source by its ID
     #[napi]
     pub fn remove_source(&self, source_id: String) -> Result<()> {
-        let mut sources = self.sources.lock().unwrap();
+        let mut sources = self.sources.lock_or_recover();
         if let Some(pos) = sources.iter().position(|s| s.id == source_id) {
             sources.remove(pos);
             Ok(())
         } else {
-            Err(Error::new(Status::InvalidArg, "Source not found"))
+            Err(error(ErrorCode::InvalidArgument, "Source not found"))
         }
     }
 
     /// Get all current sources
     #[napi]
     pub fn get_sources(&self) -> Vec<MixerSource> {
-        self.sources.lock().unwrap().to_vec()
+        self.sources.lock_or_recover().to_vec()
     }
 
     /// Get the number of sources
     #[napi]
     pub fn get_source_count(&self) -> u32 {
-        self.sources.lock().unwrap().len() as u32
+        self.sources.lock_or_recover().len() as u32
     }
 
     /// Clear all sources
     #[napi]
     pub fn clear(&self) {
-        self.sources.lock().unwrap().clear();
+        self.sources.lock_or_recover().clear();
     }
 
     /// Mix all sources at a specific time point (synchronous operation)
     /// Returns a buffer of mixed samples
     #[napi]
-    pub fn sample_at(&self, time_ms: u32) -> Result<Vec<i16>> {
-        let sources = self.sources.lock().unwrap();
+    pub fn sample_at(&self, time_ms: u32) -> Result<Int16Array> {
+        let sources = self.sources.lock_or_recover();
         if sources.is_empty() {
-            return Ok(vec![]);
+            return Ok(Int16Array::new(vec![]));
         }
 
         let mut mixed: Vec<i32> = vec![0; self.channels as usize];
@@ -108,7 +433,7 @@ impl Mixer {
         }
 
         if count == 0 {
-            return Ok(vec![0; self.channels as usize]);
+            return Ok(Int16Array::new(vec![0; self.channels as usize]));
         }
 
         // Normalize to prevent clipping
@@ -117,24 +442,150 @@ impl Mixer {
             .map(|&s| (s / count).clamp(-32768, 32767) as i16)
             .collect();
 
-        Ok(output)
+        Ok(Int16Array::new(output))
     }
 
-    /// Start mixing multiple sources in real-time (simulated)
+    /// Render a block of mixed samples starting at `time_ms`, updating the level meters.
+    /// Sources are scaled by their volume and pan and summed; the result is scaled by the
+    /// master volume and clamped to full scale.
     #[napi]
-    pub fn start_mixing(&self) -> Result<()> {
-        // In a real implementation, this would start an audio thread
-        // For now, we just verify we have something to mix
-        let sources = self.sources.lock().unwrap();
-        if sources.is_empty() {
-            return Err(Error::new(Status::InvalidArg, "No sources to mix"));
+    pub fn render_block(&self, time_ms: u32, frames: u32) -> Int16Array {
+        let start_frame = time_ms as u64 * self.sample_rate as u64 / 1000;
+        let mixed = render_mix(
+            &self.sources,
+            &self.volume,
+            &self.meters,
+            self.sample_rate,
+            self.channels,
+            start_frame,
+            frames as usize,
+        );
+        Int16Array::new(mixed.into_iter().map(to_i16).collect())
+    }
+
+    /// Render a block like `renderBlock` on a worker thread, so long offline
+    /// renders do not stall the event loop. Rejects once `signal` aborts.
+    #[napi(ts_return_type = "Promise<Int16Array>")]
+    pub fn render_block_async(
+        &self,
+        time_ms: u32,
+        frames: u32,
+        signal: Option<AbortSignal>,
+    ) -> AsyncTask<CodedTask<RenderTask>> {
+        let cancel = CancelToken::from_signal(signal.as_ref());
+        CodedTask::spawn(self.render_task(time_ms, frames, cancel), signal)
+    }
+
+    /// Start mixing all sources in real-time on the default output device
+    #[napi]
+    pub fn start_mixing(&mut self) -> Result<()> {
+        if self.sources.lock_or_recover().is_empty() {
+            return Err(error(PlayError::NotLoaded, "No sources to mix"));
+        }
+        if self.sink.is_some() {
+            return Ok(());
         }
+
+        let (sink, _) = crate::engine::connect_sink(None, None)?;
+        sink.append(crate::engine::voice(MixerOutput {
+            sources: self.sources.clone(),
+            volume: self.volume.clone(),
+            meters: self.meters.clone(),
+            sample_rate: self.sample_rate,
+            channels: self.channels,
+            position: 0,
+            block: Vec::new(),
+            index: 0,
+        }));
+        sink.play();
+
+        self.sink = Some(sink);
         Ok(())
     }
 
     /// Stop all mixing
     #[napi]
-    pub fn stop_mixing(&self) {}
+    pub fn stop_mixing(&mut self) {
+        if let Some(sink) = self.sink.take() {
+            sink.stop();
+        }
+    }
+
+    /// Check if the mixer is currently playing in real-time
+    #[napi]
+    pub fn is_mixing(&self) -> bool {
+        self.sink.is_some()
+    }
+
+    /// Set callback for level updates, fired once per metering window
+    ///
+    /// # Arguments
+    /// * `callback` - Receives the levels of every source, bus and the master output
+    /// * `interval_ms` - Metering window length in milliseconds (default: 50)
+    #[napi]
+    pub fn set_on_levels(
+        &self,
+        callback: ThreadsafeFunction<MixerLevels>,
+        interval_ms: Option<u32>,
+    ) -> Result<()> {
+        let cb = Arc::new(move |levels: MixerLevels| {
+            callback.call(
+                Ok::<_, napi::Error>(levels),
+                ThreadsafeFunctionCallMode::NonBlocking,
+            );
+        });
+
+        *self.meters.interval_ms.lock_or_recover() =
+            interval_ms.unwrap_or(DEFAULT_LEVELS_INTERVAL_MS);
+        *self.meters.on_levels.lock_or_recover() = Some(cb);
+        Ok(())
+    }
+
+    /// Get the levels of every source, bus and the master output
+    #[napi]
+    pub fn get_levels(&self) -> MixerLevels {
+        let sources = self.sources.lock_or_recover();
+        self.meters.snapshot(&sources)
+    }
+
+    /// Get the master output levels
+    #[napi]
+    pub fn get_master_levels(&self) -> MixerMeterLevels {
+        self.meters.master.lock_or_recover().levels(MASTER_METER_ID)
+    }
+
+    /// Get the levels of a bus by name
+    #[napi]
+    pub fn get_bus_levels(&self, bus: String) -> Result<MixerMeterLevels> {
+        self.meters
+            .buses
+            .lock_or_recover()
+            .get(&bus)
+            .map(|meter| meter.levels(&bus))
+            .ok_or_else(|| error(ErrorCode::InvalidArgument, "Bus not found"))
+    }
+
+    /// Get the levels of a source by its ID
+    #[napi]
+    pub fn get_source_levels(&self, source_id: String) -> Result<MixerMeterLevels> {
+        self.sources
+            .lock_or_recover()
+            .iter()
+            .find(|s| s.id == source_id)
+            .map(|s| s.get_levels())
+            .ok_or_else(|| error(ErrorCode::InvalidArgument, "Source not found"))
+    }
+
+    /// Reset all meters and clear the clip indicators
+    #[napi]
+    pub fn reset_levels(&self) {
+        for source in self.sources.lock_or_recover().iter() {
+            source.meter.lock_or_recover().reset();
+        }
+        self.meters.buses.lock_or_recover().clear();
+        self.meters.master.lock_or_recover().reset();
+        *self.meters.pending_frames.lock_or_recover() = 0;
+    }
 
     /// Get the sample rate of the mixer
     #[napi]
@@ -152,19 +603,19 @@ impl Mixer {
     #[napi]
     pub fn set_master_volume(&mut self, volume: f64) -> Result<()> {
         if !(0.0..=1.0).contains(&volume) {
-            return Err(Error::new(
-                Status::InvalidArg,
+            return Err(error(
+                ErrorCode::InvalidArgument,
                 "Volume must be between 0.0 and 1.0",
             ));
         }
-        *self.volume.lock().unwrap() = volume as f32;
+        *self.volume.lock_or_recover() = volume as f32;
         Ok(())
     }
 
     /// Get the master volume
     #[napi]
     pub fn get_master_volume(&self) -> f64 {
-        *self.volume.lock().unwrap() as f64
+        *self.volume.lock_or_recover() as f64
     }
 }
 
@@ -173,28 +624,73 @@ impl Mixer {
 #[derive(Clone)]
 pub struct MixerSource {
     id: String,
-    samples: Vec<i16>,
+    // Shared so that cloning a source into a mixer does not copy its audio
+    samples: Arc<[i16]>,
     sample_rate: u32,
     channels: u16,
     volume: Arc<Mutex<f32>>,
     pan: Arc<Mutex<f32>>, // -1.0 (left) to 1.0 (right)
     enabled: Arc<Mutex<bool>>,
+    bus: Arc<Mutex<Option<String>>>,
+    meter: Arc<Mutex<LevelMeter>>,
 }
 
-#[napi]
 impl MixerSource {
-    #[napi(constructor)]
     pub fn new(id: String, samples: Vec<i16>, sample_rate: u32, channels: u16) -> Self {
         Self {
             id,
-            samples,
+            samples: samples.into(),
             sample_rate,
             channels,
             volume: Arc::new(Mutex::new(1.0)),
             pan: Arc::new(Mutex::new(0.0)),
             enabled: Arc::new(Mutex::new(true)),
+            bus: Arc::new(Mutex::new(None)),
+            meter: Arc::new(Mutex::new(LevelMeter::default())),
+        }
+    }
+
+    /// Write this source's contribution (after volume and pan) for the given output frames
+    fn render_into(&self, start_frame: u64, out_rate: u32, out_channels: usize, out: &mut [f32]) {
+        let volume = *self.volume.lock_or_recover();
+        let pan = *self.pan.lock_or_recover();
+        let gain_left = (1.0 - pan).min(1.0);
+        let gain_right = (1.0 + pan).min(1.0);
+        let src_channels = self.channels.max(1) as usize;
+        let src_frames = self.samples.len() / src_channels;
+
+        for (i, frame) in out.chunks_mut(out_channels).enumerate() {
+            let src_frame = ((start_frame + i as u64) as f64 * self.sample_rate as f64
+                / out_rate.max(1) as f64) as usize;
+            if src_frame >= src_frames {
+                frame.fill(0.0);
+                continue;
+            }
+            for (ch, sample) in frame.iter_mut().enumerate() {
+                let raw = self.samples[src_frame * src_channels + ch % src_channels];
+                let pan_gain = match (out_channels, ch) {
+                    (2, 0) => gain_left,
+                    (2, _) => gain_right,
+                    _ => 1.0,
+                };
+                *sample = raw as f32 / I16_MAX_F32 * volume * pan_gain;
+            }
         }
     }
+}
+
+#[napi]
+impl MixerSource {
+    /// Create a source from an `Int16Array` or a `Float32Array` in [-1, 1]
+    #[napi(constructor)]
+    pub fn create(
+        id: String,
+        samples: Either<Int16Array, Float32Array>,
+        sample_rate: u32,
+        channels: u16,
+    ) -> Self {
+        Self::new(id, pcm_to_i16(&samples), sample_rate, channels)
+    }
 
     /// Get source ID
     #[napi]
@@ -204,24 +700,24 @@ impl MixerSource {
 
     /// Get audio samples
     #[napi]
-    pub fn get_samples(&self) -> Vec<i16> {
-        self.samples.clone()
+    pub fn get_samples(&self) -> Int16Array {
+        Int16Array::new(self.samples.to_vec())
     }
 
     /// Get samples at a specific time (simplified to return relative audio)
     #[napi]
-    pub fn get_samples_at(&self, time_ms: u32) -> Result<Vec<i16>> {
+    pub fn get_samples_at(&self, time_ms: u32) -> Result<Int16Array> {
         // Calculate sample offset based on time and sample rate
         let time_sec = time_ms as f64 / 1000.0;
         let sample_offset = (time_sec * self.sample_rate as f64 * self.channels as f64) as usize;
 
         if sample_offset >= self.samples.len() {
-            return Ok(vec![]);
+            return Ok(Int16Array::new(vec![]));
         }
 
         // Return samples starting from the calculated offset
         let len = (sample_offset + self.channels as usize).min(self.samples.len());
-        Ok(self.samples[sample_offset..len].to_vec())
+        Ok(Int16Array::new(self.samples[sample_offset..len].to_vec()))
     }
 
     /// Get sample rate
@@ -240,50 +736,68 @@ impl MixerSource {
     #[napi]
     pub fn set_volume(&mut self, volume: f64) -> Result<()> {
         if !(0.0..=1.0).contains(&volume) {
-            return Err(Error::new(
-                Status::InvalidArg,
+            return Err(error(
+                ErrorCode::InvalidArgument,
                 "Volume must be between 0.0 and 1.0",
             ));
         }
-        *self.volume.lock().unwrap() = volume as f32;
+        *self.volume.lock_or_recover() = volume as f32;
         Ok(())
     }
 
     /// Get volume
     #[napi]
     pub fn get_volume(&self) -> f64 {
-        *self.volume.lock().unwrap() as f64
+        *self.volume.lock_or_recover() as f64
     }
 
     /// Set pan (-1.0 left, 0.0 center, 1.0 right)
     #[napi]
     pub fn set_pan(&mut self, pan: f64) -> Result<()> {
         if !(-1.0..=1.0).contains(&pan) {
-            return Err(Error::new(
-                Status::InvalidArg,
+            return Err(error(
+                ErrorCode::InvalidArgument,
                 "Pan must be between -1.0 and 1.0",
             ));
         }
-        *self.pan.lock().unwrap() = pan as f32;
+        *self.pan.lock_or_recover() = pan as f32;
         Ok(())
     }
 
     /// Get pan
     #[napi]
     pub fn get_pan(&self) -> f64 {
-        *self.pan.lock().unwrap() as f64
+        *self.pan.lock_or_recover() as f64
     }
 
     /// Enable or disable source
     #[napi]
     pub fn set_enabled(&mut self, enabled: bool) {
-        *self.enabled.lock().unwrap() = enabled;
+        *self.enabled.lock_or_recover() = enabled;
     }
 
     /// Check if source is enabled
     #[napi]
     pub fn is_enabled(&self) -> bool {
-        *self.enabled.lock().unwrap()
+        *self.enabled.lock_or_recover()
+    }
+
+    /// Route this source to a named bus for metering (None to remove it from any bus)
+    #[napi]
+    pub fn set_bus(&mut self, bus: Option<String>) {
+        *self.bus.lock_or_recover() = bus;
+    }
+
+    /// Get the bus this source is routed to
+    #[napi]
+    pub fn get_bus(&self) -> Option<String> {
+        self.bus.lock_or_recover().clone()
+    }
+
+    /// Get the levels measured for this source during mixing
+    #[napi]
+    pub fn get_levels(&self) -> MixerMeterLevels {
+        self.meter.lock_or_recover().levels(&self.id)
     }
 
     /// Get duration in milliseconds
@@ -1,5 +1,7 @@
 //! Audio noise generation - various types of noise for synthesis and testing
 
+use crate::error::LockExt;
+use napi::bindgen_prelude::Int16Array;
 use napi_derive::napi;
 use std::sync::{Arc, Mutex};
 
@@ -22,15 +24,15 @@ impl BlueNoise {
     }
 
     #[napi]
-    pub fn get_samples(&self) -> Vec<i16> {
-        let samples = self.samples.lock().unwrap();
-        samples.clone()
+    pub fn get_samples(&self) -> Int16Array {
+        let samples = self.samples.lock_or_recover();
+        Int16Array::new(samples.clone())
     }
 
     #[napi]
     pub fn get_next(&self) -> Option<i16> {
-        let mut pos = self.position.lock().unwrap();
-        let samples = self.samples.lock().unwrap();
+        let mut pos = self.position.lock_or_recover();
+        let samples = self.samples.lock_or_recover();
 
         if *pos >= samples.len() {
             return None;
@@ -42,7 +44,7 @@ impl BlueNoise {
 
     #[napi]
     pub fn reset(&self) {
-        *self.position.lock().unwrap() = 0;
+        *self.position.lock_or_recover() = 0;
     }
 }
 
@@ -65,15 +67,15 @@ impl BrownianNoise {
     }
 
     #[napi]
-    pub fn get_samples(&self) -> Vec<i16> {
-        let samples = self.samples.lock().unwrap();
-        samples.clone()
+    pub fn get_samples(&self) -> Int16Array {
+        let samples = self.samples.lock_or_recover();
+        Int16Array::new(samples.clone())
     }
 
     #[napi]
     pub fn get_next(&self) -> Option<i16> {
-        let mut pos = self.position.lock().unwrap();
-        let samples = self.samples.lock().unwrap();
+        let mut pos = self.position.lock_or_recover();
+        let samples = self.samples.lock_or_recover();
 
         if *pos >= samples.len() {
             return None;
@@ -85,7 +87,7 @@ impl BrownianNoise {
 
     #[napi]
     pub fn reset(&self) {
-        *self.position.lock().unwrap() = 0;
+        *self.position.lock_or_recover() = 0;
     }
 }
 
@@ -108,15 +110,15 @@ impl PinkNoise {
     }
 
     #[napi]
-    pub fn get_samples(&self) -> Vec<i16> {
-        let samples = self.samples.lock().unwrap();
-        samples.clone()
+    pub fn get_samples(&self) -> Int16Array {
+        let samples = self.samples.lock_or_recover();
+        Int16Array::new(samples.clone())
     }
 
     #[napi]
     pub fn get_next(&self) -> Option<i16> {
-        let mut pos = self.position.lock().unwrap();
-        let samples = self.samples.lock().unwrap();
+        let mut pos = self.position.lock_or_recover();
+        let samples = self.samples.lock_or_recover();
 
         if *pos >= samples.len() {
             return None;
@@ -128,7 +130,7 @@ impl PinkNoise {
 
     #[napi]
     pub fn reset(&self) {
-        *self.position.lock().unwrap() = 0;
+        *self.position.lock_or_recover() = 0;
     }
 }
 
@@ -151,15 +153,15 @@ impl VelvetNoise {
     }
 
     #[napi]
-    pub fn get_samples(&self) -> Vec<i16> {
-        let samples = self.samples.lock().unwrap();
-        samples.clone()
+    pub fn get_samples(&self) -> Int16Array {
+        let samples = self.samples.lock_or_recover();
+        Int16Array::new(samples.clone())
     }
 
     #[napi]
     pub fn get_next(&self) -> Option<i16> {
-        let mut pos = self.position.lock().unwrap();
-        let samples = self.samples.lock().unwrap();
+        let mut pos = self.position.lock_or_recover();
+        let samples = self.samples.lock_or_recover();
 
         if *pos >= samples.len() {
             return None;
@@ -171,7 +173,7 @@ impl VelvetNoise {
 
     #[napi]
     pub fn reset(&self) {
-        *self.position.lock().unwrap() = 0;
+        *self.position.lock_or_recover() = 0;
     }
 }
 
@@ -194,15 +196,15 @@ impl VioletNoise {
     }
 
     #[napi]
-    pub fn get_samples(&self) -> Vec<i16> {
-        let samples = self.samples.lock().unwrap();
-        samples.clone()
+    pub fn get_samples(&self) -> Int16Array {
+        let samples = self.samples.lock_or_recover();
+        Int16Array::new(samples.clone())
     }
 
     #[napi]
     pub fn get_next(&self) -> Option<i16> {
-        let mut pos = self.position.lock().unwrap();
-        let samples = self.samples.lock().unwrap();
+        let mut pos = self.position.lock_or_recover();
+        let samples = self.samples.lock_or_recover();
 
         if *pos >= samples.len() {
             return None;
@@ -214,7 +216,7 @@ impl VioletNoise {
 
     #[napi]
     pub fn reset(&self) {
-        *self.position.lock().unwrap() = 0;
+        *self.position.lock_or_recover() = 0;
     }
 }
 
@@ -237,15 +239,15 @@ impl WhiteGaussianNoise {
     }
 
     #[napi]
-    pub fn get_samples(&self) -> Vec<i16> {
-        let samples = self.samples.lock().unwrap();
-        samples.clone()
+    pub fn get_samples(&self) -> Int16Array {
+        let samples = self.samples.lock_or_recover();
+        Int16Array::new(samples.clone())
     }
 
     #[napi]
     pub fn get_next(&self) -> Option<i16> {
-        let mut pos = self.position.lock().unwrap();
-        let samples = self.samples.lock().unwrap();
+        let mut pos = self.position.lock_or_recover();
+        let samples = self.samples.lock_or_recover();
 
         if *pos >= samples.len() {
             return None;
@@ -257,7 +259,7 @@ impl WhiteGaussianNoise {
 
     #[napi]
     pub fn reset(&self) {
-        *self.position.lock().unwrap() = 0;
+        *self.position.lock_or_recover() = 0;
     }
 }
 
@@ -280,15 +282,15 @@ impl WhiteTriangularNoise {
     }
 
     #[napi]
-    pub fn get_samples(&self) -> Vec<i16> {
-        let samples = self.samples.lock().unwrap();
-        samples.clone()
+    pub fn get_samples(&self) -> Int16Array {
+        let samples = self.samples.lock_or_recover();
+        Int16Array::new(samples.clone())
     }
 
     #[napi]
     pub fn get_next(&self) -> Option<i16> {
-        let mut pos = self.position.lock().unwrap();
-        let samples = self.samples.lock().unwrap();
+        let mut pos = self.position.lock_or_recover();
+        let samples = self.samples.lock_or_recover();
 
         if *pos >= samples.len() {
             return None;
@@ -300,7 +302,7 @@ impl WhiteTriangularNoise {
 
     #[napi]
     pub fn reset(&self) {
-        *self.position.lock().unwrap() = 0;
+        *self.position.lock_or_recover() = 0;
     }
 }
 
@@ -323,15 +325,15 @@ impl WhiteUniformNoise {
     }
 
     #[napi]
-    pub fn get_samples(&self) -> Vec<i16> {
-        let samples = self.samples.lock().unwrap();
-        samples.clone()
+    pub fn get_samples(&self) -> Int16Array {
+        let samples = self.samples.lock_or_recover();
+        Int16Array::new(samples.clone())
     }
 
     #[napi]
     pub fn get_next(&self) -> Option<i16> {
-        let mut pos = self.position.lock().unwrap();
-        let samples = self.samples.lock().unwrap();
+        let mut pos = self.position.lock_or_recover();
+        let samples = self.samples.lock_or_recover();
 
         if *pos >= samples.len() {
             return None;
@@ -343,7 +345,7 @@ impl WhiteUniformNoise {
 
     #[napi]
     pub fn reset(&self) {
-        *self.position.lock().unwrap() = 0;
+        *self.position.lock_or_recover() = 0;
     }
 }
 
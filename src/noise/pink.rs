@@ -1,6 +1,8 @@
 // Copyright (c) The miniaudio_node Contributors
 // SPDX-License-Identifier: MIT
 
+use crate::error::LockExt;
+use napi::bindgen_prelude::Int16Array;
 use napi_derive::napi;
 use std::sync::{Arc, Mutex};
 
@@ -58,15 +60,15 @@ impl Pink {
 
     /// Get all samples generated by the pink noise
     #[napi]
-    pub fn get_samples(&self) -> Vec<i16> {
-        self.samples.lock().unwrap().clone()
+    pub fn get_samples(&self) -> Int16Array {
+        Int16Array::new(self.samples.lock_or_recover().clone())
     }
 
     /// Get the next sample, looping back to the start if at the end
     #[napi]
     pub fn get_next(&self) -> i16 {
-        let mut pos = self.position.lock().unwrap();
-        let samples = self.samples.lock().unwrap();
+        let mut pos = self.position.lock_or_recover();
+        let samples = self.samples.lock_or_recover();
 
         if *pos >= samples.len() {
             *pos = 0; // Loop
@@ -80,12 +82,12 @@ impl Pink {
     /// Reset playback position to the beginning
     #[napi]
     pub fn reset(&self) {
-        *self.position.lock().unwrap() = 0;
+        *self.position.lock_or_recover() = 0;
     }
 }
 
-/// Generate pink noise as a Vec of samples
+/// Generate pink noise samples
 #[napi]
-pub fn pink(duration_ms: u32, sample_rate: u32, channels: u16) -> Vec<i16> {
+pub fn pink(duration_ms: u32, sample_rate: u32, channels: u16) -> Int16Array {
     Pink::new(duration_ms, sample_rate, channels).get_samples()
 }
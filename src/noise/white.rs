@@ -1,3 +1,5 @@
+use crate::error::LockExt;
+use napi::bindgen_prelude::Int16Array;
 use napi_derive::napi;
 use rand::Rng;
 use std::sync::{Arc, Mutex};
@@ -27,8 +29,8 @@ impl White {
     }
 
     #[napi]
-    pub fn get_samples(&self) -> Vec<i16> {
-        self.samples.lock().unwrap().clone()
+    pub fn get_samples(&self) -> Int16Array {
+        Int16Array::new(self.samples.lock_or_recover().clone())
     }
 }
 
@@ -49,7 +51,7 @@ impl WhiteGenerator {
     }
 
     #[napi]
-    pub fn generate(&self, duration_ms: u32) -> Vec<i16> {
+    pub fn generate(&self, duration_ms: u32) -> Int16Array {
         let total_samples = ((duration_ms as f64 * self.sample_rate as f64 / 1000.0) as usize)
             * self.channels as usize;
         let mut samples = Vec::with_capacity(total_samples);
@@ -59,6 +61,6 @@ impl WhiteGenerator {
             samples.push(rng.gen());
         }
 
-        samples
+        Int16Array::new(samples)
     }
 }
@@ -1,48 +1,65 @@
+use crate::error::{
+    caused_by, decode_failure, error, io_failure, open_failure, AudioTask, CodedTask,
+    ErrorCallback, ErrorCode, ErrorReporter, LockExt, Result,
+};
 use base64::{engine::general_purpose, Engine as _};
-use napi::{Error, Result, Status};
+use napi::bindgen_prelude::{AbortSignal, AsyncTask};
+use napi::Env;
 use napi_derive::napi;
-use rodio::{Decoder, OutputStreamBuilder, Sink, Source};
+use rodio::{Decoder, Sink, Source};
 use std::fs::File;
 use std::io::{BufReader, Cursor};
 use std::path::Path;
-use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, Weak};
 
 // Importamos los tipos definidos en el otro módulo
+use crate::analyser::{AnalyserSlot, AudioAnalyser, Tapped};
 use crate::debug_log;
-use crate::types::{AudioDeviceInfo, AudioPlayerConfig, PlaybackState};
+use crate::engine::{self, DeviceErrorListener};
+use crate::replaygain::{self, NormalizationConfig, NormalizationMode, ReplayGainInfo};
+use crate::types::{
+    AudioDeviceInfo, AudioPlayerConfig, CancelToken, DecoderError, PlayError, PlaybackState,
+    SeekError,
+};
 
 /// Thread-safe audio player with rodio backend
 #[napi]
 pub struct AudioPlayer {
-    current_file: Option<String>,
+    current_file: Arc<Mutex<Option<String>>>,
     volume: Arc<Mutex<f32>>,
     state: Arc<Mutex<PlaybackState>>,
     duration: Arc<Mutex<f64>>,
     sink: Arc<Mutex<Option<Sink>>>,
-    // OutputStream needs to be kept alive along with sink
-    #[allow(dead_code)]
-    output_stream: Arc<Mutex<Option<rodio::OutputStream>>>,
     audio_buffer: Arc<Mutex<Option<Vec<u8>>>>,
     // Track if player was ever initialized
-    initialized: bool,
+    initialized: Arc<AtomicBool>,
     // Track current playback time (using Unix timestamp for napi compatibility)
     start_time: Arc<Mutex<Option<u128>>>,
     total_paused_ns: Arc<Mutex<u128>>,
+    errors: Arc<ErrorReporter>,
+    normalization: Arc<Mutex<NormalizationConfig>>,
+    // Gain tags of the loaded source, or its scan once normalisation needed one
+    replay_gain: Arc<Mutex<Option<ReplayGainInfo>>>,
+    analyser: AnalyserSlot,
 }
 
 impl Default for AudioPlayer {
     fn default() -> Self {
         Self {
-            current_file: None,
+            current_file: Arc::new(Mutex::new(None)),
             volume: Arc::new(Mutex::new(1.0)),
             state: Arc::new(Mutex::new(PlaybackState::Stopped)),
             duration: Arc::new(Mutex::new(0.0)),
             sink: Arc::new(Mutex::new(None)),
-            output_stream: Arc::new(Mutex::new(None)),
             audio_buffer: Arc::new(Mutex::new(None)),
-            initialized: false,
+            initialized: Arc::new(AtomicBool::new(false)),
             start_time: Arc::new(Mutex::new(None)),
             total_paused_ns: Arc::new(Mutex::new(0)),
+            errors: Arc::new(ErrorReporter::default()),
+            normalization: Arc::new(Mutex::new(NormalizationConfig::default())),
+            replay_gain: Arc::new(Mutex::new(None)),
+            analyser: Arc::new(Mutex::new(None)),
         }
     }
 }
@@ -59,13 +76,11 @@ impl AudioPlayer {
     pub fn new() -> Result<Self> {
         let player = Self::default();
 
-        // Try to initialize the output stream and sink immediately
+        // Try to connect to the shared output immediately
         // This prevents the first-play delay
-        match OutputStreamBuilder::open_default_stream() {
-            Ok(stream) => {
-                let sink = Sink::connect_new(stream.mixer());
-                *player.output_stream.lock().unwrap() = Some(stream);
-                *player.sink.lock().unwrap() = Some(sink);
+        match engine::connect_sink(None, player.error_listener()) {
+            Ok((sink, _)) => {
+                *player.sink.lock_or_recover() = Some(sink);
                 debug_log!("Audio stream initialized in constructor");
             }
             Err(e) => {
@@ -76,6 +91,14 @@ impl AudioPlayer {
         Ok(player)
     }
 
+    /// Called with errors raised on the audio thread, e.g. a `DeviceLost`
+    /// error when the output device disappears
+    #[napi(ts_args_type = "callback: (err: Error) => void")]
+    pub fn set_on_error(&self, callback: ErrorCallback) -> Result<()> {
+        self.errors.set(callback);
+        Ok(())
+    }
+
     #[napi]
     pub fn get_devices(&self) -> Result<Vec<AudioDeviceInfo>> {
         Ok(vec![AudioDeviceInfo {
@@ -89,74 +112,65 @@ impl AudioPlayer {
     #[napi]
     pub fn load_file(&mut self, file_path: String) -> Result<()> {
         debug_log!("Loading file: {}", file_path);
-        let path = Path::new(&file_path);
-        if !path.exists() {
-            debug_log!("File not found: {}", file_path);
-            return Err(Error::new(
-                Status::InvalidArg,
-                format!("File not found: {}", file_path),
-            ));
+        let target = self.load_target();
+        // A missing file leaves the current source untouched; anything else
+        // stops playback (and marks the player initialized) before probing
+        if Path::new(&file_path).exists() {
+            target.reset();
         }
 
-        // Mark as initialized before stopping (to allow cleanup if player was used before)
-        self.initialized = true;
-        self.stop().ok();
-
-        // Validate file opening
-        let file = File::open(path)
-            .map_err(|e| Error::new(Status::InvalidArg, format!("Failed to open file: {}", e)))?;
-        let reader = BufReader::new(file);
-        let decoder = Decoder::new(reader).map_err(|e| {
-            Error::new(
-                Status::InvalidArg,
-                format!("Failed to create decoder: {}", e),
-            )
-        })?;
-
-        // Calculate duration from decoder
-        let duration = decoder
-            .total_duration()
-            .unwrap_or(std::time::Duration::ZERO);
-        let duration_seconds =
-            duration.as_secs() as f64 + duration.subsec_nanos() as f64 / 1_000_000_000.0;
-        *self.duration.lock().unwrap() = duration_seconds;
-
-        debug_log!(
-            "File loaded successfully, duration: {} seconds",
-            duration_seconds
-        );
-        self.current_file = Some(file_path);
-        *self.state.lock().unwrap() = PlaybackState::Loaded;
-
+        let duration_seconds = probe_file(&file_path)?;
+        let replay_gain = replaygain::read_file_tags(&file_path);
+        target.finish_load(file_path, duration_seconds, replay_gain);
         Ok(())
     }
 
+    /// Load a file without blocking the event loop.
+    /// Opening and probing happen in the background; the player is only
+    /// updated once that succeeds and `signal` has not fired.
+    #[napi(ts_return_type = "Promise<void>")]
+    pub fn load_file_async(
+        &self,
+        file_path: String,
+        signal: Option<AbortSignal>,
+    ) -> AsyncTask<CodedTask<LoadFileTask>> {
+        let cancel = CancelToken::from_signal(signal.as_ref());
+        CodedTask::spawn(
+            LoadFileTask {
+                target: self.load_target(),
+                file_path,
+                normalize: self.normalization.lock_or_recover().mode != NormalizationMode::Off,
+                cancel,
+            },
+            signal,
+        )
+    }
+
     #[napi]
     pub fn load_buffer(&mut self, audio_data: Vec<u8>) -> Result<()> {
         debug_log!("Loading buffer ({} bytes)", audio_data.len());
         if audio_data.is_empty() {
             debug_log!("Audio buffer is empty");
-            return Err(Error::new(Status::InvalidArg, "Audio buffer is empty"));
+            return Err(error(DecoderError::EmptyInput, "Audio buffer is empty"));
         }
         // Mark as initialized before stopping (to allow cleanup if player was used before)
-        self.initialized = true;
-        self.stop().ok();
+        self.load_target().reset();
 
         let cursor = Cursor::new(audio_data.clone());
-        let _decoder = Decoder::new(cursor).map_err(|e| {
-            Error::new(
-                Status::InvalidArg,
-                format!("Failed to decode buffer: {}", e),
-            )
-        })?;
-
-        *self.duration.lock().unwrap() = 0.0;
-        *self.audio_buffer.lock().unwrap() = Some(audio_data);
-        self.current_file = Some(format!(
+        let _decoder =
+            Decoder::new(cursor).map_err(|e| decode_failure("Failed to decode buffer", e))?;
+
+        *self.duration.lock_or_recover() = 0.0;
+        *self.replay_gain.lock_or_recover() = replaygain::read_buffer_tags(&audio_data);
+        *self.audio_buffer.lock_or_recover() = Some(audio_data);
+        *self.current_file.lock_or_recover() = Some(format!(
             "__BUFFER__{}",
-            std::time::SystemTime::now().elapsed().unwrap().as_millis()
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or(std::time::Duration::ZERO)
+                .as_millis()
         ));
-        *self.state.lock().unwrap() = PlaybackState::Loaded;
+        *self.state.lock_or_recover() = PlaybackState::Loaded;
         debug_log!("Buffer loaded successfully");
 
         Ok(())
@@ -167,14 +181,15 @@ impl AudioPlayer {
         debug_log!("Loading base64 audio data");
         if base64_data.is_empty() {
             debug_log!("Base64 data is empty");
-            return Err(Error::new(Status::InvalidArg, "Base64 data is empty"));
+            return Err(error(DecoderError::EmptyInput, "Base64 data is empty"));
         }
         let audio_data = general_purpose::STANDARD
             .decode(&base64_data)
             .map_err(|e| {
-                Error::new(
-                    Status::InvalidArg,
+                caused_by(
+                    DecoderError::InvalidFormat,
                     format!("Failed to decode base64: {}", e),
+                    e,
                 )
             })?;
         self.load_buffer(audio_data)
@@ -182,24 +197,24 @@ impl AudioPlayer {
 
     #[napi]
     pub fn play(&mut self) -> Result<()> {
-        let has_buffer = self.audio_buffer.lock().unwrap().is_some();
-        let has_file = self.current_file.is_some();
+        let has_buffer = self.audio_buffer.lock_or_recover().is_some();
+        let has_file = self.current_file.lock_or_recover().is_some();
 
         if !has_buffer && !has_file {
             debug_log!("Play called but player not initialized");
-            return Err(Error::new(Status::InvalidArg, "Player not initialized"));
+            return Err(error(PlayError::NotLoaded, "Player not initialized"));
         }
 
         debug_log!(
             "Play called, current state: {:?}",
-            self.state.lock().unwrap().clone()
+            self.state.lock_or_recover().clone()
         );
 
         // Track playback time
         {
-            let mut start_time_guard = self.start_time.lock().unwrap();
-            let mut total_paused_guard = self.total_paused_ns.lock().unwrap();
-            let current_state = self.state.lock().unwrap().clone();
+            let mut start_time_guard = self.start_time.lock_or_recover();
+            let mut total_paused_guard = self.total_paused_ns.lock_or_recover();
+            let current_state = self.state.lock_or_recover().clone();
             let now = std::time::SystemTime::now()
                 .duration_since(std::time::UNIX_EPOCH)
                 .unwrap_or(std::time::Duration::ZERO)
@@ -220,24 +235,17 @@ impl AudioPlayer {
 
         // Always ensure sink is available - recreate if needed
         let sink_needs_source = {
-            let mut output_stream_guard = self.output_stream.lock().unwrap();
-            let mut sink_guard = self.sink.lock().unwrap();
+            let mut sink_guard = self.sink.lock_or_recover();
 
-            // Create new stream and sink if either is missing
-            if sink_guard.is_none() || output_stream_guard.is_none() {
+            // Create new sink if missing
+            if sink_guard.is_none() {
                 debug_log!("Recreating output stream and sink...");
 
-                let stream = OutputStreamBuilder::open_default_stream().map_err(|e| {
-                    debug_log!("Failed to create output stream: {}", e);
-                    Error::new(
-                        Status::GenericFailure,
-                        format!("Failed to create output stream: {}", e),
-                    )
-                })?;
-
-                let sink = Sink::connect_new(stream.mixer());
+                let (sink, _) =
+                    engine::connect_sink(None, self.error_listener()).inspect_err(|e| {
+                        debug_log!("Failed to create output stream: {}", e);
+                    })?;
 
-                *output_stream_guard = Some(stream);
                 *sink_guard = Some(sink);
                 debug_log!("Output stream and sink recreated");
                 true // New sink needs a source
@@ -248,24 +256,26 @@ impl AudioPlayer {
         };
 
         // Append source and play
-        let sink_guard = self.sink.lock().unwrap();
+        let sink_guard = self.sink.lock_or_recover();
         if let Some(sink) = sink_guard.as_ref() {
-            let volume = *self.volume.lock().unwrap();
+            let volume = self.output_volume();
             sink.set_volume(volume);
             debug_log!("Setting volume to: {}", volume);
 
             if sink_needs_source || sink.empty() {
                 debug_log!("Sink is empty, appending source...");
-                if let Some(buffer_data) = self.audio_buffer.lock().unwrap().clone() {
+                if let Some(buffer_data) = self.audio_buffer.lock_or_recover().clone() {
                     debug_log!("Playing from buffer ({} bytes)", buffer_data.len());
                     let cursor = Cursor::new(buffer_data);
-                    let source = Decoder::new(cursor).unwrap();
-                    sink.append(source);
-                } else if let Some(file_path) = &self.current_file {
+                    let source = Decoder::new(cursor)
+                        .map_err(|e| decode_failure("Failed to decode buffer", e))?;
+                    sink.append(engine::voice(Tapped::new(source, self.analyser.clone())));
+                } else if let Some(file_path) = self.current_file.lock_or_recover().as_ref() {
                     debug_log!("Playing from file: {}", file_path);
-                    let file = File::open(file_path).unwrap();
-                    let source = Decoder::new(BufReader::new(file)).unwrap();
-                    sink.append(source);
+                    let file = File::open(file_path).map_err(|e| open_failure(file_path, e))?;
+                    let source = Decoder::new(BufReader::new(file))
+                        .map_err(|e| decode_failure("Failed to create decoder", e))?;
+                    sink.append(engine::voice(Tapped::new(source, self.analyser.clone())));
                 }
             } else {
                 debug_log!("Resuming paused audio");
@@ -274,7 +284,7 @@ impl AudioPlayer {
             debug_log!("Sink playing");
         }
 
-        *self.state.lock().unwrap() = PlaybackState::Playing;
+        *self.state.lock_or_recover() = PlaybackState::Playing;
         debug_log!("State set to Playing");
 
         Ok(())
@@ -283,7 +293,7 @@ impl AudioPlayer {
     #[napi]
     pub fn pause(&mut self) -> Result<()> {
         debug_log!("Pause called");
-        let current_state = self.state.lock().unwrap().clone();
+        let current_state = self.state.lock_or_recover().clone();
 
         // If already paused or stopped with no sink, just update state
         if current_state == PlaybackState::Paused {
@@ -298,7 +308,7 @@ impl AudioPlayer {
 
         // Track pause start time
         {
-            let mut start_time_guard = self.start_time.lock().unwrap();
+            let mut start_time_guard = self.start_time.lock_or_recover();
             let now = std::time::SystemTime::now()
                 .duration_since(std::time::UNIX_EPOCH)
                 .unwrap_or(std::time::Duration::ZERO)
@@ -306,17 +316,17 @@ impl AudioPlayer {
             *start_time_guard = Some(now);
         }
 
-        let sink_guard = self.sink.lock().unwrap();
+        let sink_guard = self.sink.lock_or_recover();
         if let Some(sink) = sink_guard.as_ref() {
             sink.pause();
-            *self.state.lock().unwrap() = PlaybackState::Paused;
+            *self.state.lock_or_recover() = PlaybackState::Paused;
             debug_log!("State set to Paused");
             Ok(())
         } else {
             // Sink doesn't exist but player is in Playing/Loaded state
             // This can happen after stop() was called but before play()
             // Just update state to Paused since there's nothing playing
-            *self.state.lock().unwrap() = PlaybackState::Paused;
+            *self.state.lock_or_recover() = PlaybackState::Paused;
             debug_log!("No sink available, state set to Paused anyway");
             Ok(())
         }
@@ -327,28 +337,12 @@ impl AudioPlayer {
         debug_log!("Stop called");
 
         // Only error if player was never initialized
-        if !self.initialized {
+        if !self.initialized.load(Ordering::SeqCst) {
             debug_log!("Cannot stop - player not initialized");
-            return Err(Error::new(Status::InvalidArg, "Player not initialized"));
+            return Err(error(PlayError::NotLoaded, "Player not initialized"));
         }
 
-        // Reset time tracking
-        {
-            let mut start_time_guard = self.start_time.lock().unwrap();
-            let mut total_paused_guard = self.total_paused_ns.lock().unwrap();
-            *start_time_guard = None;
-            *total_paused_guard = 0;
-        }
-
-        if let Some(sink) = self.sink.lock().unwrap().as_ref() {
-            debug_log!("Stopping sink");
-            sink.stop();
-        }
-        *self.sink.lock().unwrap() = None;
-        *self.audio_buffer.lock().unwrap() = None;
-        *self.state.lock().unwrap() = PlaybackState::Stopped;
-        self.current_file = None;
-        debug_log!("State set to Stopped");
+        self.load_target().reset();
         Ok(())
     }
 
@@ -357,15 +351,14 @@ impl AudioPlayer {
         debug_log!("Setting volume to: {}", volume);
         if !(0.0..=1.0).contains(&volume) {
             debug_log!("Invalid volume range: {}", volume);
-            return Err(Error::new(
-                Status::InvalidArg,
+            return Err(error(
+                ErrorCode::InvalidArgument,
                 "Volume must be between 0.0 and 1.0",
             ));
         }
-        let vol = volume as f32;
-        *self.volume.lock().unwrap() = vol;
-        if let Some(sink) = self.sink.lock().unwrap().as_ref() {
-            sink.set_volume(vol);
+        *self.volume.lock_or_recover() = volume as f32;
+        if let Some(sink) = self.sink.lock_or_recover().as_ref() {
+            sink.set_volume(self.output_volume());
             debug_log!("Volume set on sink: {}", volume);
         }
         Ok(())
@@ -373,12 +366,80 @@ impl AudioPlayer {
 
     #[napi]
     pub fn get_volume(&self) -> Result<f64> {
-        Ok(*self.volume.lock().unwrap() as f64)
+        Ok(*self.volume.lock_or_recover() as f64)
+    }
+
+    /// Set how tracks are normalised. The gain multiplies the `setVolume` level;
+    /// sources without ReplayGain/R128 tags play unchanged until `scanReplayGainAsync`
+    /// (or `loadFileAsync` with normalisation on) has measured them.
+    #[napi]
+    pub fn set_normalization(&mut self, config: NormalizationConfig) -> Result<()> {
+        if config.pre_amp_db.is_some_and(|db| !db.is_finite()) {
+            return Err(error(
+                ErrorCode::InvalidArgument,
+                "Pre-amp must be a finite number of dB",
+            ));
+        }
+        debug_log!("Setting normalization to: {:?}", config);
+        *self.normalization.lock_or_recover() = config;
+        if let Some(sink) = self.sink.lock_or_recover().as_ref() {
+            sink.set_volume(self.output_volume());
+        }
+        Ok(())
+    }
+
+    #[napi]
+    pub fn get_normalization(&self) -> NormalizationConfig {
+        self.normalization.lock_or_recover().clone()
+    }
+
+    /// Gain tags of the loaded source, or the measured values when it had none
+    #[napi]
+    pub fn get_replay_gain(&self) -> Option<ReplayGainInfo> {
+        self.replay_gain.lock_or_recover().clone()
+    }
+
+    /// Measure the loudness of a loaded source without gain tags in the background.
+    /// Until a source has tags or a scan, normalisation leaves its level unchanged;
+    /// `loadFileAsync` scans by itself when normalisation is on.
+    #[napi(ts_return_type = "Promise<ReplayGainInfo | null>")]
+    pub fn scan_replay_gain_async(
+        &self,
+        signal: Option<AbortSignal>,
+    ) -> AsyncTask<CodedTask<ScanGainTask>> {
+        let task = self.scan_gain_task(CancelToken::from_signal(signal.as_ref()));
+        CodedTask::spawn(task, signal)
+    }
+
+    pub(crate) fn scan_gain_task(&self, cancel: CancelToken) -> ScanGainTask {
+        ScanGainTask {
+            target: self.gain_target(),
+            file_path: self.current_file.lock_or_recover().clone(),
+            data: self.audio_buffer.lock_or_recover().clone(),
+            cancel,
+        }
+    }
+
+    /// Gain applied by normalisation in dB, on top of the volume
+    #[napi]
+    pub fn get_normalization_gain(&self) -> f64 {
+        20.0 * (self.normalization_gain() as f64).log10()
+    }
+
+    /// Feed the decoded audio (before volume and normalisation) to `analyser`, replacing any analyser attached before
+    #[napi]
+    pub fn attach_analyser(&self, analyser: &AudioAnalyser) {
+        *self.analyser.lock_or_recover() = Some(analyser.shared());
+    }
+
+    #[napi]
+    pub fn detach_analyser(&self) {
+        *self.analyser.lock_or_recover() = None;
     }
 
     #[napi]
     pub fn is_playing(&self) -> bool {
-        if let Some(sink) = self.sink.lock().unwrap().as_ref() {
+        if let Some(sink) = self.sink.lock_or_recover().as_ref() {
             !sink.is_paused() && !sink.empty()
         } else {
             false
@@ -387,24 +448,24 @@ impl AudioPlayer {
 
     #[napi]
     pub fn get_state(&self) -> PlaybackState {
-        self.state.lock().unwrap().clone()
+        self.state.lock_or_recover().clone()
     }
 
     #[napi]
     pub fn get_duration(&self) -> Result<f64> {
-        Ok(*self.duration.lock().unwrap())
+        Ok(*self.duration.lock_or_recover())
     }
 
     #[napi]
     pub fn get_current_time(&self) -> Result<f64> {
-        let start_time_opt = *self.start_time.lock().unwrap();
+        let start_time_opt = *self.start_time.lock_or_recover();
 
         if let Some(start_time_ns) = start_time_opt {
             let now = std::time::SystemTime::now()
                 .duration_since(std::time::UNIX_EPOCH)
                 .unwrap_or(std::time::Duration::ZERO)
                 .as_nanos();
-            let total_paused_ns = *self.total_paused_ns.lock().unwrap();
+            let total_paused_ns = *self.total_paused_ns.lock_or_recover();
             let playing_ns = now
                 .saturating_sub(start_time_ns)
                 .saturating_sub(total_paused_ns);
@@ -416,7 +477,7 @@ impl AudioPlayer {
 
     #[napi]
     pub fn get_current_file(&self) -> Option<String> {
-        self.current_file.clone()
+        self.current_file.lock_or_recover().clone()
     }
 
     #[napi]
@@ -425,18 +486,18 @@ impl AudioPlayer {
 
         // Validate position - handle decimal precision issues
         if position.is_nan() || position.is_infinite() {
-            return Err(Error::new(
-                Status::InvalidArg,
+            return Err(error(
+                SeekError::InvalidPosition,
                 "Position must be a valid finite number",
             ));
         }
 
-        let duration = *self.duration.lock().unwrap();
+        let duration = *self.duration.lock_or_recover();
         // Use a small epsilon for floating point comparison
         let epsilon = 1e-9;
         if position < -epsilon || position > duration + epsilon {
-            return Err(Error::new(
-                Status::InvalidArg,
+            return Err(error(
+                SeekError::OutOfBounds,
                 format!("Position must be between 0.0 and {} seconds", duration),
             ));
         }
@@ -445,29 +506,29 @@ impl AudioPlayer {
         let position = position.max(0.0).min(duration);
 
         // Check if we have a source to seek within
-        let has_file = self.current_file.is_some();
-        let has_buffer = self.audio_buffer.lock().unwrap().is_some();
+        let has_file = self.current_file.lock_or_recover().is_some();
+        let has_buffer = self.audio_buffer.lock_or_recover().is_some();
 
         if !has_file && !has_buffer {
             debug_log!("Seek called but no audio loaded");
-            return Err(Error::new(Status::InvalidArg, "No audio loaded"));
+            return Err(error(PlayError::NotLoaded, "No audio loaded"));
         }
 
         // Stop current playback without clearing source info
         {
-            let sink_guard = self.sink.lock().unwrap();
+            let sink_guard = self.sink.lock_or_recover();
             if let Some(sink) = sink_guard.as_ref() {
                 sink.stop();
             }
         }
-        *self.sink.lock().unwrap() = None;
-        *self.state.lock().unwrap() = PlaybackState::Stopped;
+        *self.sink.lock_or_recover() = None;
+        *self.state.lock_or_recover() = PlaybackState::Stopped;
         debug_log!("Sink stopped for seek");
 
         // Reset time tracking for new playback position
         {
-            let mut start_time_guard = self.start_time.lock().unwrap();
-            let mut total_paused_guard = self.total_paused_ns.lock().unwrap();
+            let mut start_time_guard = self.start_time.lock_or_recover();
+            let mut total_paused_guard = self.total_paused_ns.lock_or_recover();
             let now = std::time::SystemTime::now()
                 .duration_since(std::time::UNIX_EPOCH)
                 .unwrap_or(std::time::Duration::ZERO)
@@ -480,73 +541,53 @@ impl AudioPlayer {
             *total_paused_guard = 0;
         }
 
-        // Recreate output stream and sink only if needed
+        // Recreate sink only if needed
         {
-            let output_stream_guard = self.output_stream.lock().unwrap();
-            let sink_guard = self.sink.lock().unwrap();
-
-            if sink_guard.is_none() || output_stream_guard.is_none() {
-                drop(sink_guard);
-                drop(output_stream_guard);
-
-                let stream = OutputStreamBuilder::open_default_stream().map_err(|e| {
-                    debug_log!("Failed to create output stream for seek: {}", e);
-                    Error::new(
-                        Status::GenericFailure,
-                        format!("Failed to create output stream: {}", e),
-                    )
-                })?;
-
-                let sink_new = Sink::connect_new(stream.mixer());
-                *self.output_stream.lock().unwrap() = Some(stream);
-                *self.sink.lock().unwrap() = Some(sink_new);
+            let mut sink_guard = self.sink.lock_or_recover();
+
+            if sink_guard.is_none() {
+                let (sink_new, _) =
+                    engine::connect_sink(None, self.error_listener()).inspect_err(|e| {
+                        debug_log!("Failed to create output stream for seek: {}", e);
+                    })?;
+
+                *sink_guard = Some(sink_new);
                 debug_log!("Output stream and sink recreated for seek");
             }
         }
 
         // Create source with skip and append to sink
-        let sink_guard = self.sink.lock().unwrap();
+        let sink_guard = self.sink.lock_or_recover();
         if let Some(sink) = sink_guard.as_ref() {
-            let volume = *self.volume.lock().unwrap();
-            sink.set_volume(volume);
+            sink.set_volume(self.output_volume());
 
-            if let Some(ref file_path) = self.current_file {
+            let current_file = self.current_file.lock_or_recover().clone();
+            if let Some(ref file_path) = current_file {
                 let path = Path::new(file_path);
-                let file = File::open(path).map_err(|e| {
-                    Error::new(
-                        Status::GenericFailure,
-                        format!("Failed to reopen file: {}", e),
-                    )
-                })?;
+                let file = File::open(path)
+                    .map_err(|e| io_failure(format!("Failed to reopen file: {}", e), e))?;
 
                 let reader = BufReader::new(file);
-                let decoder = Decoder::new(reader).map_err(|e| {
-                    Error::new(
-                        Status::GenericFailure,
-                        format!("Failed to create decoder: {}", e),
-                    )
-                })?;
+                let decoder = Decoder::new(reader)
+                    .map_err(|e| decode_failure("Failed to create decoder", e))?;
 
                 // Skip to the desired position
-                let skip_duration = std::time::Duration::from_secs_f64(position);
+                let skip_duration = std::time::Duration::try_from_secs_f64(position)
+                    .unwrap_or(std::time::Duration::ZERO);
                 let source = decoder.skip_duration(skip_duration);
-                sink.append(source);
+                sink.append(engine::voice(Tapped::new(source, self.analyser.clone())));
                 debug_log!("File source appended with skip to position: {}s", position);
-            } else if let Some(ref buffer_data) = *self.audio_buffer.lock().unwrap() {
+            } else if let Some(ref buffer_data) = *self.audio_buffer.lock_or_recover() {
                 // For buffer sources, we skip bytes based on approximate position
                 let sample_rate = 44100.0; // Assume common sample rate
                 let bytes_per_second = sample_rate * 4.0; // 16-bit stereo = 4 bytes per sample
                 let skip_bytes = ((position * bytes_per_second) as usize).min(buffer_data.len());
 
                 let cursor = Cursor::new(buffer_data[skip_bytes..].to_vec());
-                let decoder = Decoder::new(cursor).map_err(|e| {
-                    Error::new(
-                        Status::GenericFailure,
-                        format!("Failed to create decoder: {}", e),
-                    )
-                })?;
-
-                sink.append(decoder);
+                let decoder = Decoder::new(cursor)
+                    .map_err(|e| decode_failure("Failed to create decoder", e))?;
+
+                sink.append(engine::voice(Tapped::new(decoder, self.analyser.clone())));
                 debug_log!(
                     "Buffer source appended with skip to position: {}s",
                     position
@@ -554,7 +595,7 @@ impl AudioPlayer {
             }
 
             sink.play();
-            *self.state.lock().unwrap() = PlaybackState::Playing;
+            *self.state.lock_or_recover() = PlaybackState::Playing;
             debug_log!("Seek complete, playing from position: {}s", position);
         }
 
@@ -562,6 +603,232 @@ impl AudioPlayer {
     }
 }
 
+impl AudioPlayer {
+    fn error_listener(&self) -> Option<Weak<dyn DeviceErrorListener>> {
+        Some(Arc::downgrade(&self.errors) as _)
+    }
+
+    fn normalization_gain(&self) -> f32 {
+        self.gain_target().normalization_gain()
+    }
+
+    /// Sink volume: the user volume scaled by the normalisation gain
+    fn output_volume(&self) -> f32 {
+        self.gain_target().output_volume()
+    }
+
+    fn gain_target(&self) -> GainTarget {
+        GainTarget {
+            current_file: self.current_file.clone(),
+            volume: self.volume.clone(),
+            sink: self.sink.clone(),
+            normalization: self.normalization.clone(),
+            replay_gain: self.replay_gain.clone(),
+        }
+    }
+
+    fn load_target(&self) -> LoadTarget {
+        LoadTarget {
+            current_file: self.current_file.clone(),
+            initialized: self.initialized.clone(),
+            state: self.state.clone(),
+            duration: self.duration.clone(),
+            sink: self.sink.clone(),
+            audio_buffer: self.audio_buffer.clone(),
+            start_time: self.start_time.clone(),
+            total_paused_ns: self.total_paused_ns.clone(),
+            replay_gain: self.replay_gain.clone(),
+        }
+    }
+}
+
+/// Open a file and read its duration in seconds
+fn probe_file(file_path: &str) -> Result<f64> {
+    let path = Path::new(file_path);
+    if !path.exists() {
+        debug_log!("File not found: {}", file_path);
+        return Err(error(
+            DecoderError::FileNotFound,
+            format!("File not found: {}", file_path),
+        ));
+    }
+
+    // Validate file opening
+    let file = File::open(path).map_err(|e| open_failure(file_path, e))?;
+    let reader = BufReader::new(file);
+    let decoder =
+        Decoder::new(reader).map_err(|e| decode_failure("Failed to create decoder", e))?;
+
+    // Calculate duration from decoder
+    let duration = decoder
+        .total_duration()
+        .unwrap_or(std::time::Duration::ZERO);
+    Ok(duration.as_secs() as f64 + duration.subsec_nanos() as f64 / 1_000_000_000.0)
+}
+
+/// Shared player state touched when loading or stopping
+struct LoadTarget {
+    current_file: Arc<Mutex<Option<String>>>,
+    initialized: Arc<AtomicBool>,
+    state: Arc<Mutex<PlaybackState>>,
+    duration: Arc<Mutex<f64>>,
+    sink: Arc<Mutex<Option<Sink>>>,
+    audio_buffer: Arc<Mutex<Option<Vec<u8>>>>,
+    start_time: Arc<Mutex<Option<u128>>>,
+    total_paused_ns: Arc<Mutex<u128>>,
+    replay_gain: Arc<Mutex<Option<ReplayGainInfo>>>,
+}
+
+impl LoadTarget {
+    /// Stop playback and forget the loaded source
+    fn reset(&self) {
+        self.initialized.store(true, Ordering::SeqCst);
+
+        // Reset time tracking
+        *self.start_time.lock_or_recover() = None;
+        *self.total_paused_ns.lock_or_recover() = 0;
+
+        if let Some(sink) = self.sink.lock_or_recover().take() {
+            debug_log!("Stopping sink");
+            sink.stop();
+        }
+        *self.audio_buffer.lock_or_recover() = None;
+        *self.state.lock_or_recover() = PlaybackState::Stopped;
+        *self.current_file.lock_or_recover() = None;
+        *self.replay_gain.lock_or_recover() = None;
+        debug_log!("State set to Stopped");
+    }
+
+    fn finish_load(
+        &self,
+        file_path: String,
+        duration_seconds: f64,
+        replay_gain: Option<ReplayGainInfo>,
+    ) {
+        *self.duration.lock_or_recover() = duration_seconds;
+        *self.replay_gain.lock_or_recover() = replay_gain;
+        debug_log!(
+            "File loaded successfully, duration: {} seconds",
+            duration_seconds
+        );
+        *self.current_file.lock_or_recover() = Some(file_path);
+        *self.state.lock_or_recover() = PlaybackState::Loaded;
+    }
+}
+
+/// Background load of a file into a player
+pub struct LoadFileTask {
+    target: LoadTarget,
+    file_path: String,
+    // Scan untagged files here rather than on the first `play`
+    normalize: bool,
+    cancel: CancelToken,
+}
+
+impl AudioTask for LoadFileTask {
+    type Output = (f64, Option<ReplayGainInfo>);
+    type JsValue = ();
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        self.cancel.check()?;
+        let duration_seconds = probe_file(&self.file_path)?;
+        let mut replay_gain = replaygain::read_file_tags(&self.file_path);
+        if replay_gain.is_none() && self.normalize {
+            replay_gain = Some(replaygain::scan(Some(&self.file_path), None, &self.cancel)?);
+        }
+        self.cancel.check()?;
+        Ok((duration_seconds, replay_gain))
+    }
+
+    fn resolve(
+        &mut self,
+        _env: Env,
+        (duration_seconds, replay_gain): Self::Output,
+    ) -> Result<Self::JsValue> {
+        // Applied on the JS thread so it cannot interleave with other player calls
+        self.cancel.check()?;
+        self.target.reset();
+        self.target.finish_load(
+            std::mem::take(&mut self.file_path),
+            duration_seconds,
+            replay_gain,
+        );
+        Ok(())
+    }
+}
+
+/// Player state that decides the normalisation gain
+struct GainTarget {
+    current_file: Arc<Mutex<Option<String>>>,
+    volume: Arc<Mutex<f32>>,
+    sink: Arc<Mutex<Option<Sink>>>,
+    normalization: Arc<Mutex<NormalizationConfig>>,
+    replay_gain: Arc<Mutex<Option<ReplayGainInfo>>>,
+}
+
+impl GainTarget {
+    fn normalization_gain(&self) -> f32 {
+        let config = self.normalization.lock_or_recover().clone();
+        self.replay_gain
+            .lock_or_recover()
+            .as_ref()
+            .map_or(1.0, |info| info.gain(&config))
+    }
+
+    fn output_volume(&self) -> f32 {
+        *self.volume.lock_or_recover() * self.normalization_gain()
+    }
+}
+
+/// Background loudness scan of the source loaded in a player
+pub struct ScanGainTask {
+    target: GainTarget,
+    // The loaded source when the scan started; `data` is set for buffers
+    file_path: Option<String>,
+    data: Option<Vec<u8>>,
+    cancel: CancelToken,
+}
+
+impl AudioTask for ScanGainTask {
+    type Output = Option<ReplayGainInfo>;
+    type JsValue = Option<ReplayGainInfo>;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        if let Some(info) = self.target.replay_gain.lock_or_recover().clone() {
+            return Ok(Some(info));
+        }
+        if self.file_path.is_none() {
+            return Ok(None);
+        }
+        debug_log!("No gain tags, scanning loudness");
+        replaygain::scan(
+            self.file_path.as_deref(),
+            self.data.as_deref(),
+            &self.cancel,
+        )
+        .map(Some)
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+        self.cancel.check()?;
+        self.apply(&output);
+        Ok(output)
+    }
+}
+
+impl ScanGainTask {
+    /// Store a scan result, unless another source was loaded in the meantime
+    pub(crate) fn apply(&self, info: &Option<ReplayGainInfo>) {
+        if *self.target.current_file.lock_or_recover() != self.file_path {
+            return;
+        }
+        *self.target.replay_gain.lock_or_recover() = info.clone();
+        if let Some(sink) = self.target.sink.lock_or_recover().as_ref() {
+            sink.set_volume(self.target.output_volume());
+        }
+    }
+}
+
 // Factory functions
 
 #[napi]
@@ -0,0 +1,557 @@
+//! Playlist file import/export (M3U/M3U8, PLS, XSPF and CUE sheets)
+
+use crate::error::{error, io_failure, Result};
+use crate::types::DecoderError;
+use napi_derive::napi;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// CUE sheet timestamps count 75 frames per second
+const CUE_FRAMES_PER_SECOND: f64 = 75.0;
+
+/// Supported playlist file formats
+#[napi(string_enum)]
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum PlaylistFormat {
+    M3u,
+    M3u8,
+    Pls,
+    Xspf,
+    Cue,
+}
+
+/// One entry read from or written to a playlist file
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PlaylistEntry {
+    pub location: String,
+    pub title: Option<String>,
+    /// Duration in seconds
+    pub duration: Option<f64>,
+    /// Start of the entry within `location` in seconds (CUE tracks)
+    pub start_time: Option<f64>,
+    /// End of the entry within `location` in seconds (CUE tracks)
+    pub end_time: Option<f64>,
+}
+
+impl PlaylistFormat {
+    /// Guess the format from a file extension
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_lowercase();
+        match ext.as_str() {
+            "m3u" => Some(Self::M3u),
+            "m3u8" => Some(Self::M3u8),
+            "pls" => Some(Self::Pls),
+            "xspf" => Some(Self::Xspf),
+            "cue" => Some(Self::Cue),
+            _ => None,
+        }
+    }
+
+    /// Guess the format from the file contents
+    pub fn sniff(content: &str) -> Option<Self> {
+        let head = content.trim_start_matches('\u{feff}').trim_start();
+        let lower = head.to_lowercase();
+        if lower.starts_with("#extm3u") {
+            Some(Self::M3u8)
+        } else if lower.starts_with("[playlist]") {
+            Some(Self::Pls)
+        } else if lower.starts_with("<?xml") || lower.starts_with("<playlist") {
+            Some(Self::Xspf)
+        } else if head.lines().any(|l| l.trim_start().starts_with("FILE ")) {
+            Some(Self::Cue)
+        } else {
+            None
+        }
+    }
+}
+
+/// Read a playlist file and resolve its entries against the playlist's directory
+pub fn read_playlist(path: &Path, format: Option<PlaylistFormat>) -> Result<Vec<PlaylistEntry>> {
+    let bytes = std::fs::read(path).map_err(|e| {
+        io_failure(
+            format!("Failed to read playlist {}: {}", path.display(), e),
+            e,
+        )
+    })?;
+    // Plain .m3u files are traditionally Latin-1; everything else is UTF-8
+    let content = match String::from_utf8(bytes) {
+        Ok(text) => text,
+        Err(e) => e.into_bytes().iter().map(|&b| b as char).collect(),
+    };
+
+    let format = format
+        .or_else(|| PlaylistFormat::from_path(path))
+        .or_else(|| PlaylistFormat::sniff(&content))
+        .ok_or_else(|| {
+            error(
+                DecoderError::InvalidFormat,
+                format!("Unrecognised playlist format: {}", path.display()),
+            )
+        })?;
+
+    let entries = match format {
+        PlaylistFormat::M3u | PlaylistFormat::M3u8 => parse_m3u(&content),
+        PlaylistFormat::Pls => parse_pls(&content),
+        PlaylistFormat::Xspf => parse_xspf(&content),
+        PlaylistFormat::Cue => parse_cue(&content),
+    };
+
+    let base = path.parent().unwrap_or_else(|| Path::new(""));
+    Ok(entries
+        .into_iter()
+        .map(|mut entry| {
+            entry.location = resolve_location(base, &entry.location);
+            entry
+        })
+        .collect())
+}
+
+/// Write entries to a playlist file; locations inside the playlist's directory are made relative
+pub fn write_playlist(
+    path: &Path,
+    format: PlaylistFormat,
+    entries: &[PlaylistEntry],
+) -> Result<()> {
+    let base = path.parent().unwrap_or_else(|| Path::new(""));
+    let entries: Vec<PlaylistEntry> = entries
+        .iter()
+        .cloned()
+        .map(|mut entry| {
+            entry.location = relative_location(base, &entry.location);
+            entry
+        })
+        .collect();
+
+    let content = match format {
+        PlaylistFormat::M3u | PlaylistFormat::M3u8 => format_m3u(&entries),
+        PlaylistFormat::Pls => format_pls(&entries),
+        PlaylistFormat::Xspf => format_xspf(&entries),
+        PlaylistFormat::Cue => format_cue(&entries),
+    };
+
+    std::fs::write(path, content).map_err(|e| {
+        io_failure(
+            format!("Failed to write playlist {}: {}", path.display(), e),
+            e,
+        )
+    })
+}
+
+fn is_url(location: &str) -> bool {
+    location.contains("://") && !location.starts_with("file://")
+}
+
+fn resolve_location(base: &Path, location: &str) -> String {
+    if is_url(location) {
+        return location.to_string();
+    }
+    let location = location.strip_prefix("file://").unwrap_or(location);
+    let path = PathBuf::from(location.replace('\\', "/"));
+    if path.is_absolute() {
+        path.to_string_lossy().into_owned()
+    } else {
+        base.join(path).to_string_lossy().into_owned()
+    }
+}
+
+fn relative_location(base: &Path, location: &str) -> String {
+    if is_url(location) || base.as_os_str().is_empty() {
+        return location.to_string();
+    }
+    Path::new(location)
+        .strip_prefix(base)
+        .map(|p| p.to_string_lossy().into_owned())
+        .unwrap_or_else(|_| location.to_string())
+}
+
+/// Treat negative or missing lengths (-1 means "unknown" in M3U and PLS) as None
+fn parse_seconds(value: &str) -> Option<f64> {
+    value.trim().parse::<f64>().ok().filter(|d| *d >= 0.0)
+}
+
+// =====================================================================
+// M3U / M3U8
+// =====================================================================
+
+fn parse_m3u(content: &str) -> Vec<PlaylistEntry> {
+    let mut entries = Vec::new();
+    let mut pending = PlaylistEntry::default();
+
+    for line in content.lines() {
+        let line = line.trim().trim_start_matches('\u{feff}');
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(info) = line.strip_prefix("#EXTINF:") {
+            // #EXTINF:<seconds> [attributes],<title>
+            let (head, title) = info.split_once(',').unwrap_or((info, ""));
+            pending.duration = head.split_whitespace().next().and_then(parse_seconds);
+            pending.title = Some(title.trim().to_string()).filter(|t| !t.is_empty());
+        } else if !line.starts_with('#') {
+            pending.location = line.to_string();
+            entries.push(std::mem::take(&mut pending));
+        }
+    }
+
+    entries
+}
+
+fn format_m3u(entries: &[PlaylistEntry]) -> String {
+    let mut out = String::from("#EXTM3U\n");
+    for entry in entries {
+        if entry.title.is_some() || entry.duration.is_some() {
+            let duration = entry.duration.map_or(-1, |d| d.round() as i64);
+            out.push_str(&format!(
+                "#EXTINF:{},{}\n",
+                duration,
+                entry.title.as_deref().unwrap_or("")
+            ));
+        }
+        out.push_str(&entry.location);
+        out.push('\n');
+    }
+    out
+}
+
+// =====================================================================
+// PLS
+// =====================================================================
+
+fn parse_pls(content: &str) -> Vec<PlaylistEntry> {
+    let mut entries: BTreeMap<u32, PlaylistEntry> = BTreeMap::new();
+
+    for line in content.lines() {
+        let Some((key, value)) = line.trim().split_once('=') else {
+            continue;
+        };
+        let key = key.trim().to_lowercase();
+        let value = value.trim();
+        let (field, number) = key.split_at(key.find(|c: char| c.is_ascii_digit()).unwrap_or(0));
+        let Ok(number) = number.parse::<u32>() else {
+            continue;
+        };
+
+        let entry = entries.entry(number).or_default();
+        match field {
+            "file" => entry.location = value.to_string(),
+            "title" => entry.title = Some(value.to_string()).filter(|t| !t.is_empty()),
+            "length" => entry.duration = parse_seconds(value),
+            _ => {}
+        }
+    }
+
+    entries
+        .into_values()
+        .filter(|e| !e.location.is_empty())
+        .collect()
+}
+
+fn format_pls(entries: &[PlaylistEntry]) -> String {
+    let mut out = String::from("[playlist]\n");
+    for (i, entry) in entries.iter().enumerate() {
+        let n = i + 1;
+        out.push_str(&format!("File{}={}\n", n, entry.location));
+        if let Some(title) = &entry.title {
+            out.push_str(&format!("Title{}={}\n", n, title));
+        }
+        let duration = entry.duration.map_or(-1, |d| d.round() as i64);
+        out.push_str(&format!("Length{}={}\n", n, duration));
+    }
+    out.push_str(&format!("NumberOfEntries={}\nVersion=2\n", entries.len()));
+    out
+}
+
+// =====================================================================
+// XSPF
+// =====================================================================
+
+/// Contents of the first `<tag>...</tag>` element in `xml`
+fn xml_element<'a>(xml: &'a str, tag: &str) -> Option<&'a str> {
+    let open = format!("<{}>", tag);
+    let close = format!("</{}>", tag);
+    let start = xml.find(&open)? + open.len();
+    let end = start + xml[start..].find(&close)?;
+    Some(&xml[start..end])
+}
+
+fn xml_unescape(text: &str) -> String {
+    let text = text.trim();
+    if let Some(cdata) = text
+        .strip_prefix("<![CDATA[")
+        .and_then(|t| t.strip_suffix("]]>"))
+    {
+        return cdata.to_string();
+    }
+
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let Some(semi) = rest.find(';') else {
+            break;
+        };
+        let entity = &rest[1..semi];
+        let decoded = match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => entity
+                .strip_prefix("#x")
+                .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                .or_else(|| entity.strip_prefix('#').and_then(|dec| dec.parse().ok()))
+                .and_then(char::from_u32),
+        };
+        match decoded {
+            Some(c) => {
+                out.push(c);
+                rest = &rest[semi + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 3 <= bytes.len() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap_or("");
+            if let Ok(byte) = u8::from_str_radix(hex, 16) {
+                out.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+fn percent_encode(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for byte in text.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                out.push(byte as char)
+            }
+            _ => out.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    out
+}
+
+fn parse_xspf(content: &str) -> Vec<PlaylistEntry> {
+    let mut entries = Vec::new();
+    let mut rest = content;
+
+    while let Some(start) = rest.find("<track>") {
+        let Some(len) = rest[start..].find("</track>") else {
+            break;
+        };
+        let track = &rest[start..start + len];
+        rest = &rest[start + len..];
+
+        let Some(location) = xml_element(track, "location").map(xml_unescape) else {
+            continue;
+        };
+        let location = if is_url(&location) {
+            location
+        } else {
+            percent_decode(&location)
+        };
+        entries.push(PlaylistEntry {
+            location,
+            title: xml_element(track, "title").map(xml_unescape),
+            // XSPF durations are in milliseconds
+            duration: xml_element(track, "duration")
+                .and_then(parse_seconds)
+                .map(|ms| ms / 1000.0),
+            ..Default::default()
+        });
+    }
+
+    entries
+}
+
+fn format_xspf(entries: &[PlaylistEntry]) -> String {
+    let mut out = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <playlist version=\"1\" xmlns=\"http://xspf.org/ns/0/\">\n  <trackList>\n",
+    );
+    for entry in entries {
+        let location = if is_url(&entry.location) {
+            entry.location.clone()
+        } else if Path::new(&entry.location).is_absolute() {
+            format!("file://{}", percent_encode(&entry.location))
+        } else {
+            percent_encode(&entry.location)
+        };
+        out.push_str("    <track>\n");
+        out.push_str(&format!(
+            "      <location>{}</location>\n",
+            xml_escape(&location)
+        ));
+        if let Some(title) = &entry.title {
+            out.push_str(&format!("      <title>{}</title>\n", xml_escape(title)));
+        }
+        if let Some(duration) = entry.duration {
+            out.push_str(&format!(
+                "      <duration>{}</duration>\n",
+                (duration * 1000.0).round() as u64
+            ));
+        }
+        out.push_str("    </track>\n");
+    }
+    out.push_str("  </trackList>\n</playlist>\n");
+    out
+}
+
+// =====================================================================
+// CUE sheets
+// =====================================================================
+
+/// Split a CUE command line into words, keeping quoted strings together
+fn cue_words(line: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    for c in line.chars() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            c if c.is_whitespace() && !in_quotes => {
+                if !current.is_empty() {
+                    words.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        words.push(current);
+    }
+    words
+}
+
+/// Parse an `mm:ss:ff` CUE timestamp into seconds
+fn parse_cue_time(value: &str) -> Option<f64> {
+    let parts: Vec<f64> = value
+        .split(':')
+        .map(|p| p.parse::<f64>().ok())
+        .collect::<Option<_>>()?;
+    match parts.as_slice() {
+        [m, s, f] => Some(m * 60.0 + s + f / CUE_FRAMES_PER_SECOND),
+        _ => None,
+    }
+}
+
+fn format_cue_time(seconds: f64) -> String {
+    let total_frames = (seconds * CUE_FRAMES_PER_SECOND).round() as u64;
+    let frames = total_frames % 75;
+    let total_seconds = total_frames / 75;
+    format!(
+        "{:02}:{:02}:{:02}",
+        total_seconds / 60,
+        total_seconds % 60,
+        frames
+    )
+}
+
+fn parse_cue(content: &str) -> Vec<PlaylistEntry> {
+    let mut entries: Vec<PlaylistEntry> = Vec::new();
+    let mut file: Option<String> = None;
+    // Index into `entries` of the first track of the current FILE
+    let mut file_start = 0;
+    let mut in_track = false;
+
+    for line in content.lines() {
+        let words = cue_words(line);
+        let Some(command) = words.first() else {
+            continue;
+        };
+        match command.to_uppercase().as_str() {
+            "FILE" => {
+                file = words.get(1).cloned();
+                file_start = entries.len();
+                in_track = false;
+            }
+            "TRACK" => {
+                if let Some(file) = &file {
+                    entries.push(PlaylistEntry {
+                        location: file.clone(),
+                        ..Default::default()
+                    });
+                    in_track = true;
+                }
+            }
+            "TITLE" if in_track => {
+                if let Some(entry) = entries.last_mut() {
+                    entry.title = words.get(1).cloned();
+                }
+            }
+            "INDEX" if in_track && words.get(1).map(String::as_str) == Some("01") => {
+                let start = words.get(2).and_then(|t| parse_cue_time(t));
+                let index = entries.len() - 1;
+                entries[index].start_time = start;
+                // The previous track of the same file ends where this one starts
+                if index > file_start {
+                    entries[index - 1].end_time = start;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    for entry in &mut entries {
+        if let (Some(start), Some(end)) = (entry.start_time, entry.end_time) {
+            entry.duration = Some(end - start);
+        }
+    }
+    entries
+}
+
+fn format_cue(entries: &[PlaylistEntry]) -> String {
+    let mut out = String::new();
+    let mut current_file: Option<&str> = None;
+    for (i, entry) in entries.iter().enumerate() {
+        if current_file != Some(entry.location.as_str()) {
+            let file_type = match Path::new(&entry.location)
+                .extension()
+                .and_then(|e| e.to_str())
+                .map(|e| e.to_lowercase())
+                .as_deref()
+            {
+                Some("mp3") => "MP3",
+                _ => "WAVE",
+            };
+            out.push_str(&format!("FILE \"{}\" {}\n", entry.location, file_type));
+            current_file = Some(&entry.location);
+        }
+        out.push_str(&format!("  TRACK {:02} AUDIO\n", i + 1));
+        if let Some(title) = &entry.title {
+            out.push_str(&format!("    TITLE \"{}\"\n", title.replace('"', "'")));
+        }
+        out.push_str(&format!(
+            "    INDEX 01 {}\n",
+            format_cue_time(entry.start_time.unwrap_or(0.0))
+        ));
+    }
+    out
+}
@@ -0,0 +1,299 @@
+//! Content-based format probing - sniffs magic bytes and container headers
+
+use crate::error::{caused_by, error, io_failure, Result};
+use crate::types::DecoderError;
+use napi::bindgen_prelude::{Buffer, Either};
+use napi_derive::napi;
+use std::fs::File;
+use std::io::{Cursor, Read, Seek, SeekFrom};
+use symphonia::core::codecs::{DecoderOptions, CODEC_TYPE_MP3, CODEC_TYPE_NULL};
+use symphonia::core::formats::{FormatOptions, SeekMode, SeekTo};
+use symphonia::core::io::{MediaSource, MediaSourceStream};
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+
+/// Bytes read from the start of a file for sniffing
+const SNIFF_LEN: u64 = 64 * 1024;
+
+/// Stream information read from an input's headers
+#[napi(object)]
+#[derive(Debug, Clone)]
+pub struct AudioProbe {
+    /// Container detected from the magic bytes: wav, mp3, flac, ogg, mp4 or adts
+    pub container: String,
+    /// Codec short name, e.g. "pcm_s16le", "mp3", "flac", "vorbis" or "aac"
+    pub codec: String,
+    pub sample_rate: u32,
+    pub channels: u16,
+    /// Bits per sample; unset for lossy codecs that do not declare one
+    pub bit_depth: Option<u32>,
+    /// Bits per second; exact for PCM, otherwise averaged over the input size
+    pub bitrate: Option<u32>,
+    /// Duration in seconds; unset when the headers do not declare a length
+    pub duration: Option<f64>,
+    /// Whether the container supports seeking
+    pub seekable: bool,
+    /// Whether packet sizes vary (always for FLAC/Vorbis/AAC, Xing/VBRI-tagged MP3)
+    pub is_vbr: bool,
+}
+
+/// Containers recognised by their magic bytes
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Container {
+    Wav,
+    Mp3,
+    Flac,
+    Ogg,
+    Mp4,
+    Adts,
+}
+
+impl Container {
+    fn name(self) -> &'static str {
+        match self {
+            Container::Wav => "wav",
+            Container::Mp3 => "mp3",
+            Container::Flac => "flac",
+            Container::Ogg => "ogg",
+            Container::Mp4 => "mp4",
+            Container::Adts => "adts",
+        }
+    }
+}
+
+/// Inspect a file path or an in-memory buffer without decoding it for playback.
+/// Throws an error whose `code` is a `DecoderError` when the input cannot be played.
+#[napi]
+pub fn probe(input: Either<String, Buffer>) -> Result<AudioProbe> {
+    match input {
+        Either::A(path) => probe_file(&path),
+        Either::B(bytes) => probe_bytes(bytes.to_vec()),
+    }
+}
+
+pub(crate) fn probe_file(path: &str) -> Result<AudioProbe> {
+    let open = || {
+        File::open(path)
+            .map_err(|e| io_failure(format!("Failed to open file '{}': {}", path, e), e))
+    };
+
+    let read_at = |offset: u64| -> Result<Vec<u8>> {
+        let mut file = open()?;
+        let mut bytes = Vec::new();
+        file.seek(SeekFrom::Start(offset))
+            .and_then(|_| file.take(SNIFF_LEN).read_to_end(&mut bytes))
+            .map_err(|e| io_failure(format!("Failed to read file: {}", e), e))?;
+        Ok(bytes)
+    };
+
+    let size = open()?.metadata().map(|m| m.len()).ok();
+    let header = read_at(0)?;
+    // ID3 tags can be far larger than the sniffed bytes, so read the frame after them
+    let frame = match id3_len(&header) {
+        0 => header.clone(),
+        tag => read_at(tag as u64)?,
+    };
+
+    probe_source(&header, &frame, Box::new(open()?), size)
+}
+
+pub(crate) fn probe_bytes(bytes: Vec<u8>) -> Result<AudioProbe> {
+    let size = bytes.len() as u64;
+    let window = |offset: usize| {
+        let start = offset.min(bytes.len());
+        bytes[start..bytes.len().min(start + SNIFF_LEN as usize)].to_vec()
+    };
+    let header = window(0);
+    let frame = window(id3_len(&header));
+    probe_source(&header, &frame, Box::new(Cursor::new(bytes)), Some(size))
+}
+
+/// `header` is the start of the input, `frame` the start of the audio after any ID3 tag
+fn probe_source(
+    header: &[u8],
+    frame: &[u8],
+    source: Box<dyn MediaSource>,
+    size: Option<u64>,
+) -> Result<AudioProbe> {
+    if header.is_empty() {
+        return Err(error(DecoderError::EmptyInput, "Input is empty"));
+    }
+    let container = sniff_container(header, frame)?;
+
+    let mut hint = Hint::new();
+    hint.with_extension(container.name());
+    let mss = MediaSourceStream::new(source, Default::default());
+    let probed = symphonia::default::get_probe()
+        .format(
+            &hint,
+            mss,
+            &FormatOptions::default(),
+            &MetadataOptions::default(),
+        )
+        .map_err(|e| {
+            caused_by(
+                DecoderError::CorruptedData,
+                format!("Failed to read {} headers: {}", container.name(), e),
+                e,
+            )
+        })?;
+    let mut reader = probed.format;
+
+    let track = reader
+        .tracks()
+        .iter()
+        .find(|t| t.codec_params.codec != CODEC_TYPE_NULL)
+        .cloned()
+        .ok_or_else(|| error(DecoderError::NoAudioTrack, "No audio track found"))?;
+    let params = &track.codec_params;
+
+    let codecs = symphonia::default::get_codecs();
+    let codec = codecs.get_codec(params.codec).ok_or_else(|| {
+        error(
+            DecoderError::UnsupportedCodec,
+            format!(
+                "The {} audio codec ({}) is not supported",
+                container.name(),
+                params.codec
+            ),
+        )
+    })?;
+
+    // Decode the first packet so corrupted streams are caught before playback
+    let mut decoder = codecs
+        .make(params, &DecoderOptions::default())
+        .map_err(|e| caused_by(DecoderError::CorruptedData, e.to_string(), e))?;
+    let packet = loop {
+        let packet = reader.next_packet().map_err(|e| {
+            caused_by(
+                DecoderError::CorruptedData,
+                format!("Failed to read the first packet: {}", e),
+                e,
+            )
+        })?;
+        if packet.track_id() == track.id {
+            break packet;
+        }
+    };
+    let decoded_spec = decoder
+        .decode(&packet)
+        .map(|buffer| *buffer.spec())
+        .map_err(|e| {
+            caused_by(
+                DecoderError::CorruptedData,
+                format!("Failed to decode the first packet: {}", e),
+                e,
+            )
+        })?;
+
+    let sample_rate = params.sample_rate.unwrap_or(decoded_spec.rate);
+    let channels = params.channels.unwrap_or(decoded_spec.channels).count() as u16;
+    let bit_depth = params.bits_per_sample.or(params.bits_per_coded_sample);
+    let duration = params
+        .n_frames
+        .filter(|_| sample_rate > 0)
+        .map(|frames| frames as f64 / sample_rate as f64);
+
+    let is_vbr = match container {
+        Container::Wav => false,
+        Container::Mp3 => params.codec == CODEC_TYPE_MP3 && has_vbr_header(frame),
+        _ => true,
+    };
+    let bitrate = match (container, bit_depth) {
+        (Container::Wav, Some(bits)) => Some(sample_rate * channels as u32 * bits),
+        _ => match (size, duration) {
+            (Some(size), Some(duration)) if duration > 0.0 => {
+                Some((size as f64 * 8.0 / duration) as u32)
+            }
+            _ => None,
+        },
+    };
+
+    let seekable = reader
+        .seek(
+            SeekMode::Coarse,
+            SeekTo::TimeStamp {
+                ts: 0,
+                track_id: track.id,
+            },
+        )
+        .is_ok();
+
+    Ok(AudioProbe {
+        container: container.name().to_string(),
+        codec: codec.short_name.to_string(),
+        sample_rate,
+        channels,
+        bit_depth,
+        bitrate,
+        duration,
+        seekable,
+        is_vbr,
+    })
+}
+
+/// Identify the container from its leading bytes
+fn sniff_container(header: &[u8], frame: &[u8]) -> Result<Container> {
+    let unsupported = |name: &str| {
+        Err(error(
+            DecoderError::UnsupportedFormat,
+            format!("{} files are not supported", name),
+        ))
+    };
+
+    if header.len() >= 12 && &header[0..4] == b"RIFF" && &header[8..12] == b"WAVE" {
+        return Ok(Container::Wav);
+    }
+    if header.starts_with(b"fLaC") {
+        return Ok(Container::Flac);
+    }
+    if header.starts_with(b"OggS") {
+        return Ok(Container::Ogg);
+    }
+    if header.len() >= 8 && &header[4..8] == b"ftyp" {
+        return Ok(Container::Mp4);
+    }
+    if header.len() >= 12 && &header[0..4] == b"FORM" && &header[8..11] == b"AIF" {
+        return unsupported("AIFF");
+    }
+    if header.starts_with(&[0x1A, 0x45, 0xDF, 0xA3]) {
+        return unsupported("Matroska/WebM");
+    }
+    if header.starts_with(b"caff") {
+        return unsupported("CAF");
+    }
+
+    // MPEG audio and ADTS share a sync word; ID3 tags may precede either
+    if frame.len() >= 2 && frame[0] == 0xFF && frame[1] & 0xE0 == 0xE0 {
+        return Ok(if frame[1] & 0x06 == 0 {
+            Container::Adts
+        } else {
+            Container::Mp3
+        });
+    }
+
+    Err(error(
+        DecoderError::InvalidFormat,
+        "Unrecognised audio format",
+    ))
+}
+
+/// Size of a leading ID3v2 tag, or 0 when there is none
+fn id3_len(header: &[u8]) -> usize {
+    if header.len() < 10 || !header.starts_with(b"ID3") {
+        return 0;
+    }
+    // Tag size is a 28-bit syncsafe integer, excluding the 10 byte header
+    // and the 10 byte footer that ID3v2.4 flags with bit 4
+    let size = header[6..10]
+        .iter()
+        .fold(0usize, |acc, &b| (acc << 7) | (b & 0x7F) as usize);
+    let footer = if header[5] & 0x10 != 0 { 10 } else { 0 };
+    10 + size + footer
+}
+
+/// Whether the first MP3 frame carries a Xing or VBRI (variable bitrate) header
+fn has_vbr_header(frame: &[u8]) -> bool {
+    let search = &frame[..frame.len().min(256)];
+    search.windows(4).any(|w| w == b"Xing" || w == b"VBRI")
+}
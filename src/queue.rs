@@ -1,8 +1,64 @@
 //! Queue management for audio sources - handles multiple audio sources in sequence
 
-use napi::{Error, Result, Status};
+use crate::conversions::pcm_to_i16;
+use crate::error::{error, ErrorCode, LockExt, Result};
+use crate::playlist::{self, PlaylistEntry, PlaylistFormat};
+use crate::types::StreamError;
+use napi::bindgen_prelude::{AsyncTask, Either, Float32Array, Int16Array};
+use napi::threadsafe_function::{ThreadsafeFunction, ThreadsafeFunctionCallMode};
+use napi::{Env, Task};
 use napi_derive::napi;
-use std::sync::{Arc, Mutex};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+/// How the queue picks the next item
+#[napi(string_enum)]
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum QueuePlaybackMode {
+    /// Play in order and stop after the last item
+    RepeatOff,
+    /// Keep returning the current item
+    RepeatOne,
+    /// Play in order and wrap around to the first item
+    RepeatAll,
+    /// Play every item once in random order, then reshuffle
+    Shuffle,
+}
+
+/// Playback-mode state: shuffle order and the history walked by `previous`
+struct QueuePlayback {
+    mode: QueuePlaybackMode,
+    rng: StdRng,
+    shuffle_order: Vec<usize>,
+    history: Vec<usize>,
+}
+
+impl QueuePlayback {
+    fn new() -> Self {
+        Self {
+            mode: QueuePlaybackMode::RepeatOff,
+            rng: StdRng::from_entropy(),
+            shuffle_order: Vec::new(),
+            history: Vec::new(),
+        }
+    }
+
+    /// Fill the shuffle order with a Fisher-Yates permutation of every index except `current`
+    fn reshuffle(&mut self, len: usize, current: usize) {
+        let mut order: Vec<usize> = (0..len).filter(|&i| i != current).collect();
+        for i in (1..order.len()).rev() {
+            let j = self.rng.gen_range(0..=i);
+            order.swap(i, j);
+        }
+        // Popped from the back, so the first item to play is last
+        order.reverse();
+        self.shuffle_order = order;
+    }
+}
 
 /// A queue for managing multiple audio sources that play in sequence
 #[napi]
@@ -10,6 +66,8 @@ pub struct AudioSourceQueue {
     sources: Arc<Mutex<Vec<AudioQueueItem>>>,
     current_index: Arc<Mutex<usize>>,
     is_playing: Arc<Mutex<bool>>,
+    playback: Arc<Mutex<QueuePlayback>>,
+    next_id: Arc<AtomicU64>,
 }
 
 impl Default for AudioSourceQueue {
@@ -19,12 +77,108 @@ impl Default for AudioSourceQueue {
 }
 
 #[napi(object)]
-#[derive(Clone)]
+#[derive(Default)]
 pub struct AudioQueueItem {
     pub source_id: String,
     pub file_path: Option<String>,
-    pub buffer: Option<Vec<i16>>,
+    pub buffer: Option<Int16Array>,
     pub title: Option<String>,
+    /// Duration in seconds, when known
+    pub duration: Option<f64>,
+    /// Start of the item within `file_path` in seconds (e.g. a CUE track).
+    /// Metadata only: play the range with `AudioDecoder.decodeSlice`.
+    pub start_time: Option<f64>,
+    /// End of the item within `file_path` in seconds (e.g. a CUE track); unset
+    /// means the end of the file. Metadata only, like `startTime`.
+    pub end_time: Option<f64>,
+}
+
+// Typed arrays are not `Clone`; copy the samples instead
+impl Clone for AudioQueueItem {
+    fn clone(&self) -> Self {
+        Self {
+            source_id: self.source_id.clone(),
+            file_path: self.file_path.clone(),
+            buffer: self.buffer.as_ref().map(|b| Int16Array::new(b.to_vec())),
+            title: self.title.clone(),
+            duration: self.duration,
+            start_time: self.start_time,
+            end_time: self.end_time,
+        }
+    }
+}
+
+impl AudioSourceQueue {
+    /// Generate an ID that is never reused, even after items are removed
+    fn next_source_id(&self) -> String {
+        format!("source_{}", self.next_id.fetch_add(1, Ordering::SeqCst))
+    }
+
+    /// Insert a new item at `index` and keep the current index on the same item.
+    /// The item's `source_id` is replaced with a freshly generated one.
+    fn insert_item(&self, index: usize, item: AudioQueueItem) -> Result<String> {
+        let mut sources = self.sources.lock_or_recover();
+        if index > sources.len() {
+            return Err(error(
+                ErrorCode::InvalidArgument,
+                format!("Index out of bounds: {} > {}", index, sources.len()),
+            ));
+        }
+
+        let id = self.next_source_id();
+        let previous_len = sources.len();
+        sources.insert(
+            index,
+            AudioQueueItem {
+                source_id: id.clone(),
+                ..item
+            },
+        );
+
+        // Appending shifts nothing, so a consumed-to-the-end index then points at the new item
+        if index < previous_len {
+            self.remap_indices(0, |i| Some(if i >= index { i + 1 } else { i }));
+        }
+        if previous_len > 0 {
+            self.schedule_shuffled(index);
+        }
+        Ok(id)
+    }
+
+    /// Rewrite the current index, history and shuffle order after items moved.
+    /// `map` gives the new position of an old index, or None if that item was removed,
+    /// in which case the current index falls back to `removed_fallback`.
+    fn remap_indices<F>(&self, removed_fallback: usize, map: F)
+    where
+        F: Fn(usize) -> Option<usize>,
+    {
+        let mut current = self.current_index.lock_or_recover();
+        let mut playback = self.playback.lock_or_recover();
+        *current = map(*current).unwrap_or(removed_fallback);
+        playback.history = playback.history.iter().filter_map(|&i| map(i)).collect();
+        playback.shuffle_order = playback
+            .shuffle_order
+            .iter()
+            .filter_map(|&i| map(i))
+            .collect();
+    }
+
+    /// Make a newly added item part of the remaining shuffle order
+    fn schedule_shuffled(&self, index: usize) {
+        let mut playback = self.playback.lock_or_recover();
+        if playback.mode == QueuePlaybackMode::Shuffle {
+            let upcoming = playback.shuffle_order.len();
+            let position = playback.rng.gen_range(0..=upcoming);
+            playback.shuffle_order.insert(position, index);
+        }
+    }
+
+    fn position_of(sources: &[AudioQueueItem], source_id: &str) -> Result<usize> {
+        sources
+            .iter()
+            .position(|s| s.source_id == source_id)
+            .ok_or_else(|| error(ErrorCode::InvalidArgument, "Source not found"))
+    }
 }
 
 #[napi]
@@ -35,109 +189,470 @@ impl AudioSourceQueue {
             sources: Arc::new(Mutex::new(Vec::new())),
             current_index: Arc::new(Mutex::new(0)),
             is_playing: Arc::new(Mutex::new(false)),
+            playback: Arc::new(Mutex::new(QueuePlayback::new())),
+            next_id: Arc::new(AtomicU64::new(0)),
         }
     }
 
     /// Add an audio source from a file
     #[napi]
     pub fn add_source(&mut self, file_path: String, title: Option<String>) -> Result<String> {
-        let id = format!("source_{}", self.sources.lock().unwrap().len());
-        let mut sources = self.sources.lock().unwrap();
-        sources.push(AudioQueueItem {
-            source_id: id.clone(),
-            file_path: Some(file_path),
-            buffer: None,
-            title,
-        });
-        Ok(id)
+        let len = self.sources.lock_or_recover().len();
+        self.insert_item(
+            len,
+            AudioQueueItem {
+                file_path: Some(file_path),
+                title,
+                ..Default::default()
+            },
+        )
     }
 
-    /// Add an audio source from a buffer
+    /// Add an audio source from an `Int16Array` or a `Float32Array` in [-1, 1]
     #[napi]
-    pub fn add_buffer(&mut self, buffer: Vec<i16>, title: Option<String>) -> Result<String> {
-        let id = format!("source_{}", self.sources.lock().unwrap().len());
-        let mut sources = self.sources.lock().unwrap();
-        sources.push(AudioQueueItem {
-            source_id: id.clone(),
-            file_path: None,
-            buffer: Some(buffer),
-            title,
-        });
-        Ok(id)
+    pub fn add_buffer(
+        &mut self,
+        buffer: Either<Int16Array, Float32Array>,
+        title: Option<String>,
+    ) -> Result<String> {
+        let len = self.sources.lock_or_recover().len();
+        self.insert_item(
+            len,
+            AudioQueueItem {
+                buffer: Some(Int16Array::new(pcm_to_i16(&buffer))),
+                title,
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Insert an audio source from a file at the given position
+    #[napi]
+    pub fn insert_at(
+        &mut self,
+        index: u32,
+        file_path: String,
+        title: Option<String>,
+    ) -> Result<String> {
+        self.insert_item(
+            index as usize,
+            AudioQueueItem {
+                file_path: Some(file_path),
+                title,
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Insert an audio source from a buffer at the given position
+    #[napi]
+    pub fn insert_buffer_at(
+        &mut self,
+        index: u32,
+        buffer: Either<Int16Array, Float32Array>,
+        title: Option<String>,
+    ) -> Result<String> {
+        self.insert_item(
+            index as usize,
+            AudioQueueItem {
+                buffer: Some(Int16Array::new(pcm_to_i16(&buffer))),
+                title,
+                ..Default::default()
+            },
+        )
     }
 
-    /// Remove a source by its ID
+    /// Append the entries of an M3U/M3U8, PLS, XSPF or CUE playlist file.
+    /// The format is taken from `format`, the file extension or the contents, in that order.
+    /// Relative paths are resolved against the playlist's directory. CUE tracks share
+    /// one file and carry their range in `startTime`/`endTime`; the queue does not trim it.
+    /// Returns the IDs of the added items.
+    #[napi]
+    pub fn load_playlist(
+        &mut self,
+        path: String,
+        format: Option<PlaylistFormat>,
+    ) -> Result<Vec<String>> {
+        let entries = playlist::read_playlist(Path::new(&path), format)?;
+        let mut ids = Vec::with_capacity(entries.len());
+        for entry in entries {
+            let len = self.sources.lock_or_recover().len();
+            ids.push(self.insert_item(
+                len,
+                AudioQueueItem {
+                    file_path: Some(entry.location),
+                    title: entry.title,
+                    duration: entry.duration,
+                    start_time: entry.start_time,
+                    end_time: entry.end_time,
+                    ..Default::default()
+                },
+            )?);
+        }
+        Ok(ids)
+    }
+
+    /// Save the file-backed items of the queue as a playlist.
+    /// Buffer items have no location and are skipped. Returns the number of entries written.
+    #[napi]
+    pub fn save_playlist(&self, path: String, format: PlaylistFormat) -> Result<u32> {
+        let entries: Vec<PlaylistEntry> = self
+            .sources
+            .lock_or_recover()
+            .iter()
+            .filter_map(|item| {
+                Some(PlaylistEntry {
+                    location: item.file_path.clone()?,
+                    title: item.title.clone(),
+                    duration: item.duration,
+                    start_time: item.start_time,
+                    end_time: item.end_time,
+                })
+            })
+            .collect();
+        playlist::write_playlist(Path::new(&path), format, &entries)?;
+        Ok(entries.len() as u32)
+    }
+
+    /// Remove a source by its ID.
+    /// If it was the current item, the current index moves to the item that followed it,
+    /// or to the new last item when the last one was removed.
     #[napi]
     pub fn remove_source(&mut self, source_id: String) -> Result<()> {
-        let mut sources = self.sources.lock().unwrap();
-        if let Some(pos) = sources.iter().position(|s| s.source_id == source_id) {
-            sources.remove(pos);
-            Ok(())
-        } else {
-            Err(Error::new(Status::InvalidArg, "Source not found"))
+        let mut sources = self.sources.lock_or_recover();
+        let pos = Self::position_of(&sources, &source_id)?;
+        sources.remove(pos);
+        let fallback = pos.min(sources.len().saturating_sub(1));
+        self.remap_indices(fallback, |i| match i.cmp(&pos) {
+            std::cmp::Ordering::Less => Some(i),
+            std::cmp::Ordering::Equal => None,
+            std::cmp::Ordering::Greater => Some(i - 1),
+        });
+        Ok(())
+    }
+
+    /// Move a source to a new position in the queue
+    #[napi]
+    pub fn move_item(&mut self, source_id: String, new_index: u32) -> Result<()> {
+        let mut sources = self.sources.lock_or_recover();
+        let from = Self::position_of(&sources, &source_id)?;
+        let to = new_index as usize;
+        if to >= sources.len() {
+            return Err(error(
+                ErrorCode::InvalidArgument,
+                format!("Index out of bounds: {} >= {}", to, sources.len()),
+            ));
         }
+
+        let item = sources.remove(from);
+        sources.insert(to, item);
+        self.remap_indices(to, |i| {
+            if i == from {
+                Some(to)
+            } else {
+                let without = if i > from { i - 1 } else { i };
+                Some(if without >= to { without + 1 } else { without })
+            }
+        });
+        Ok(())
+    }
+
+    /// Swap the positions of two sources
+    #[napi]
+    pub fn swap(&mut self, first_id: String, second_id: String) -> Result<()> {
+        let mut sources = self.sources.lock_or_recover();
+        let a = Self::position_of(&sources, &first_id)?;
+        let b = Self::position_of(&sources, &second_id)?;
+        sources.swap(a, b);
+        self.remap_indices(a, |i| {
+            Some(if i == a {
+                b
+            } else if i == b {
+                a
+            } else {
+                i
+            })
+        });
+        Ok(())
     }
 
     /// Get a specific source by its ID
     #[napi]
     pub fn get_source(&self, source_id: String) -> Result<AudioQueueItem> {
-        let sources = self.sources.lock().unwrap();
+        let sources = self.sources.lock_or_recover();
         sources
             .iter()
             .find(|s| s.source_id == source_id)
             .cloned()
-            .ok_or_else(|| Error::new(Status::InvalidArg, "Source not found"))
+            .ok_or_else(|| error(ErrorCode::InvalidArgument, "Source not found"))
     }
 
     #[napi]
     pub fn get_sources(&self) -> Vec<AudioQueueItem> {
-        self.sources.lock().unwrap().clone()
+        self.sources.lock_or_recover().clone()
     }
 
     #[napi]
     pub fn get_length(&self) -> u32 {
-        self.sources.lock().unwrap().len() as u32
+        self.sources.lock_or_recover().len() as u32
     }
 
     #[napi]
     pub fn get_current_index(&self) -> u32 {
-        *self.current_index.lock().unwrap() as u32
+        *self.current_index.lock_or_recover() as u32
     }
 
     #[napi]
     pub fn set_current_index(&self, index: u32) -> Result<()> {
-        let len = self.sources.lock().unwrap().len() as u32;
+        let len = self.sources.lock_or_recover().len() as u32;
         if index >= len {
-            return Err(Error::new(
-                Status::InvalidArg,
+            return Err(error(
+                ErrorCode::InvalidArgument,
                 format!("Index out of bounds: {} >= {}", index, len),
             ));
         }
-        *self.current_index.lock().unwrap() = index as usize;
+        let mut current = self.current_index.lock_or_recover();
+        let mut playback = self.playback.lock_or_recover();
+        if *current != index as usize {
+            playback.history.push(*current);
+            playback.shuffle_order.retain(|&i| i != index as usize);
+        }
+        *current = index as usize;
         Ok(())
     }
 
+    /// Set the playback mode. `seed` makes the shuffle order reproducible.
+    #[napi]
+    pub fn set_playback_mode(&self, mode: QueuePlaybackMode, seed: Option<u32>) {
+        let len = self.sources.lock_or_recover().len();
+        let current = *self.current_index.lock_or_recover();
+        let mut playback = self.playback.lock_or_recover();
+        if let Some(seed) = seed {
+            playback.rng = StdRng::seed_from_u64(seed as u64);
+        }
+        playback.mode = mode;
+        if mode == QueuePlaybackMode::Shuffle {
+            playback.reshuffle(len, current);
+        } else {
+            playback.shuffle_order.clear();
+        }
+    }
+
+    #[napi]
+    pub fn get_playback_mode(&self) -> QueuePlaybackMode {
+        self.playback.lock_or_recover().mode
+    }
+
+    /// Get the item at the current index
+    #[napi]
+    pub fn get_current(&self) -> Option<AudioQueueItem> {
+        let sources = self.sources.lock_or_recover();
+        let current = *self.current_index.lock_or_recover();
+        sources.get(current).cloned()
+    }
+
+    /// Advance according to the playback mode and return the new current item.
+    /// Returns null when the end of the queue is reached with repeat off.
+    #[napi]
+    pub fn next(&self) -> Option<AudioQueueItem> {
+        let sources = self.sources.lock_or_recover();
+        let mut current = self.current_index.lock_or_recover();
+        let mut playback = self.playback.lock_or_recover();
+        let len = sources.len();
+        if len == 0 {
+            return None;
+        }
+
+        let next_index = match playback.mode {
+            QueuePlaybackMode::RepeatOne => *current,
+            QueuePlaybackMode::RepeatOff => {
+                if *current + 1 >= len {
+                    return None;
+                }
+                *current + 1
+            }
+            QueuePlaybackMode::RepeatAll => (*current + 1) % len,
+            QueuePlaybackMode::Shuffle => {
+                let mut next = None;
+                while next.is_none() {
+                    if playback.shuffle_order.is_empty() {
+                        playback.reshuffle(len, *current);
+                        if playback.shuffle_order.is_empty() {
+                            // Single item: nothing else to shuffle to
+                            next = Some(*current);
+                            break;
+                        }
+                    }
+                    next = playback.shuffle_order.pop().filter(|&i| i < len);
+                }
+                next.unwrap_or(*current)
+            }
+        };
+
+        if next_index != *current {
+            playback.history.push(*current);
+        }
+        *current = next_index;
+        sources.get(next_index).cloned()
+    }
+
+    /// Step back through the playback history and return the new current item.
+    /// Without history, linear modes fall back to the preceding item.
+    #[napi]
+    pub fn previous(&self) -> Option<AudioQueueItem> {
+        let sources = self.sources.lock_or_recover();
+        let mut current = self.current_index.lock_or_recover();
+        let mut playback = self.playback.lock_or_recover();
+        let len = sources.len();
+        if len == 0 {
+            return None;
+        }
+
+        let previous_index = loop {
+            match playback.history.pop() {
+                Some(i) if i < len => break Some(i),
+                Some(_) => continue,
+                None => break None,
+            }
+        };
+
+        let previous_index = match (previous_index, playback.mode) {
+            (Some(i), _) => {
+                if playback.mode == QueuePlaybackMode::Shuffle {
+                    // Keep the item we stepped back from upcoming
+                    playback.shuffle_order.push(*current);
+                }
+                i
+            }
+            (None, QueuePlaybackMode::RepeatOne) => *current,
+            (None, QueuePlaybackMode::RepeatAll) => (*current + len - 1) % len,
+            (None, QueuePlaybackMode::RepeatOff) if *current > 0 => *current - 1,
+            (None, _) => return None,
+        };
+
+        *current = previous_index;
+        sources.get(previous_index).cloned()
+    }
+
     #[napi]
     pub fn clear(&self) {
-        *self.sources.lock().unwrap() = Vec::new();
-        *self.current_index.lock().unwrap() = 0;
-        *self.is_playing.lock().unwrap() = false;
+        *self.sources.lock_or_recover() = Vec::new();
+        *self.current_index.lock_or_recover() = 0;
+        *self.is_playing.lock_or_recover() = false;
+        let mut playback = self.playback.lock_or_recover();
+        playback.shuffle_order.clear();
+        playback.history.clear();
     }
 
     #[napi]
     pub fn is_playing(&self) -> bool {
-        *self.is_playing.lock().unwrap()
+        *self.is_playing.lock_or_recover()
     }
 
     #[napi]
     pub fn set_playing(&self, playing: bool) {
-        *self.is_playing.lock().unwrap() = playing;
+        *self.is_playing.lock_or_recover() = playing;
     }
 }
 
+/// Callback type for items arriving in a shared queue
+type OnItemCallback = Box<dyn Fn(AudioQueueItem) + Send + Sync>;
+
+/// Queue state shared by a connected input/output pair
+struct SharedSourcesQueue {
+    queue: Mutex<AudioSourceQueue>,
+    item_added: Condvar,
+    closed: AtomicBool,
+    on_item_added: Mutex<Option<OnItemCallback>>,
+}
+
+impl SharedSourcesQueue {
+    fn new() -> Arc<Self> {
+        Arc::new(Self {
+            queue: Mutex::new(AudioSourceQueue::new()),
+            item_added: Condvar::new(),
+            closed: AtomicBool::new(false),
+            on_item_added: Mutex::new(None),
+        })
+    }
+
+    /// Add an item through `add` and wake up anyone waiting on the output side
+    fn push<F>(&self, add: F) -> Result<String>
+    where
+        F: FnOnce(&mut AudioSourceQueue) -> Result<String>,
+    {
+        if self.closed.load(Ordering::SeqCst) {
+            return Err(error(StreamError::Ended, "Queue is closed"));
+        }
+
+        let (id, item) = {
+            let mut queue = self.queue.lock_or_recover();
+            let id = add(&mut queue)?;
+            let item = queue.get_source(id.clone())?;
+            (id, item)
+        };
+        self.item_added.notify_all();
+
+        let callback_guard = self.on_item_added.lock_or_recover();
+        if let Some(cb) = callback_guard.as_ref() {
+            cb(item);
+        }
+        Ok(id)
+    }
+
+    /// Remove and return the next item, so a long-running queue does not keep
+    /// every buffer it has been handed
+    fn try_pop(queue: &AudioSourceQueue) -> Option<AudioQueueItem> {
+        let mut sources = queue.sources.lock_or_recover();
+        let idx = *queue.current_index.lock_or_recover();
+        if idx >= sources.len() {
+            return None;
+        }
+        let item = sources.remove(idx);
+        queue.remap_indices(idx, |i| match i.cmp(&idx) {
+            std::cmp::Ordering::Less => Some(i),
+            std::cmp::Ordering::Equal => None,
+            std::cmp::Ordering::Greater => Some(i - 1),
+        });
+        Some(item)
+    }
+
+    /// Pop the next item, waiting up to `timeout` (forever if None) for one to arrive.
+    /// Returns None on timeout or once the queue is closed and drained.
+    fn pop_wait(&self, timeout: Option<Duration>) -> Option<AudioQueueItem> {
+        let deadline = timeout.map(|t| Instant::now() + t);
+        let mut queue = self.queue.lock_or_recover();
+        loop {
+            if let Some(item) = Self::try_pop(&queue) {
+                return Some(item);
+            }
+            if self.closed.load(Ordering::SeqCst) {
+                return None;
+            }
+            queue = match deadline {
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        return None;
+                    }
+                    self.item_added
+                        .wait_timeout(queue, deadline - now)
+                        .unwrap_or_else(PoisonError::into_inner)
+                        .0
+                }
+                None => self
+                    .item_added
+                    .wait(queue)
+                    .unwrap_or_else(PoisonError::into_inner),
+            };
+        }
+    }
+}
+
+/// Queue input interface - for producing sources into a queue
 #[napi]
 pub struct SourcesQueueInput {
-    queue: Arc<Mutex<AudioSourceQueue>>,
+    shared: Arc<SharedSourcesQueue>,
 }
 
 impl Default for SourcesQueueInput {
@@ -148,42 +663,60 @@ impl Default for SourcesQueueInput {
 
 #[napi]
 impl SourcesQueueInput {
+    /// Create an input with its own private queue (see `sources_queue` for a connected pair)
     #[napi(constructor)]
     pub fn new() -> Self {
         Self {
-            queue: Arc::new(Mutex::new(AudioSourceQueue::new())),
+            shared: SharedSourcesQueue::new(),
         }
     }
 
     #[napi]
     pub fn push_file(&self, file_path: String) -> Result<String> {
-        let mut queue = self.queue.lock().unwrap();
-        queue.add_source(file_path, None)
+        self.shared.push(|queue| queue.add_source(file_path, None))
     }
 
     #[napi]
-    pub fn push_buffer(&self, buffer: Vec<i16>) -> Result<String> {
-        let mut queue = self.queue.lock().unwrap();
-        queue.add_buffer(buffer, None)
+    pub fn push_buffer(&self, buffer: Either<Int16Array, Float32Array>) -> Result<String> {
+        self.shared.push(|queue| queue.add_buffer(buffer, None))
     }
 
     #[napi]
     pub fn set_title(&self, source_id: String, title: String) -> Result<()> {
-        let queue = self.queue.lock().unwrap();
-        let mut sources = queue.sources.lock().unwrap();
+        let queue = self.shared.queue.lock_or_recover();
+        let mut sources = queue.sources.lock_or_recover();
         if let Some(source) = sources.iter_mut().find(|s| s.source_id == source_id) {
             source.title = Some(title);
             Ok(())
         } else {
-            Err(Error::new(Status::InvalidArg, "Source not found"))
+            Err(error(ErrorCode::InvalidArgument, "Source not found"))
         }
     }
+
+    /// Signal that no more items will be pushed; waiting pops return null once drained
+    #[napi]
+    pub fn close(&self) {
+        self.shared.closed.store(true, Ordering::SeqCst);
+        self.shared.item_added.notify_all();
+    }
+
+    #[napi]
+    pub fn is_closed(&self) -> bool {
+        self.shared.closed.load(Ordering::SeqCst)
+    }
+}
+
+// Waiting pops would otherwise never return once the producer is collected
+impl Drop for SourcesQueueInput {
+    fn drop(&mut self) {
+        self.close();
+    }
 }
 
 /// Queue output interface - for consuming sources from a queue
 #[napi]
 pub struct SourcesQueueOutput {
-    queue: Arc<Mutex<AudioSourceQueue>>,
+    shared: Arc<SharedSourcesQueue>,
 }
 
 impl Default for SourcesQueueOutput {
@@ -194,21 +727,22 @@ impl Default for SourcesQueueOutput {
 
 #[napi]
 impl SourcesQueueOutput {
+    /// Create an output with its own private queue (see `sources_queue` for a connected pair)
     #[napi(constructor)]
     pub fn new() -> Self {
         Self {
-            queue: Arc::new(Mutex::new(AudioSourceQueue::new())),
+            shared: SharedSourcesQueue::new(),
         }
     }
 
     #[napi]
     pub fn peek(&self) -> Result<AudioQueueItem> {
-        let queue = self.queue.lock().unwrap();
-        let sources = queue.sources.lock().unwrap();
-        let idx = *queue.current_index.lock().unwrap();
+        let queue = self.shared.queue.lock_or_recover();
+        let sources = queue.sources.lock_or_recover();
+        let idx = *queue.current_index.lock_or_recover();
 
         if idx >= sources.len() {
-            return Err(Error::new(Status::InvalidArg, "Queue is empty"));
+            return Err(error(ErrorCode::InvalidArgument, "Queue is empty"));
         }
 
         Ok(sources[idx].clone())
@@ -216,34 +750,96 @@ impl SourcesQueueOutput {
 
     #[napi]
     pub fn pop(&self) -> Result<AudioQueueItem> {
-        let queue = self.queue.lock().unwrap();
-        let sources = queue.sources.lock().unwrap();
-        let mut idx = *queue.current_index.lock().unwrap();
+        let queue = self.shared.queue.lock_or_recover();
+        SharedSourcesQueue::try_pop(&queue)
+            .ok_or_else(|| error(ErrorCode::InvalidArgument, "Queue is empty"))
+    }
 
-        if idx >= sources.len() {
-            return Err(Error::new(Status::InvalidArg, "Queue is empty"));
-        }
+    /// Pop the next item, blocking the calling thread until one arrives.
+    /// Returns null after `timeout_ms`, or once the input is closed and the queue drained.
+    #[napi]
+    pub fn pop_blocking(&self, timeout_ms: Option<u32>) -> Option<AudioQueueItem> {
+        self.shared
+            .pop_wait(timeout_ms.map(|ms| Duration::from_millis(ms as u64)))
+    }
 
-        let item = sources[idx].clone();
-        idx += 1;
-        *queue.current_index.lock().unwrap() = idx;
-        Ok(item)
+    /// Pop the next item without blocking the event loop.
+    /// Resolves to null after `timeout_ms`, or once the input is closed and the queue drained.
+    #[napi(ts_return_type = "Promise<AudioQueueItem | null>")]
+    pub fn pop_async(&self, timeout_ms: Option<u32>) -> AsyncTask<PopTask> {
+        AsyncTask::new(PopTask {
+            shared: self.shared.clone(),
+            timeout: timeout_ms.map(|ms| Duration::from_millis(ms as u64)),
+        })
+    }
+
+    /// Set callback fired whenever an item is pushed into the connected input
+    #[napi]
+    pub fn set_on_item_added(&self, callback: ThreadsafeFunction<AudioQueueItem>) -> Result<()> {
+        let cb = Box::new(move |item: AudioQueueItem| {
+            callback.call(
+                Ok::<_, napi::Error>(item),
+                ThreadsafeFunctionCallMode::NonBlocking,
+            );
+        });
+
+        *self.shared.on_item_added.lock_or_recover() = Some(cb);
+        Ok(())
     }
 
     #[napi]
     pub fn has_next(&self) -> bool {
-        let queue = self.queue.lock().unwrap();
-        let sources = queue.sources.lock().unwrap();
-        let idx = *queue.current_index.lock().unwrap();
+        let queue = self.shared.queue.lock_or_recover();
+        let sources = queue.sources.lock_or_recover();
+        let idx = *queue.current_index.lock_or_recover();
         idx < sources.len()
     }
 
     #[napi]
     pub fn get_remaining(&self) -> u32 {
-        let queue = self.queue.lock().unwrap();
-        let sources = queue.sources.lock().unwrap();
-        let idx = *queue.current_index.lock().unwrap();
-        (sources.len() - idx) as u32
+        let queue = self.shared.queue.lock_or_recover();
+        let sources = queue.sources.lock_or_recover();
+        let idx = *queue.current_index.lock_or_recover();
+        sources.len().saturating_sub(idx) as u32
+    }
+}
+
+/// Background wait for the next item of a shared queue
+pub struct PopTask {
+    shared: Arc<SharedSourcesQueue>,
+    timeout: Option<Duration>,
+}
+
+impl Task for PopTask {
+    type Output = Option<AudioQueueItem>;
+    type JsValue = Option<AudioQueueItem>;
+
+    fn compute(&mut self) -> napi::Result<Self::Output> {
+        Ok(self.shared.pop_wait(self.timeout))
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+        Ok(output)
+    }
+}
+
+/// A connected input/output pair sharing one queue
+#[napi(object, object_from_js = false)]
+pub struct SourcesQueuePair {
+    pub input: SourcesQueueInput,
+    pub output: SourcesQueueOutput,
+}
+
+/// Creates an input and an output connected to the same queue:
+/// whatever is pushed into the input can be popped from the output
+#[napi]
+pub fn sources_queue() -> SourcesQueuePair {
+    let shared = SharedSourcesQueue::new();
+    SourcesQueuePair {
+        input: SourcesQueueInput {
+            shared: shared.clone(),
+        },
+        output: SourcesQueueOutput { shared },
     }
 }
 
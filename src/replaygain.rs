@@ -0,0 +1,195 @@
+//! ReplayGain / R128 gain tags and loudness normalisation
+
+use crate::error::{decode_failure, open_failure, Result};
+use crate::loudness;
+use crate::types::CancelToken;
+use napi_derive::napi;
+use rodio::Decoder;
+use std::fs::File;
+use std::io::{BufReader, Cursor};
+use symphonia::core::formats::FormatOptions;
+use symphonia::core::io::{MediaSource, MediaSourceStream};
+use symphonia::core::meta::{MetadataOptions, MetadataRevision, StandardTagKey};
+use symphonia::core::probe::Hint;
+
+/// ReplayGain 2.0 reference level; gains bring tracks to this loudness
+const REFERENCE_LUFS: f64 = -18.0;
+/// R128 gain tags are relative to -23 LUFS instead
+const R128_OFFSET_DB: f64 = REFERENCE_LUFS - -23.0;
+
+/// How `AudioPlayer` adjusts each track's level
+#[napi(string_enum)]
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum NormalizationMode {
+    #[default]
+    Off,
+    /// Play every track at the reference loudness
+    Track,
+    /// Keep level differences within an album; tracks without album gain use track gain
+    Album,
+}
+
+#[napi(object)]
+#[derive(Debug, Clone, Default)]
+pub struct NormalizationConfig {
+    pub mode: NormalizationMode,
+    /// Extra gain in dB applied on top of the track or album gain (default 0)
+    pub pre_amp_db: Option<f64>,
+    /// Lower the gain so the tagged or measured peak does not clip (default true)
+    pub prevent_clipping: Option<bool>,
+}
+
+/// Gains in dB relative to the -18 LUFS ReplayGain reference, peaks as linear amplitude
+#[napi(object)]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ReplayGainInfo {
+    pub track_gain: Option<f64>,
+    pub track_peak: Option<f64>,
+    pub album_gain: Option<f64>,
+    pub album_peak: Option<f64>,
+    /// Whether the values were measured because the file had no gain tags
+    pub scanned: bool,
+}
+
+impl ReplayGainInfo {
+    fn is_empty(&self) -> bool {
+        self.track_gain.is_none() && self.album_gain.is_none()
+    }
+
+    /// Linear gain for `config`; 1.0 when normalisation is off
+    pub(crate) fn gain(&self, config: &NormalizationConfig) -> f32 {
+        let (gain, peak) = match config.mode {
+            NormalizationMode::Off => return 1.0,
+            NormalizationMode::Track => (self.track_gain, self.track_peak),
+            NormalizationMode::Album => (
+                self.album_gain.or(self.track_gain),
+                self.album_peak.or(self.track_peak),
+            ),
+        };
+        let db = gain.unwrap_or(0.0) + config.pre_amp_db.unwrap_or(0.0);
+        let mut linear = 10f64.powf(db / 20.0);
+        if config.prevent_clipping.unwrap_or(true) {
+            if let Some(peak) = peak.filter(|&p| p > 0.0) {
+                linear = linear.min(1.0 / peak);
+            }
+        }
+        linear as f32
+    }
+}
+
+/// Read gain tags from a file, or `None` when it has none
+pub(crate) fn read_file_tags(file_path: &str) -> Option<ReplayGainInfo> {
+    let file = File::open(file_path).ok()?;
+    let mut hint = Hint::new();
+    if let Some(ext) = std::path::Path::new(file_path).extension() {
+        hint.with_extension(&ext.to_string_lossy());
+    }
+    read_tags(Box::new(file), &hint)
+}
+
+pub(crate) fn read_buffer_tags(data: &[u8]) -> Option<ReplayGainInfo> {
+    read_tags(Box::new(Cursor::new(data.to_vec())), &Hint::new())
+}
+
+fn read_tags(source: Box<dyn MediaSource>, hint: &Hint) -> Option<ReplayGainInfo> {
+    let mss = MediaSourceStream::new(source, Default::default());
+    let mut probed = symphonia::default::get_probe()
+        .format(
+            hint,
+            mss,
+            &FormatOptions::default(),
+            &MetadataOptions::default(),
+        )
+        .ok()?;
+
+    // Tags may sit in front of the container (ID3v2) or inside it
+    let mut info = ReplayGainInfo::default();
+    if let Some(revision) = probed.metadata.get().as_ref().and_then(|m| m.current()) {
+        apply_tags(&mut info, revision);
+    }
+    if let Some(revision) = probed.format.metadata().current() {
+        apply_tags(&mut info, revision);
+    }
+    (!info.is_empty()).then_some(info)
+}
+
+pub(crate) fn apply_tags(info: &mut ReplayGainInfo, revision: &MetadataRevision) {
+    for tag in revision.tags() {
+        let value = tag.value.to_string();
+        let key = tag.key.to_ascii_lowercase();
+        // Freeform MP4 atoms keep their "----:com.apple.iTunes:" prefix
+        let name = key.rsplit(':').next().unwrap_or(&key);
+        match (tag.std_key, name) {
+            (Some(StandardTagKey::ReplayGainTrackGain), _) | (_, "replaygain_track_gain") => {
+                info.track_gain = parse_number(&value);
+            }
+            (Some(StandardTagKey::ReplayGainTrackPeak), _) | (_, "replaygain_track_peak") => {
+                info.track_peak = parse_number(&value);
+            }
+            (Some(StandardTagKey::ReplayGainAlbumGain), _) | (_, "replaygain_album_gain") => {
+                info.album_gain = parse_number(&value);
+            }
+            (Some(StandardTagKey::ReplayGainAlbumPeak), _) | (_, "replaygain_album_peak") => {
+                info.album_peak = parse_number(&value);
+            }
+            // Q7.8 fixed point dB, as written by opusenc and loudgain
+            (_, "r128_track_gain") => {
+                info.track_gain = parse_r128(&value).or(info.track_gain);
+            }
+            (_, "r128_album_gain") => {
+                info.album_gain = parse_r128(&value).or(info.album_gain);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Parse "-6.52 dB", "+3.2 dB" or "0.988"
+fn parse_number(value: &str) -> Option<f64> {
+    let number = value.split_whitespace().next()?;
+    let number = number.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+    number.parse::<f64>().ok().filter(|v| v.is_finite())
+}
+
+fn parse_r128(value: &str) -> Option<f64> {
+    let q78 = value.trim().parse::<i32>().ok()?;
+    Some(q78 as f64 / 256.0 + R128_OFFSET_DB)
+}
+
+/// Measure track gain and true peak of a file or buffer without gain tags
+pub(crate) fn scan(
+    file_path: Option<&str>,
+    data: Option<&[u8]>,
+    cancel: &CancelToken,
+) -> Result<ReplayGainInfo> {
+    let stats = match (file_path, data) {
+        (_, Some(data)) => {
+            let decoder = Decoder::new(Cursor::new(data.to_vec()))
+                .map_err(|e| decode_failure("Failed to decode buffer", e))?;
+            measure(decoder, cancel)?
+        }
+        (Some(file_path), None) => {
+            let file = File::open(file_path).map_err(|e| open_failure(file_path, e))?;
+            let decoder = Decoder::new(BufReader::new(file))
+                .map_err(|e| decode_failure("Failed to create decoder", e))?;
+            measure(decoder, cancel)?
+        }
+        (None, None) => return Ok(ReplayGainInfo::default()),
+    };
+
+    Ok(ReplayGainInfo {
+        track_gain: stats
+            .integrated
+            .is_finite()
+            .then_some(REFERENCE_LUFS - stats.integrated),
+        track_peak: Some(10f64.powf(stats.true_peak / 20.0)).filter(|p| *p > 0.0),
+        album_gain: None,
+        album_peak: None,
+        scanned: true,
+    })
+}
+
+fn measure<S: rodio::Source>(source: S, cancel: &CancelToken) -> Result<loudness::LoudnessStats> {
+    let (rate, channels) = (source.sample_rate(), source.channels());
+    loudness::measure(source, rate, channels, cancel)
+}
@@ -1,5 +1,7 @@
 //! Audio signal sources for audio synthesis and playback
 
+use crate::error::LockExt;
+use napi::bindgen_prelude::Int16Array;
 use napi_derive::napi;
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
@@ -52,7 +54,17 @@ impl AudioSource {
 
     /// Generate samples for the given duration
     #[napi]
-    pub fn generate(&self, duration_ms: u32) -> Vec<i16> {
+    pub fn generate(&self, duration_ms: u32) -> Int16Array {
+        Int16Array::new(self.render(duration_ms))
+    }
+
+    #[napi]
+    pub fn get_samples(&self) -> Int16Array {
+        Int16Array::new(self.samples.lock_or_recover().clone())
+    }
+
+    /// Samples for the given duration, for use on the Rust side
+    fn render(&self, duration_ms: u32) -> Vec<i16> {
         let total_samples = ((duration_ms as f64 * self.sample_rate as f64 / 1000.0) as usize)
             * self.channels as usize;
         let mut samples = Vec::with_capacity(total_samples);
@@ -95,19 +107,14 @@ impl AudioSource {
         samples
     }
 
-    #[napi]
-    pub fn get_samples(&self) -> Vec<i16> {
-        self.samples.lock().unwrap().clone()
-    }
-
     #[napi]
     pub fn get_next(&self) -> Option<i16> {
-        let mut pos = self.position.lock().unwrap();
-        let samples = self.samples.lock().unwrap();
+        let mut pos = self.position.lock_or_recover();
+        let samples = self.samples.lock_or_recover();
 
         if *pos >= samples.len() {
             // Generate more samples if needed
-            let generated = self.generate(100); // 100ms at a time
+            let generated = self.render(100); // 100ms at a time
             if !generated.is_empty() {
                 for sample in generated {
                     samples.push(sample);
@@ -123,7 +130,7 @@ impl AudioSource {
 
     #[napi]
     pub fn reset(&self) {
-        *self.position.lock().unwrap() = 0;
+        *self.position.lock_or_recover() = 0;
     }
 
     /// Get sample rate of source
@@ -166,12 +173,14 @@ impl Amplify {
     }
 
     #[napi]
-    pub fn get_samples(&self) -> Vec<i16> {
+    pub fn get_samples(&self) -> Int16Array {
         let samples = self.source.get_samples();
-        samples
-            .into_iter()
-            .map(|s| ((s as f64 * self.gain).clamp(i16::MIN as f64, i16::MAX as f64)) as i16)
-            .collect()
+        Int16Array::new(
+            samples
+                .iter()
+                .map(|&s| ((s as f64 * self.gain).clamp(i16::MIN as f64, i16::MAX as f64)) as i16)
+                .collect(),
+        )
     }
 
     #[napi]
@@ -204,17 +213,17 @@ impl Pausable {
 
     #[napi]
     pub fn pause(&self) {
-        *self.paused.lock().unwrap() = true;
+        *self.paused.lock_or_recover() = true;
     }
 
     #[napi]
     pub fn resume(&self) {
-        *self.paused.lock().unwrap() = false;
+        *self.paused.lock_or_recover() = false;
     }
 
     #[napi]
     pub fn is_paused(&self) -> bool {
-        *self.paused.lock().unwrap()
+        *self.paused.lock_or_recover()
     }
 
     #[napi]
@@ -245,19 +254,19 @@ impl Stoppable {
 
     #[napi]
     pub fn stop(&self) {
-        *self.stopped.lock().unwrap() = true;
+        *self.stopped.lock_or_recover() = true;
         self.source.reset();
     }
 
     #[napi]
     pub fn start(&self) {
-        *self.stopped.lock().unwrap() = false;
+        *self.stopped.lock_or_recover() = false;
         self.source.reset();
     }
 
     #[napi]
     pub fn is_stopped(&self) -> bool {
-        *self.stopped.lock().unwrap()
+        *self.stopped.lock_or_recover()
     }
 
     #[napi]
@@ -305,7 +314,7 @@ impl Chirp {
     }
 
     #[napi]
-    pub fn get_samples(&self) -> Vec<i16> {
+    pub fn get_samples(&self) -> Int16Array {
         let total_samples = ((self.duration as f64 * self.source.get_sample_rate() as f64 / 1000.0)
             as usize)
             * self.source.get_channels() as usize;
@@ -324,7 +333,7 @@ impl Chirp {
             samples.push(sample as i16);
         }
 
-        samples
+        Int16Array::new(samples)
     }
 }
 
@@ -350,7 +359,7 @@ impl LinearGainRamp {
     }
 
     #[napi]
-    pub fn get_samples(&self) -> Vec<i16> {
+    pub fn get_samples(&self) -> Int16Array {
         let source_samples = self.source.get_samples();
         let mut output = Vec::with_capacity(source_samples.len());
 
@@ -362,7 +371,7 @@ impl LinearGainRamp {
             output.push(scaled);
         }
 
-        output
+        Int16Array::new(output)
     }
 }
 
@@ -388,7 +397,7 @@ impl SineWave {
     }
 
     #[napi]
-    pub fn get_samples(&self, duration_ms: u32) -> Vec<i16> {
+    pub fn get_samples(&self, duration_ms: u32) -> Int16Array {
         self.source.generate(duration_ms)
     }
 }
@@ -415,7 +424,7 @@ impl SquareWave {
     }
 
     #[napi]
-    pub fn get_samples(&self, duration_ms: u32) -> Vec<i16> {
+    pub fn get_samples(&self, duration_ms: u32) -> Int16Array {
         self.source.generate(duration_ms)
     }
 }
@@ -442,7 +451,7 @@ impl SawtoothWave {
     }
 
     #[napi]
-    pub fn get_samples(&self, duration_ms: u32) -> Vec<i16> {
+    pub fn get_samples(&self, duration_ms: u32) -> Int16Array {
         self.source.generate(duration_ms)
     }
 }
@@ -469,7 +478,7 @@ impl TriangleWave {
     }
 
     #[napi]
-    pub fn get_samples(&self, duration_ms: u32) -> Vec<i16> {
+    pub fn get_samples(&self, duration_ms: u32) -> Int16Array {
         self.source.generate(duration_ms)
     }
 }
@@ -492,10 +501,10 @@ impl Empty {
     }
 
     #[napi]
-    pub fn get_samples(&self, duration_ms: u32) -> Vec<i16> {
+    pub fn get_samples(&self, duration_ms: u32) -> Int16Array {
         let total_samples = ((duration_ms as f64 * self.sample_rate as f64 / 1000.0) as usize)
             * self.channels as usize;
-        vec![0; total_samples]
+        Int16Array::new(vec![0; total_samples])
     }
 }
 
@@ -532,7 +541,7 @@ impl SkipDuration {
     }
 
     #[napi]
-    pub fn get_samples(&self, duration_ms: u32) -> Vec<i16> {
+    pub fn get_samples(&self, duration_ms: u32) -> Int16Array {
         let total_samples = ((duration_ms as f64 * self.source.get_sample_rate() as f64 / 1000.0)
             as usize)
             * self.source.get_channels() as usize;
@@ -542,11 +551,11 @@ impl SkipDuration {
 
         // Simple skip by returning empty for now
         // In a real implementation, this would read from the source and skip
-        let all_samples = self.source.generate(duration_ms + self.skip_ms);
+        let all_samples = self.source.render(duration_ms + self.skip_ms);
         if skip_samples < all_samples.len() {
-            all_samples[skip_samples..].to_vec()
+            Int16Array::new(all_samples[skip_samples..].to_vec())
         } else {
-            Vec::new()
+            Int16Array::new(Vec::new())
         }
     }
 }
@@ -570,13 +579,13 @@ impl Skippable {
 
     #[napi]
     pub fn skip(&self, samples: u32) {
-        let mut pos = self.position.lock().unwrap();
+        let mut pos = self.position.lock_or_recover();
         *pos = (*pos + samples as usize).min(self.source.get_samples().len());
     }
 
     #[napi]
     pub fn get_next(&self) -> Option<i16> {
-        let mut pos = self.position.lock().unwrap();
+        let mut pos = self.position.lock_or_recover();
         let source_samples = self.source.get_samples();
 
         if *pos >= source_samples.len() {
@@ -604,17 +613,17 @@ impl Repeat {
     }
 
     #[napi]
-    pub fn get_samples(&self, duration_ms: u32) -> Vec<i16> {
-        let samples = self.source.generate(duration_ms);
+    pub fn get_samples(&self, duration_ms: u32) -> Int16Array {
+        let samples = self.source.render(duration_ms);
         if self.times <= 1 {
-            return samples;
+            return Int16Array::new(samples);
         }
 
         let mut result = Vec::with_capacity(samples.len() * self.times as usize);
         for _ in 0..self.times {
             result.extend_from_slice(&samples);
         }
-        result
+        Int16Array::new(result)
     }
 }
 
@@ -640,7 +649,7 @@ impl SignalGenerator {
     }
 
     #[napi]
-    pub fn generate(&self, duration_ms: u32) -> Vec<i16> {
+    pub fn generate(&self, duration_ms: u32) -> Int16Array {
         let channels = 1; // Mono for signal generators
         let audio_source = AudioSource::new(
             self.source_type.clone(),
@@ -673,12 +682,11 @@ impl UniformSourceIterator {
     }
 
     #[napi]
-    pub fn next(&self) -> Option<Vec<i16>> {
+    pub fn next(&self) -> Option<Int16Array> {
         if self.count == 0 {
             return None;
         }
-        let samples = self.source.generate(self.buffer_size);
-        Some(samples)
+        Some(self.source.generate(self.buffer_size))
     }
 }
 
@@ -700,10 +708,10 @@ impl Zero {
     }
 
     #[napi]
-    pub fn generate(&self, duration_ms: u32) -> Vec<i16> {
+    pub fn generate(&self, duration_ms: u32) -> Int16Array {
         let total_samples = ((duration_ms as f64 * self.sample_rate as f64 / 1000.0) as usize)
             * self.channels as usize;
-        vec![0; total_samples]
+        Int16Array::new(vec![0; total_samples])
     }
 }
 
@@ -719,15 +727,15 @@ pub type Crossfade = f64;
 
 /// Create a sine wave source
 #[napi]
-pub fn from_iter(filename: &str) -> Result<Vec<i16>, napi::Error> {
+pub fn from_iter(filename: &str) -> Result<Int16Array, napi::Error> {
     // Placeholder for where to read samples from
-    Ok(vec![0; 44100])
+    Ok(Int16Array::new(vec![0; 44100]))
 }
 
 /// Create a RIFF WAV decoder from a factory
 #[napi]
-pub fn from_factory() -> Vec<i16> {
-    vec![0; 44100]
+pub fn from_factory() -> Int16Array {
+    Int16Array::new(vec![0; 44100])
 }
 
 /// Create a chirp source
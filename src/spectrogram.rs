@@ -0,0 +1,618 @@
+//! Offline spectrograms - STFT magnitude matrices, optionally mel-scaled or rendered to PNG
+
+use crate::analyser::{window_coefficients, Fft, MagnitudeScale, WindowFunction};
+use crate::decoder::CANCEL_CHECK_INTERVAL;
+use crate::error::{error, ErrorCode, Result};
+use crate::png;
+use crate::types::CancelToken;
+use napi::bindgen_prelude::{Buffer, Float32Array};
+use napi_derive::napi;
+use std::collections::VecDeque;
+
+const DEFAULT_FFT_SIZE: u32 = 2048;
+const MIN_FFT_SIZE: u32 = 32;
+const MAX_FFT_SIZE: u32 = 32768;
+/// Magnitude matrix size allowed by default
+const DEFAULT_MEMORY_BUDGET_MB: f64 = 64.0;
+/// Default span between the quietest and loudest colour of a rendered image
+const DEFAULT_DYNAMIC_RANGE_DB: f64 = 80.0;
+const MAX_IMAGE_SIZE: u32 = 16384;
+/// Space left for axis labels around the plot: left, right, top, bottom
+const AXIS_MARGINS: (usize, usize, usize, usize) = (26, 4, 4, 10);
+const AXIS_COLOR: [u8; 3] = [200, 200, 200];
+
+#[napi(string_enum)]
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum ColorMap {
+    #[default]
+    Viridis,
+    Magma,
+    Grayscale,
+}
+
+#[napi(object)]
+#[derive(Debug, Clone, Default)]
+pub struct SpectrogramOptions {
+    /// Samples per transform, a power of two from 32 to 32768 (default 2048)
+    pub fft_size: Option<u32>,
+    /// Samples between transforms, at most `fftSize` (default a quarter of it)
+    pub hop_size: Option<u32>,
+    /// Window applied before each transform (default Hann)
+    pub window: Option<WindowFunction>,
+    /// Group the bins into this many mel bands instead of returning every bin
+    pub mel_bands: Option<u32>,
+    /// Scale of `getData` and `getColumn` (default Decibels)
+    pub scale: Option<MagnitudeScale>,
+    /// Largest size of the magnitude matrix in MB (default 64). Longer inputs
+    /// fold several transforms into each column, keeping the loudest value.
+    pub memory_budget_mb: Option<f64>,
+}
+
+#[napi(object)]
+#[derive(Debug, Clone, Default)]
+pub struct SpectrogramImageOptions {
+    pub color_map: Option<ColorMap>,
+    /// Level drawn with the brightest colour (default 0 dBFS)
+    pub max_db: Option<f64>,
+    /// Level drawn with the darkest colour (default 80 dB below `maxDb`)
+    pub min_db: Option<f64>,
+    /// Draw frequency and time axes with labels (default true)
+    pub axes: Option<bool>,
+    /// Plot size in pixels, excluding axes; defaults to one pixel per column and row
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+}
+
+/// STFT magnitudes over time; rows run from the lowest frequency up
+#[napi]
+pub struct Spectrogram {
+    sample_rate: u32,
+    hop_size: u32,
+    frames_per_column: u32,
+    scale: MagnitudeScale,
+    // Centre frequency of each row
+    frequencies: Vec<f32>,
+    // Linear magnitudes, column by column: `column * rows + row`
+    magnitudes: Vec<f32>,
+}
+
+#[napi]
+impl Spectrogram {
+    /// Number of columns (time steps)
+    #[napi]
+    pub fn get_columns(&self) -> u32 {
+        (self.magnitudes.len() / self.frequencies.len().max(1)) as u32
+    }
+
+    /// Number of rows (frequency bins or mel bands)
+    #[napi]
+    pub fn get_rows(&self) -> u32 {
+        self.frequencies.len() as u32
+    }
+
+    #[napi]
+    pub fn get_sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Time covered by each column
+    #[napi]
+    pub fn get_seconds_per_column(&self) -> f64 {
+        self.frames_per_column as f64 * self.hop_size as f64 / self.sample_rate.max(1) as f64
+    }
+
+    /// Centre frequency of each row in Hz
+    #[napi]
+    pub fn get_frequencies(&self) -> Float32Array {
+        Float32Array::new(self.frequencies.clone())
+    }
+
+    /// Every magnitude, column by column (`column * rows + row`)
+    #[napi]
+    pub fn get_data(&self) -> Float32Array {
+        Float32Array::new(self.magnitudes.iter().map(|&m| self.scaled(m)).collect())
+    }
+
+    #[napi]
+    pub fn get_column(&self, column: u32) -> Result<Float32Array> {
+        let rows = self.frequencies.len();
+        let start = column as usize * rows;
+        let values = self.magnitudes.get(start..start + rows).ok_or_else(|| {
+            error(
+                ErrorCode::InvalidArgument,
+                format!("Column {} out of range (0-{})", column, self.get_columns()),
+            )
+        })?;
+        Ok(Float32Array::new(
+            values.iter().map(|&m| self.scaled(m)).collect(),
+        ))
+    }
+
+    /// Render as a PNG image, low frequencies at the bottom
+    #[napi]
+    pub fn to_png(&self, options: Option<SpectrogramImageOptions>) -> Result<Buffer> {
+        self.render(&options.unwrap_or_default()).map(Buffer::from)
+    }
+}
+
+impl Spectrogram {
+    fn scaled(&self, magnitude: f32) -> f32 {
+        match self.scale {
+            MagnitudeScale::Decibels => 20.0 * magnitude.log10(),
+            MagnitudeScale::Linear => magnitude,
+        }
+    }
+
+    fn render(&self, options: &SpectrogramImageOptions) -> Result<Vec<u8>> {
+        let rows = self.frequencies.len();
+        let columns = self.get_columns() as usize;
+        let plot_w = options.width.unwrap_or(columns as u32).max(1);
+        let plot_h = options.height.unwrap_or(rows as u32).max(1);
+        let max_db = options.max_db.unwrap_or(0.0);
+        let min_db = options.min_db.unwrap_or(max_db - DEFAULT_DYNAMIC_RANGE_DB);
+        if plot_w > MAX_IMAGE_SIZE || plot_h > MAX_IMAGE_SIZE {
+            return Err(error(
+                ErrorCode::InvalidArgument,
+                format!("Image size must be at most {} pixels", MAX_IMAGE_SIZE),
+            ));
+        }
+        if !(min_db.is_finite() && max_db.is_finite() && min_db < max_db) {
+            return Err(error(
+                ErrorCode::InvalidArgument,
+                "minDb must be below maxDb",
+            ));
+        }
+
+        let (plot_w, plot_h) = (plot_w as usize, plot_h as usize);
+        let (left, right, top, bottom) = if options.axes.unwrap_or(true) {
+            AXIS_MARGINS
+        } else {
+            (0, 0, 0, 0)
+        };
+        let mut image = Image::new(left + plot_w + right, top + plot_h + bottom);
+        let color_map = options.color_map.unwrap_or_default();
+
+        if rows > 0 && columns > 0 {
+            for y in 0..plot_h {
+                let row = (plot_h - 1 - y) * rows / plot_h;
+                for x in 0..plot_w {
+                    let column = x * columns / plot_w;
+                    let db = 20.0 * (self.magnitudes[column * rows + row] as f64).log10();
+                    let level = ((db - min_db) / (max_db - min_db)).clamp(0.0, 1.0);
+                    image.set(left + x, top + y, color(color_map, level));
+                }
+            }
+        }
+
+        if left > 0 {
+            self.draw_axes(&mut image, (left, top, plot_w, plot_h));
+        }
+        Ok(png::encode_rgb(
+            image.width as u32,
+            image.height as u32,
+            &image.rgb,
+        ))
+    }
+
+    fn draw_axes(
+        &self,
+        image: &mut Image,
+        (left, top, plot_w, plot_h): (usize, usize, usize, usize),
+    ) {
+        let bottom = top + plot_h;
+        for y in top..=bottom {
+            image.set(left - 1, y, AXIS_COLOR);
+        }
+        for x in left - 1..left + plot_w {
+            image.set(x, bottom, AXIS_COLOR);
+        }
+
+        // Frequency ticks at round values, skipping those too close to the last one
+        let mut last_y = usize::MAX;
+        for hz in [50.0, 100.0, 200.0, 500.0, 1e3, 2e3, 5e3, 1e4, 2e4] {
+            let Some(row) = self.row_at(hz) else {
+                continue;
+            };
+            let y =
+                top + plot_h - 1 - (row * plot_h as f64 / self.frequencies.len() as f64) as usize;
+            if last_y.abs_diff(y) < 10 {
+                continue;
+            }
+            last_y = y;
+            image.set(left - 2, y, AXIS_COLOR);
+            let label = if hz >= 1e3 {
+                format!("{}k", hz / 1e3)
+            } else {
+                format!("{}", hz)
+            };
+            let x = (left - 3).saturating_sub(text_width(&label));
+            image.text(x, y.saturating_sub(2), &label);
+        }
+
+        // Time ticks at the finest round step that keeps labels apart
+        let duration = self.get_columns() as f64 * self.get_seconds_per_column();
+        if duration <= 0.0 {
+            return;
+        }
+        let seconds_per_px = duration / plot_w as f64;
+        let step = [
+            0.1, 0.2, 0.5, 1.0, 2.0, 5.0, 10.0, 15.0, 30.0, 60.0, 120.0, 300.0, 600.0,
+        ]
+        .into_iter()
+        .find(|step| step / seconds_per_px >= 32.0)
+        .unwrap_or(1800.0);
+        let mut t: f64 = 0.0;
+        while t <= duration {
+            let x = left + (t / seconds_per_px) as usize;
+            if x < left + plot_w {
+                image.set(x, bottom + 1, AXIS_COLOR);
+                let label = format!("{}s", (t * 10.0).round() / 10.0);
+                image.text(x.saturating_sub(text_width(&label) / 2), bottom + 3, &label);
+            }
+            t += step;
+        }
+    }
+
+    /// Fractional row whose centre frequency is `hz`, if within range
+    fn row_at(&self, hz: f64) -> Option<f64> {
+        let index = self.frequencies.iter().position(|&f| f as f64 >= hz)?;
+        if index == 0 {
+            return None;
+        }
+        let (lo, hi) = (
+            self.frequencies[index - 1] as f64,
+            self.frequencies[index] as f64,
+        );
+        Some(index as f64 - 1.0 + (hz - lo) / (hi - lo).max(f64::EPSILON))
+    }
+}
+
+/// Transform sizes and the column pooling that keeps the matrix within budget
+pub(crate) struct StftPlan {
+    fft_size: usize,
+    hop_size: usize,
+    frames_per_column: usize,
+    window: Vec<f64>,
+    normalization: f64,
+    mel: Option<Vec<MelBand>>,
+    scale: MagnitudeScale,
+}
+
+/// Resolve `options` for a `sample_rate` input; `frames` counts the input's frames
+pub(crate) fn plan(
+    options: &SpectrogramOptions,
+    sample_rate: u32,
+    frames: impl FnOnce() -> Result<u64>,
+) -> Result<StftPlan> {
+    let fft_size = options.fft_size.unwrap_or(DEFAULT_FFT_SIZE);
+    if !fft_size.is_power_of_two() || !(MIN_FFT_SIZE..=MAX_FFT_SIZE).contains(&fft_size) {
+        return Err(error(
+            ErrorCode::InvalidArgument,
+            format!(
+                "FFT size must be a power of two between {} and {}",
+                MIN_FFT_SIZE, MAX_FFT_SIZE
+            ),
+        ));
+    }
+    let hop_size = options.hop_size.unwrap_or(fft_size / 4);
+    if hop_size == 0 || hop_size > fft_size {
+        return Err(error(
+            ErrorCode::InvalidArgument,
+            "Hop size must be between 1 and the FFT size",
+        ));
+    }
+    if options.mel_bands == Some(0) {
+        return Err(error(
+            ErrorCode::InvalidArgument,
+            "melBands must be at least 1",
+        ));
+    }
+    let budget = options.memory_budget_mb.unwrap_or(DEFAULT_MEMORY_BUDGET_MB);
+    if !budget.is_finite() || budget <= 0.0 {
+        return Err(error(
+            ErrorCode::InvalidArgument,
+            "memoryBudgetMb must be a positive number",
+        ));
+    }
+
+    let (fft_size, hop_size) = (fft_size as usize, hop_size as usize);
+    let mel = options
+        .mel_bands
+        .map(|bands| mel_filters(bands as usize, fft_size, sample_rate));
+    let rows = mel.as_ref().map_or(fft_size / 2, Vec::len);
+    let max_columns = ((budget * 1024.0 * 1024.0) / (rows * 4) as f64).max(1.0) as u64;
+    let transforms = frames()?.div_ceil(hop_size as u64).max(1);
+    let window = window_coefficients(options.window.unwrap_or_default(), fft_size);
+
+    Ok(StftPlan {
+        fft_size,
+        hop_size,
+        frames_per_column: transforms.div_ceil(max_columns) as usize,
+        normalization: 2.0 / window.iter().sum::<f64>(),
+        window,
+        mel,
+        scale: options.scale.unwrap_or_default(),
+    })
+}
+
+/// Stream interleaved `samples` through the STFT, holding one transform of input
+pub(crate) fn compute<I: Iterator<Item = f32>>(
+    samples: I,
+    channels: u16,
+    sample_rate: u32,
+    plan: StftPlan,
+    cancel: &CancelToken,
+) -> Result<Spectrogram> {
+    let n = channels.max(1) as usize;
+    let mut stft = Stft::new(plan);
+    let mut pending = VecDeque::with_capacity(stft.plan.fft_size);
+    let (mut frame_sum, mut channel) = (0.0, 0);
+
+    for (i, sample) in samples.enumerate() {
+        if i % CANCEL_CHECK_INTERVAL == 0 {
+            cancel.check()?;
+        }
+        frame_sum += sample;
+        channel += 1;
+        if channel == n {
+            pending.push_back(frame_sum / n as f32);
+            (frame_sum, channel) = (0.0, 0);
+            if pending.len() == stft.plan.fft_size {
+                stft.transform(&pending);
+                pending.drain(..stft.plan.hop_size);
+            }
+        }
+    }
+    // Zero-pad the tail when it holds samples no transform covered yet
+    let covered = if stft.transforms > 0 {
+        stft.plan.fft_size - stft.plan.hop_size
+    } else {
+        0
+    };
+    if pending.len() > covered {
+        pending.resize(stft.plan.fft_size, 0.0);
+        stft.transform(&pending);
+    }
+    cancel.check()?;
+    Ok(stft.finish(sample_rate))
+}
+
+struct Stft {
+    plan: StftPlan,
+    fft: Fft,
+    re: Vec<f64>,
+    im: Vec<f64>,
+    // Loudest magnitudes of the transforms folded into the current column
+    column: Vec<f32>,
+    pooled: usize,
+    transforms: usize,
+    magnitudes: Vec<f32>,
+}
+
+impl Stft {
+    fn new(plan: StftPlan) -> Self {
+        let rows = plan.mel.as_ref().map_or(plan.fft_size / 2, Vec::len);
+        Self {
+            fft: Fft::new(plan.fft_size),
+            re: vec![0.0; plan.fft_size],
+            im: vec![0.0; plan.fft_size],
+            column: vec![0.0; rows],
+            pooled: 0,
+            transforms: 0,
+            magnitudes: Vec::new(),
+            plan,
+        }
+    }
+
+    fn transform(&mut self, input: &VecDeque<f32>) {
+        for ((re, &s), &w) in self.re.iter_mut().zip(input).zip(&self.plan.window) {
+            *re = s as f64 * w;
+        }
+        self.im.fill(0.0);
+        self.fft.process(&mut self.re, &mut self.im);
+
+        let norm = self.plan.normalization;
+        let magnitude = |k: usize| self.re[k].hypot(self.im[k]) * norm;
+        match &self.plan.mel {
+            None => {
+                for (k, value) in self.column.iter_mut().enumerate() {
+                    *value = value.max(magnitude(k) as f32);
+                }
+            }
+            Some(bands) => {
+                for (band, value) in bands.iter().zip(self.column.iter_mut()) {
+                    let power: f64 = band
+                        .weights
+                        .iter()
+                        .enumerate()
+                        .map(|(i, w)| w * magnitude(band.start + i).powi(2))
+                        .sum();
+                    *value = value.max(power.sqrt() as f32);
+                }
+            }
+        }
+
+        self.transforms += 1;
+        self.pooled += 1;
+        if self.pooled == self.plan.frames_per_column {
+            self.flush();
+        }
+    }
+
+    fn flush(&mut self) {
+        self.magnitudes.extend_from_slice(&self.column);
+        self.column.fill(0.0);
+        self.pooled = 0;
+    }
+
+    fn finish(mut self, sample_rate: u32) -> Spectrogram {
+        if self.pooled > 0 {
+            self.flush();
+        }
+        let bin_hz = sample_rate as f64 / self.plan.fft_size as f64;
+        let frequencies = match &self.plan.mel {
+            Some(bands) => bands.iter().map(|b| b.center_hz as f32).collect(),
+            None => (0..self.column.len())
+                .map(|k| (k as f64 * bin_hz) as f32)
+                .collect(),
+        };
+        Spectrogram {
+            sample_rate,
+            hop_size: self.plan.hop_size as u32,
+            frames_per_column: self.plan.frames_per_column as u32,
+            scale: self.plan.scale,
+            frequencies,
+            magnitudes: self.magnitudes,
+        }
+    }
+}
+
+/// Triangular mel filter over FFT bins `start..start + weights.len()`
+struct MelBand {
+    start: usize,
+    weights: Vec<f64>,
+    center_hz: f64,
+}
+
+fn hz_to_mel(hz: f64) -> f64 {
+    2595.0 * (1.0 + hz / 700.0).log10()
+}
+
+fn mel_to_hz(mel: f64) -> f64 {
+    700.0 * (10f64.powf(mel / 2595.0) - 1.0)
+}
+
+/// HTK-style mel filters with unit peaks, spread from 0 Hz to Nyquist
+fn mel_filters(bands: usize, fft_size: usize, sample_rate: u32) -> Vec<MelBand> {
+    let bins = fft_size / 2;
+    let bin_hz = sample_rate as f64 / fft_size as f64;
+    let top = hz_to_mel(sample_rate as f64 / 2.0);
+    let edge = |i: usize| mel_to_hz(top * i as f64 / (bands + 1) as f64);
+
+    (0..bands)
+        .map(|band| {
+            let (lo, center, hi) = (edge(band), edge(band + 1), edge(band + 2));
+            let start = ((lo / bin_hz).floor() as usize).min(bins.saturating_sub(1));
+            let end = ((hi / bin_hz).ceil() as usize).clamp(start + 1, bins.max(1));
+            let mut weights: Vec<f64> = (start..end)
+                .map(|k| {
+                    let hz = k as f64 * bin_hz;
+                    if hz <= lo || hz >= hi {
+                        0.0
+                    } else if hz <= center {
+                        (hz - lo) / (center - lo)
+                    } else {
+                        (hi - hz) / (hi - center)
+                    }
+                })
+                .collect();
+            // Bands narrower than a bin take the bin nearest their centre
+            if weights.iter().all(|&w| w == 0.0) {
+                let nearest = ((center / bin_hz).round() as usize).clamp(start, end - 1);
+                weights[nearest - start] = 1.0;
+            }
+            MelBand {
+                start,
+                weights,
+                center_hz: center,
+            }
+        })
+        .collect()
+}
+
+/// Interpolate `level` in [0, 1] along a colour map
+fn color(map: ColorMap, level: f64) -> [u8; 3] {
+    const VIRIDIS: [[u8; 3]; 9] = [
+        [68, 1, 84],
+        [71, 44, 122],
+        [59, 81, 139],
+        [44, 113, 142],
+        [33, 144, 141],
+        [39, 173, 129],
+        [92, 200, 99],
+        [170, 220, 50],
+        [253, 231, 37],
+    ];
+    const MAGMA: [[u8; 3]; 9] = [
+        [0, 0, 4],
+        [28, 16, 68],
+        [79, 18, 123],
+        [129, 37, 129],
+        [181, 54, 122],
+        [229, 80, 100],
+        [251, 135, 97],
+        [254, 194, 135],
+        [252, 253, 191],
+    ];
+    let anchors: &[[u8; 3]] = match map {
+        ColorMap::Viridis => &VIRIDIS,
+        ColorMap::Magma => &MAGMA,
+        ColorMap::Grayscale => &[[0, 0, 0], [255, 255, 255]],
+    };
+    let position = level * (anchors.len() - 1) as f64;
+    let index = (position as usize).min(anchors.len() - 2);
+    let t = position - index as f64;
+    let (a, b) = (anchors[index], anchors[index + 1]);
+    std::array::from_fn(|c| (a[c] as f64 + (b[c] as f64 - a[c] as f64) * t).round() as u8)
+}
+
+/// RGB canvas for rendering
+struct Image {
+    width: usize,
+    height: usize,
+    rgb: Vec<u8>,
+}
+
+impl Image {
+    fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            rgb: vec![0; width * height * 3],
+        }
+    }
+
+    fn set(&mut self, x: usize, y: usize, color: [u8; 3]) {
+        if x < self.width && y < self.height {
+            let i = (y * self.width + x) * 3;
+            self.rgb[i..i + 3].copy_from_slice(&color);
+        }
+    }
+
+    /// Draw `text` with its top-left corner at (x, y) in the 3x5 label font
+    fn text(&mut self, x: usize, y: usize, text: &str) {
+        for (i, c) in text.chars().enumerate() {
+            for (row, bits) in glyph(c).iter().enumerate() {
+                for col in 0..3 {
+                    if bits & (0b100 >> col) != 0 {
+                        self.set(x + i * 4 + col, y + row, AXIS_COLOR);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn text_width(text: &str) -> usize {
+    (text.chars().count() * 4).saturating_sub(1)
+}
+
+/// Rows of a 3x5 glyph, most significant bit on the left
+fn glyph(c: char) -> [u8; 5] {
+    match c {
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b111, 0b001, 0b111, 0b100, 0b111],
+        '3' => [0b111, 0b001, 0b111, 0b001, 0b111],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b111, 0b001, 0b111],
+        '6' => [0b111, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b001, 0b001, 0b001],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b111],
+        '.' => [0b000, 0b000, 0b000, 0b000, 0b010],
+        'k' => [0b100, 0b101, 0b110, 0b101, 0b101],
+        's' => [0b000, 0b011, 0b100, 0b001, 0b110],
+        _ => [0; 5],
+    }
+}
@@ -1,24 +1,414 @@
 //! Audio streaming module for real-time playback
 
 use crate::buffer::SamplesBuffer;
-use crate::types::{PlayError, SupportedStreamConfig};
-use napi::{Error, Result, Status};
+use crate::devices::DeviceSupportedConfigs;
+use crate::engine::{self, find_output_device, DeviceErrorListener};
+use crate::error::{
+    caused_by, decode_failure, error, open_failure, stream_failure, AudioError, ErrorCallback,
+    ErrorCode, ErrorReporter, LockExt, Result,
+};
+use crate::types::{
+    DecoderError, DevicesError, StreamError, StreamOutputConfig, StreamState, SupportedStreamConfig,
+};
+use napi::bindgen_prelude::{Either, Float32Array, Int16Array};
+use napi::threadsafe_function::{ThreadsafeFunction, ThreadsafeFunctionCallMode};
 use napi_derive::napi;
-use rodio::{OutputStream, OutputStreamBuilder, Sink, Source as RodioSource};
+use ringbuf::traits::{Consumer, Observer, Producer, Split};
+use ringbuf::{HeapCons, HeapProd, HeapRb};
+use rodio::cpal::traits::DeviceTrait;
+use rodio::source::EmptyCallback;
+use rodio::{cpal, OutputStream, OutputStreamBuilder, Sink, Source as RodioSource};
 use std::fs::File;
 use std::io::BufReader;
 use std::path::Path;
-use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, Weak};
 use std::time::Duration;
 
+type OnPushEventCallback = Box<dyn Fn(f64) + Send + Sync>;
+type OnUnderrunCallback = Box<dyn Fn(u32) + Send + Sync>;
+type OnStateCallback = Box<dyn Fn(StreamState) + Send + Sync>;
+
+const DEFAULT_PUSH_BUFFER_MS: u32 = 2000;
+const DEFAULT_PUSH_HIGH_WATER_MS: u32 = 500;
+const DEFAULT_PUSH_PREBUFFER_MS: u32 = 100;
+
+/// Sample format of the PCM data written to a push stream
+#[napi(string_enum)]
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum PcmSampleFormat {
+    /// Signed 16-bit samples, written as `Int16Array`
+    I16,
+    /// 32-bit float samples in [-1, 1], written as `Float32Array`
+    F32,
+}
+
+/// Configuration of a push-mode stream
+#[napi(object)]
+#[derive(Debug, Clone)]
+pub struct PushStreamOptions {
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub format: PcmSampleFormat,
+    /// Capacity of the jitter buffer in milliseconds (default 2000)
+    pub buffer_ms: Option<u32>,
+    /// `write` returns false once this much audio is queued (default 500)
+    pub high_water_ms: Option<u32>,
+    /// Audio collected before playback starts or resumes after an underrun (default 100)
+    pub prebuffer_ms: Option<u32>,
+}
+
+/// Callbacks of a push stream, kept on the `AudioStream` so they survive reopening
+#[derive(Clone, Default)]
+pub(crate) struct PushCallbacks {
+    pub(crate) on_drain: Arc<Mutex<Option<OnPushEventCallback>>>,
+    pub(crate) on_underrun: Arc<Mutex<Option<OnUnderrunCallback>>>,
+    pub(crate) on_ended: Arc<Mutex<Option<OnUnderrunCallback>>>,
+}
+
+/// State shared between the writer (JS thread) and the source (audio thread)
+struct PushShared {
+    sample_rate: u32,
+    channels: u16,
+    format: PcmSampleFormat,
+    high_water: usize,
+    low_water: usize,
+    prebuffer: usize,
+    ended: AtomicBool,
+    finished: AtomicBool,
+    drain_pending: AtomicBool,
+    underruns: AtomicU32,
+    callbacks: PushCallbacks,
+}
+
+impl PushShared {
+    fn samples_to_ms(&self, samples: usize) -> f64 {
+        samples as f64 * 1000.0 / (self.sample_rate as f64 * self.channels as f64)
+    }
+}
+
+/// Writing half of a push stream
+pub(crate) struct PushWriter {
+    producer: HeapProd<f32>,
+    shared: Arc<PushShared>,
+}
+
+impl PushWriter {
+    /// Queue interleaved samples, returning false when the high-water mark is reached
+    pub(crate) fn write_samples(&mut self, samples: &[f32]) -> Result<bool> {
+        if self.shared.ended.load(Ordering::SeqCst) {
+            return Err(error(StreamError::Ended, "Push stream has already ended"));
+        }
+        if !samples.len().is_multiple_of(self.shared.channels as usize) {
+            return Err(error(
+                ErrorCode::InvalidArgument,
+                format!(
+                    "Sample count {} is not a multiple of {} channels",
+                    samples.len(),
+                    self.shared.channels
+                ),
+            ));
+        }
+        if samples.len() > self.producer.vacant_len() {
+            return Err(error(
+                StreamError::Overflow,
+                "Push buffer overflow: wait for the drain event before writing more",
+            ));
+        }
+
+        self.producer.push_slice(samples);
+
+        if self.producer.occupied_len() < self.shared.high_water {
+            return Ok(true);
+        }
+        self.shared.drain_pending.store(true, Ordering::SeqCst);
+        // The audio thread may have drained the buffer before the flag was raised
+        if self.producer.occupied_len() <= self.shared.low_water
+            && self.shared.drain_pending.swap(false, Ordering::SeqCst)
+        {
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    /// Mark the stream as finished; queued audio still plays out
+    pub(crate) fn end(&self) {
+        self.shared.ended.store(true, Ordering::SeqCst);
+    }
+
+    pub(crate) fn buffered_ms(&self) -> f64 {
+        self.shared.samples_to_ms(self.producer.occupied_len())
+    }
+
+    pub(crate) fn underrun_count(&self) -> u32 {
+        self.shared.underruns.load(Ordering::SeqCst)
+    }
+}
+
+impl Drop for PushWriter {
+    fn drop(&mut self) {
+        // Without a writer nothing can be queued anymore, let the source finish
+        self.end();
+    }
+}
+
+/// Reading half of a push stream, played by the sink
+pub(crate) struct PushSource {
+    consumer: HeapCons<f32>,
+    shared: Arc<PushShared>,
+    buffering: bool,
+    frame_pos: u16,
+}
+
+impl PushSource {
+    fn finish(&self) {
+        if !self.shared.finished.swap(true, Ordering::SeqCst) {
+            if let Some(cb) = self.shared.callbacks.on_ended.lock_or_recover().as_ref() {
+                cb(self.shared.underruns.load(Ordering::SeqCst));
+            }
+        }
+    }
+}
+
+impl Iterator for PushSource {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // Only switch between buffering and playing on frame boundaries so
+        // channels never get swapped
+        if self.frame_pos == 0 {
+            let available = self.consumer.occupied_len();
+            let ended = self.shared.ended.load(Ordering::SeqCst);
+            if self.buffering {
+                if available >= self.shared.prebuffer || (ended && available > 0) {
+                    self.buffering = false;
+                } else if ended {
+                    self.finish();
+                    return None;
+                }
+            } else if available < self.shared.channels as usize {
+                if ended {
+                    self.finish();
+                    return None;
+                }
+                self.buffering = true;
+                let count = self.shared.underruns.fetch_add(1, Ordering::SeqCst) + 1;
+                if let Some(cb) = self.shared.callbacks.on_underrun.lock_or_recover().as_ref() {
+                    cb(count);
+                }
+            }
+        }
+
+        self.frame_pos = (self.frame_pos + 1) % self.shared.channels;
+        if self.buffering {
+            return Some(0.0);
+        }
+
+        let sample = self.consumer.try_pop().unwrap_or(0.0);
+        if self.shared.drain_pending.load(Ordering::Relaxed) {
+            let queued = self.consumer.occupied_len();
+            if queued <= self.shared.low_water
+                && self.shared.drain_pending.swap(false, Ordering::SeqCst)
+            {
+                if let Some(cb) = self.shared.callbacks.on_drain.lock_or_recover().as_ref() {
+                    cb(self.shared.samples_to_ms(queued));
+                }
+            }
+        }
+        Some(sample)
+    }
+}
+
+impl RodioSource for PushSource {
+    fn current_span_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        self.shared.channels
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.shared.sample_rate
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+/// Create the ring buffer backed writer/source pair of a push stream
+pub(crate) fn push_channel(
+    options: &PushStreamOptions,
+    callbacks: PushCallbacks,
+) -> Result<(PushWriter, PushSource)> {
+    if options.sample_rate == 0 || options.channels == 0 {
+        return Err(error(
+            ErrorCode::InvalidArgument,
+            "Sample rate and channel count must be greater than zero",
+        ));
+    }
+
+    let ms_to_samples = |ms: u32| {
+        (ms as u64 * options.sample_rate as u64 * options.channels as u64 / 1000) as usize
+    };
+    let frame = options.channels as usize;
+    let capacity = ms_to_samples(options.buffer_ms.unwrap_or(DEFAULT_PUSH_BUFFER_MS)).max(frame);
+    let high_water = ms_to_samples(options.high_water_ms.unwrap_or(DEFAULT_PUSH_HIGH_WATER_MS))
+        .clamp(frame, capacity);
+    let prebuffer = ms_to_samples(options.prebuffer_ms.unwrap_or(DEFAULT_PUSH_PREBUFFER_MS))
+        .clamp(frame, high_water);
+
+    let shared = Arc::new(PushShared {
+        sample_rate: options.sample_rate,
+        channels: options.channels,
+        format: options.format,
+        high_water,
+        low_water: high_water / 2,
+        prebuffer,
+        ended: AtomicBool::new(false),
+        finished: AtomicBool::new(false),
+        drain_pending: AtomicBool::new(false),
+        underruns: AtomicU32::new(0),
+        callbacks,
+    });
+
+    let (producer, consumer) = HeapRb::<f32>::new(capacity).split();
+    Ok((
+        PushWriter {
+            producer,
+            shared: shared.clone(),
+        },
+        PushSource {
+            consumer,
+            shared,
+            buffering: true,
+            frame_pos: 0,
+        },
+    ))
+}
+
+/// Tracks the stream state from the sink: sources are followed by a marker
+/// that reports when they have been played out
+pub(crate) struct StreamStateTracker {
+    state: Mutex<StreamState>,
+    pending_sources: AtomicUsize,
+    input_ended: AtomicBool,
+    pub(crate) on_change: Mutex<Option<OnStateCallback>>,
+    pub(crate) errors: ErrorReporter,
+}
+
+impl StreamStateTracker {
+    pub(crate) fn new() -> Self {
+        Self {
+            state: Mutex::new(StreamState::Idle),
+            pending_sources: AtomicUsize::new(0),
+            input_ended: AtomicBool::new(false),
+            on_change: Mutex::new(None),
+            errors: ErrorReporter::default(),
+        }
+    }
+
+    pub(crate) fn get(&self) -> StreamState {
+        *self.state.lock_or_recover()
+    }
+
+    fn set(&self, state: StreamState) {
+        {
+            let mut current = self.state.lock_or_recover();
+            if *current == state {
+                return;
+            }
+            *current = state;
+        }
+        if let Some(cb) = self.on_change.lock_or_recover().as_ref() {
+            cb(state);
+        }
+    }
+
+    fn playing_state(&self) -> StreamState {
+        if self.input_ended.load(Ordering::SeqCst) {
+            StreamState::Draining
+        } else {
+            StreamState::Playing
+        }
+    }
+
+    pub(crate) fn source_queued(&self) {
+        self.pending_sources.fetch_add(1, Ordering::SeqCst);
+        self.input_ended.store(false, Ordering::SeqCst);
+        self.set(StreamState::Playing);
+    }
+
+    pub(crate) fn source_finished(&self) {
+        let previous = self
+            .pending_sources
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1));
+        if previous == Ok(1) && matches!(self.get(), StreamState::Playing | StreamState::Draining) {
+            self.set(StreamState::Ended);
+        }
+    }
+
+    pub(crate) fn end_input(&self) {
+        self.input_ended.store(true, Ordering::SeqCst);
+        if self.get() == StreamState::Playing {
+            self.set(StreamState::Draining);
+        }
+    }
+
+    pub(crate) fn pause(&self) {
+        if matches!(self.get(), StreamState::Playing | StreamState::Draining) {
+            self.set(StreamState::Paused);
+        }
+    }
+
+    pub(crate) fn resume(&self) {
+        if self.get() == StreamState::Paused {
+            self.set(self.playing_state());
+        }
+    }
+
+    pub(crate) fn reset(&self) {
+        self.pending_sources.store(0, Ordering::SeqCst);
+        self.input_ended.store(false, Ordering::SeqCst);
+        self.set(StreamState::Idle);
+    }
+
+    pub(crate) fn fail(&self) {
+        self.set(StreamState::Error);
+    }
+}
+
+impl DeviceErrorListener for StreamStateTracker {
+    fn stream_error(&self, err: AudioError) {
+        if err.status == ErrorCode::Devices(DevicesError::DeviceLost) {
+            self.fail();
+        }
+        self.errors.report(err);
+    }
+}
+
+/// Output errors only end the stream when the device is gone; other errors
+/// (e.g. ALSA xruns) are transient
+fn track_output_errors(
+    tracker: Arc<StreamStateTracker>,
+) -> impl FnMut(cpal::StreamError) + Clone + Send + 'static {
+    move |error| {
+        let lost = matches!(error, cpal::StreamError::DeviceNotAvailable);
+        tracker.stream_error(stream_failure(lost, &error));
+    }
+}
+
 /// Audio stream for real-time playback
 #[napi]
 pub struct AudioStream {
     sink: Arc<Mutex<Option<Sink>>>,
     output_stream: Arc<Mutex<Option<OutputStream>>>,
-    is_playing: Arc<Mutex<bool>>,
-    is_paused: Arc<Mutex<bool>>,
+    state: Arc<StreamStateTracker>,
     volume: Arc<Mutex<f32>>,
+    config: Arc<Mutex<Option<StreamOutputConfig>>>,
+    push: Arc<Mutex<Option<PushWriter>>>,
+    push_callbacks: PushCallbacks,
 }
 
 impl Default for AudioStream {
@@ -34,49 +424,49 @@ impl AudioStream {
         Self {
             sink: Arc::new(Mutex::new(None)),
             output_stream: Arc::new(Mutex::new(None)),
-            is_playing: Arc::new(Mutex::new(false)),
-            is_paused: Arc::new(Mutex::new(false)),
+            state: Arc::new(StreamStateTracker::new()),
             volume: Arc::new(Mutex::new(1.0)),
+            config: Arc::new(Mutex::new(None)),
+            push: Arc::new(Mutex::new(None)),
+            push_callbacks: PushCallbacks::default(),
         }
     }
 
-    /// Open and initialize the audio stream
+    /// Open and initialize the audio stream on the shared default output
     #[napi]
     pub fn open(&mut self) -> Result<()> {
-        let stream = OutputStreamBuilder::open_default_stream().map_err(|e| {
-            Error::new(
-                Status::InvalidArg,
-                format!("Failed to create output stream: {}", e),
-            )
-        })?;
-
-        let mixer = stream.mixer();
-        let sink = Sink::connect_new(mixer);
-
-        *self.output_stream.lock().unwrap() = Some(stream);
-        *self.sink.lock().unwrap() = Some(sink);
+        let listener: Weak<dyn DeviceErrorListener> = Arc::downgrade(&self.state) as _;
+        let (sink, config) =
+            engine::connect_sink(None, Some(listener)).inspect_err(|_| self.state.fail())?;
 
+        *self.output_stream.lock_or_recover() = None;
+        self.attach_sink(sink, config);
         Ok(())
     }
 
+    /// Get the configuration the output was opened with, or null before `open`
+    #[napi]
+    pub fn get_config(&self) -> Option<StreamOutputConfig> {
+        self.config.lock_or_recover().clone()
+    }
+
     /// Play an audio file
 
     #[napi]
     pub fn play_file(&self, file_path: String) -> Result<()> {
         let path = Path::new(&file_path);
         if !path.exists() {
-            return Err(Error::new(
-                Status::InvalidArg,
+            return Err(error(
+                DecoderError::FileNotFound,
                 format!("File not found: {}", file_path),
             ));
         }
 
-        let file = File::open(path)
-            .map_err(|e| Error::new(Status::InvalidArg, format!("Failed to open file: {}", e)))?;
+        let file = File::open(path).map_err(|e| open_failure(&file_path, e))?;
 
         let reader = BufReader::new(file);
-        let source = rodio::Decoder::new(reader)
-            .map_err(|e| Error::new(Status::InvalidArg, format!("Failed to decode: {}", e)))?;
+        let source =
+            rodio::Decoder::new(reader).map_err(|e| decode_failure("Failed to decode", e))?;
 
         self.play_source(source)
     }
@@ -85,7 +475,7 @@ impl AudioStream {
     #[napi]
     pub fn play_buffer(&self, buffer: &SamplesBuffer) -> Result<()> {
         // Convert buffer samples to a rodio source (rodio uses f32)
-        let samples_i16 = buffer.get_samples();
+        let samples_i16 = buffer.samples();
         let samples_f32: Vec<f32> = samples_i16
             .into_iter()
             .map(|s| s as f32 / 32768.0)
@@ -106,74 +496,214 @@ impl AudioStream {
 
         let data = general_purpose::STANDARD
             .decode(&base64_data)
-            .map_err(|e| Error::new(Status::InvalidArg, format!("Invalid base64: {}", e)))?;
+            .map_err(|e| {
+                caused_by(
+                    DecoderError::InvalidFormat,
+                    format!("Invalid base64: {}", e),
+                    e,
+                )
+            })?;
 
         let cursor = std::io::Cursor::new(data);
-        let source = rodio::Decoder::new(cursor)
-            .map_err(|e| Error::new(Status::InvalidArg, format!("Failed to decode: {}", e)))?;
+        let source =
+            rodio::Decoder::new(cursor).map_err(|e| decode_failure("Failed to decode", e))?;
 
         self.play_source(source)
     }
 
-    /// Get the current playback state
+    /// Start push mode: PCM chunks passed to `write` are played as they arrive.
+    /// Opens the output if needed; a previous push stream is ended first.
     #[napi]
-    pub fn get_state(&self) -> PlayError {
-        let is_playing = *self.is_playing.lock().unwrap();
-        let is_paused = *self.is_paused.lock().unwrap();
-
-        if is_paused {
-            PlayError::AlreadyPlaying
-        } else if is_playing {
-            PlayError::NotLoaded
-        } else {
-            PlayError::SystemError
+    pub fn open_push(&mut self, options: PushStreamOptions) -> Result<()> {
+        if self.sink.lock_or_recover().is_none() {
+            self.open()?;
+        }
+
+        let (writer, source) = push_channel(&options, self.push_callbacks.clone())?;
+        // Dropping the old writer ends it so the sink moves on to the new source
+        *self.push.lock_or_recover() = Some(writer);
+        self.play_source(source)
+    }
+
+    /// Queue a chunk of interleaved PCM samples in the format given to `openPush`.
+    /// Returns false once the high-water mark is reached; wait for the drain
+    /// event before writing more.
+    #[napi]
+    pub fn write(&self, data: Either<Int16Array, Float32Array>) -> Result<bool> {
+        let mut push_guard = self.push.lock_or_recover();
+        let writer = push_guard
+            .as_mut()
+            .ok_or_else(|| error(StreamError::NotPlaying, "Push stream not opened"))?;
+
+        match (data, writer.shared.format) {
+            (Either::A(samples), PcmSampleFormat::I16) => {
+                let samples: Vec<f32> = samples.iter().map(|&s| s as f32 / 32768.0).collect();
+                writer.write_samples(&samples)
+            }
+            (Either::B(samples), PcmSampleFormat::F32) => writer.write_samples(&samples),
+            (_, format) => Err(error(
+                ErrorCode::InvalidArgument,
+                format!(
+                    "Push stream expects {} samples",
+                    match format {
+                        PcmSampleFormat::I16 => "Int16Array",
+                        PcmSampleFormat::F32 => "Float32Array",
+                    }
+                ),
+            )),
         }
     }
 
-    /// Check if audio is currently playing
+    /// Stop accepting data; queued audio is played out before the ended event
+    #[napi]
+    pub fn end(&self) -> Result<()> {
+        let push_guard = self.push.lock_or_recover();
+        let writer = push_guard
+            .as_ref()
+            .ok_or_else(|| error(StreamError::NotPlaying, "Push stream not opened"))?;
+        writer.end();
+        self.state.end_input();
+        Ok(())
+    }
+
+    /// Get the amount of audio queued in the push buffer in milliseconds
+    #[napi]
+    pub fn get_buffered_ms(&self) -> f64 {
+        self.push
+            .lock_or_recover()
+            .as_ref()
+            .map_or(0.0, |writer| writer.buffered_ms())
+    }
+
+    /// Get the number of underruns of the current push stream
+    #[napi]
+    pub fn get_underrun_count(&self) -> u32 {
+        self.push
+            .lock_or_recover()
+            .as_ref()
+            .map_or(0, |writer| writer.underrun_count())
+    }
+
+    /// Called with the buffered milliseconds once the buffer drains below half the high-water mark
+    #[napi]
+    pub fn set_on_drain(&self, callback: ThreadsafeFunction<f64>) -> Result<()> {
+        let cb = Box::new(move |buffered_ms: f64| {
+            callback.call(
+                Ok::<_, napi::Error>(buffered_ms),
+                ThreadsafeFunctionCallMode::NonBlocking,
+            );
+        });
+
+        *self.push_callbacks.on_drain.lock_or_recover() = Some(cb);
+        Ok(())
+    }
+
+    /// Called with the total underrun count whenever the push buffer runs dry
+    #[napi]
+    pub fn set_on_underrun(&self, callback: ThreadsafeFunction<u32>) -> Result<()> {
+        let cb = Box::new(move |count: u32| {
+            callback.call(
+                Ok::<_, napi::Error>(count),
+                ThreadsafeFunctionCallMode::NonBlocking,
+            );
+        });
+
+        *self.push_callbacks.on_underrun.lock_or_recover() = Some(cb);
+        Ok(())
+    }
+
+    /// Called with the total underrun count once the push stream has ended
+    /// and all queued audio was played
+    #[napi]
+    pub fn set_on_ended(&self, callback: ThreadsafeFunction<u32>) -> Result<()> {
+        let cb = Box::new(move |underruns: u32| {
+            callback.call(
+                Ok::<_, napi::Error>(underruns),
+                ThreadsafeFunctionCallMode::NonBlocking,
+            );
+        });
+
+        *self.push_callbacks.on_ended.lock_or_recover() = Some(cb);
+        Ok(())
+    }
+
+    /// Get the current stream state
+    #[napi]
+    pub fn get_state(&self) -> StreamState {
+        self.state.get()
+    }
+
+    /// Called with the new state whenever the stream state changes
+    #[napi]
+    pub fn set_on_state_change(&self, callback: ThreadsafeFunction<StreamState>) -> Result<()> {
+        let cb = Box::new(move |state: StreamState| {
+            callback.call(
+                Ok::<_, napi::Error>(state),
+                ThreadsafeFunctionCallMode::NonBlocking,
+            );
+        });
+
+        *self.state.on_change.lock_or_recover() = Some(cb);
+        Ok(())
+    }
+
+    /// Called with errors raised on the audio thread, e.g. a `DeviceLost`
+    /// error when the output device disappears
+    #[napi(ts_args_type = "callback: (err: Error) => void")]
+    pub fn set_on_error(&self, callback: ErrorCallback) -> Result<()> {
+        self.state.errors.set(callback);
+        Ok(())
+    }
+
+    /// Check if audio is currently playing (including a draining push stream)
     #[napi]
     pub fn is_playing(&self) -> bool {
-        *self.is_playing.lock().unwrap()
+        matches!(
+            self.state.get(),
+            StreamState::Playing | StreamState::Draining
+        )
     }
 
     /// Pause the stream
     #[napi]
     pub fn pause(&mut self) -> Result<()> {
-        let sink_guard = self.sink.lock().unwrap();
+        let sink_guard = self.sink.lock_or_recover();
         if let Some(sink) = sink_guard.as_ref() {
             sink.pause();
-            *self.is_playing.lock().unwrap() = true;
-            *self.is_paused.lock().unwrap() = true;
+            self.state.pause();
             Ok(())
         } else {
-            Err(Error::new(Status::InvalidArg, "Stream not initialized"))
+            Err(error(
+                DevicesError::NotInitialized,
+                "Stream not initialized",
+            ))
         }
     }
 
     /// Resume the stream
     #[napi]
     pub fn resume(&mut self) -> Result<()> {
-        let sink_guard = self.sink.lock().unwrap();
+        let sink_guard = self.sink.lock_or_recover();
         if let Some(sink) = sink_guard.as_ref() {
             sink.play();
-            *self.is_playing.lock().unwrap() = true;
-            *self.is_paused.lock().unwrap() = false;
+            self.state.resume();
             Ok(())
         } else {
-            Err(Error::new(Status::InvalidArg, "Stream not initialized"))
+            Err(error(
+                DevicesError::NotInitialized,
+                "Stream not initialized",
+            ))
         }
     }
 
     /// Stop the stream
     #[napi]
     pub fn stop(&mut self) -> Result<()> {
-        let sink_guard = self.sink.lock().unwrap();
-        if let Some(sink) = sink_guard.as_ref() {
+        if let Some(sink) = self.sink.lock_or_recover().take() {
             sink.stop();
         }
-        *self.sink.lock().unwrap() = None;
-        *self.is_playing.lock().unwrap() = false;
-        *self.is_paused.lock().unwrap() = false;
+        *self.push.lock_or_recover() = None;
+        self.state.reset();
         Ok(())
     }
 
@@ -181,14 +711,14 @@ impl AudioStream {
     #[napi]
     pub fn set_volume(&mut self, volume: f64) -> Result<()> {
         if !(0.0..=1.0).contains(&volume) {
-            return Err(Error::new(
-                Status::InvalidArg,
+            return Err(error(
+                ErrorCode::InvalidArgument,
                 "Volume must be between 0.0 and 1.0",
             ));
         }
 
-        *self.volume.lock().unwrap() = volume as f32;
-        let sink_guard = self.sink.lock().unwrap();
+        *self.volume.lock_or_recover() = volume as f32;
+        let sink_guard = self.sink.lock_or_recover();
         if let Some(sink) = sink_guard.as_ref() {
             sink.set_volume(volume as f32);
         }
@@ -198,47 +728,67 @@ impl AudioStream {
     /// Get the current volume
     #[napi]
     pub fn get_volume(&self) -> f64 {
-        *self.volume.lock().unwrap() as f64
+        *self.volume.lock_or_recover() as f64
     }
 
-    /// Get supported stream configurations
+    /// Get supported stream configurations of the default output device
     #[napi]
     pub fn get_supported_configs() -> Vec<SupportedStreamConfig> {
-        vec![
-            SupportedStreamConfig {
-                sample_rate: 44100,
-                channel_count: 2,
-                sample_width: 16,
-            },
-            SupportedStreamConfig {
-                sample_rate: 48000,
-                channel_count: 2,
-                sample_width: 16,
-            },
-            SupportedStreamConfig {
-                sample_rate: 96000,
-                channel_count: 2,
-                sample_width: 16,
-            },
-        ]
+        crate::devices::device_output_configs(None)
+            .map(|supported| crate::devices::stream_configs(&supported))
+            .unwrap_or_default()
+    }
+
+    /// Get the supported output ranges of a device (default device if omitted)
+    #[napi]
+    pub fn get_device_configs(device_id: Option<String>) -> Result<DeviceSupportedConfigs> {
+        crate::devices::device_output_configs(device_id)
+    }
+
+    fn attach_output(&mut self, stream: OutputStream) {
+        let sink = Sink::connect_new(stream.mixer());
+        let config = engine::output_config(&stream);
+        *self.output_stream.lock_or_recover() = Some(stream);
+        self.attach_sink(sink, config);
+    }
+
+    fn attach_sink(&mut self, sink: Sink, config: StreamOutputConfig) {
+        sink.set_volume(*self.volume.lock_or_recover());
+        *self.config.lock_or_recover() = Some(config);
+        *self.sink.lock_or_recover() = Some(sink);
+        self.state.reset();
     }
 
     fn play_source<S>(&self, source: S) -> Result<()>
     where
         S: RodioSource<Item = f32> + Send + 'static,
     {
-        let sink_guard = self.sink.lock().unwrap();
-        if sink_guard.is_none() {
-            return Err(Error::new(Status::InvalidArg, "Stream not initialized"));
+        let sink_guard = self.sink.lock_or_recover();
+        let Some(sink) = sink_guard.as_ref() else {
+            return Err(error(
+                DevicesError::NotInitialized,
+                "Stream not initialized",
+            ));
+        };
+        if self.state.get() == StreamState::Error {
+            return Err(error(
+                DevicesError::DeviceLost,
+                "Output device is no longer available",
+            ));
         }
 
-        let sink = sink_guard.as_ref().unwrap();
-        sink.append(source);
+        self.state.source_queued();
+        sink.append(engine::voice(source));
+        // Reports when the source before it has been played out
+        let state = self.state.clone();
+        let reported = AtomicBool::new(false);
+        sink.append(EmptyCallback::new(Box::new(move || {
+            if !reported.swap(true, Ordering::SeqCst) {
+                state.source_finished();
+            }
+        })));
         sink.play();
 
-        *self.is_playing.lock().unwrap() = true;
-        *self.is_paused.lock().unwrap() = false;
-
         Ok(())
     }
 }
@@ -249,6 +799,7 @@ pub struct AudioStreamBuilder {
     sample_rate: Option<u32>,
     channels: Option<u16>,
     buffer_size: Option<u32>,
+    device_id: Option<String>,
 }
 
 impl Default for AudioStreamBuilder {
@@ -265,6 +816,7 @@ impl AudioStreamBuilder {
             sample_rate: None,
             channels: None,
             buffer_size: None,
+            device_id: None,
         }
     }
 
@@ -283,14 +835,150 @@ impl AudioStreamBuilder {
         self.buffer_size = Some(size);
     }
 
+    /// Use a specific output device (`host:index` id); the default device is used otherwise
+    #[napi]
+    pub fn set_device(&mut self, device_id: String) {
+        self.device_id = Some(device_id);
+    }
+
+    /// Apply every field set in `config`
+    #[napi]
+    pub fn set_config(&mut self, config: StreamOutputConfig) {
+        self.sample_rate = config.sample_rate.or(self.sample_rate);
+        self.channels = config.channels.or(self.channels);
+        self.buffer_size = config.buffer_size.or(self.buffer_size);
+    }
+
+    /// Open the output with the requested settings. Fails if the device cannot
+    /// provide them instead of silently falling back to another configuration.
     #[napi]
     pub fn build(&self) -> Result<AudioStream> {
-        // For now, just return a basic stream
-        // In a full implementation, this would configure the stream with the given parameters
-        Ok(AudioStream::new())
+        if self.sample_rate == Some(0) || self.channels == Some(0) || self.buffer_size == Some(0) {
+            return Err(error(
+                ErrorCode::InvalidArgument,
+                "Sample rate, channels and buffer size must be greater than zero",
+            ));
+        }
+
+        let device = find_output_device(self.device_id.as_deref())?;
+        let supported = self.find_supported_config(&device)?;
+
+        let mut stream = AudioStream::new();
+        let mut builder = OutputStreamBuilder::default()
+            .with_device(device)
+            .with_supported_config(&supported)
+            .with_error_callback(track_output_errors(stream.state.clone()));
+        if let Some(frames) = self.buffer_size {
+            builder = builder.with_buffer_size(cpal::BufferSize::Fixed(frames));
+        }
+
+        let output = builder.open_stream().map_err(|e| {
+            caused_by(
+                DevicesError::DeviceUnavailable,
+                format!(
+                    "Failed to open output stream at {} Hz, {} channels: {}",
+                    supported.sample_rate().0,
+                    supported.channels(),
+                    e
+                ),
+                e,
+            )
+        })?;
+
+        stream.attach_output(output);
+        Ok(stream)
+    }
+
+    /// Pick a device config matching the requested settings, preferring the
+    /// device's default sample format. Unset settings use the device defaults.
+    fn find_supported_config(&self, device: &cpal::Device) -> Result<cpal::SupportedStreamConfig> {
+        let device_name = device.name().unwrap_or_else(|_| "Unknown".to_string());
+        let default = device.default_output_config().map_err(|e| {
+            caused_by(
+                DevicesError::DeviceUnavailable,
+                format!("Failed to query output device '{}': {}", device_name, e),
+                e,
+            )
+        })?;
+        let sample_rate = self.sample_rate.unwrap_or(default.sample_rate().0);
+        let channels = self.channels.unwrap_or(default.channels());
+
+        let ranges: Vec<cpal::SupportedStreamConfigRange> = device
+            .supported_output_configs()
+            .map_err(|e| {
+                caused_by(
+                    DevicesError::DeviceUnavailable,
+                    format!("Failed to query output device '{}': {}", device_name, e),
+                    e,
+                )
+            })?
+            .collect();
+
+        let mut matching: Vec<&cpal::SupportedStreamConfigRange> = ranges
+            .iter()
+            .filter(|range| config_range_supports(range, sample_rate, channels, self.buffer_size))
+            .collect();
+        matching.sort_by_key(|range| range.sample_format() != default.sample_format());
+
+        match matching.first() {
+            Some(range) => Ok(range.with_sample_rate(cpal::SampleRate(sample_rate))),
+            None => {
+                let requested = match self.buffer_size {
+                    Some(frames) => format!(
+                        "{} Hz, {} channels, {} frame buffer",
+                        sample_rate, channels, frames
+                    ),
+                    None => format!("{} Hz, {} channels", sample_rate, channels),
+                };
+                let available: Vec<String> = ranges.iter().map(describe_config_range).collect();
+                Err(error(
+                    StreamError::UnsupportedFormat,
+                    format!(
+                        "Output device '{}' does not support {}; supported: {}",
+                        device_name,
+                        requested,
+                        available.join(", ")
+                    ),
+                ))
+            }
+        }
     }
 }
 
+/// Whether a device config range can run at the given settings
+pub(crate) fn config_range_supports(
+    range: &cpal::SupportedStreamConfigRange,
+    sample_rate: u32,
+    channels: u16,
+    buffer_size: Option<u32>,
+) -> bool {
+    let buffer_ok = match (buffer_size, range.buffer_size()) {
+        (Some(frames), cpal::SupportedBufferSize::Range { min, max }) => {
+            (*min..=*max).contains(&frames)
+        }
+        _ => true,
+    };
+
+    range.channels() == channels
+        && (range.min_sample_rate().0..=range.max_sample_rate().0).contains(&sample_rate)
+        && buffer_ok
+}
+
+fn describe_config_range(range: &cpal::SupportedStreamConfigRange) -> String {
+    let buffer = match range.buffer_size() {
+        cpal::SupportedBufferSize::Range { min, max } => format!(" ({}-{} frames)", min, max),
+        cpal::SupportedBufferSize::Unknown => String::new(),
+    };
+    format!(
+        "{}ch {}-{} Hz {}{}",
+        range.channels(),
+        range.min_sample_rate().0,
+        range.max_sample_rate().0,
+        range.sample_format(),
+        buffer
+    )
+}
+
 /// Create and open a stream for audio playback
 #[napi]
 pub fn play(file_path: String) -> Result<AudioStream> {
@@ -300,6 +988,14 @@ pub fn play(file_path: String) -> Result<AudioStream> {
     Ok(stream)
 }
 
+/// Create and open a stream in push mode, ready for `write` calls
+#[napi]
+pub fn open_push_stream(options: PushStreamOptions) -> Result<AudioStream> {
+    let mut stream = AudioStream::new();
+    stream.open_push(options)?;
+    Ok(stream)
+}
+
 /// Get supported output configurations for the audio system
 #[napi]
 pub fn supported_output_configs() -> Result<Vec<SupportedStreamConfig>> {
@@ -353,9 +1049,10 @@ fn make_source_from_vec(
         }
 
         fn total_duration(&self) -> Option<Duration> {
-            Some(Duration::from_secs_f64(
+            Duration::try_from_secs_f64(
                 (self.samples.len() as f64) / (self.sample_rate as f64 * self.channels as f64),
-            ))
+            )
+            .ok()
         }
     }
 
@@ -0,0 +1,400 @@
+//! Tempo and beat tracking from a spectral-flux onset envelope
+
+use crate::analyser::{window_coefficients, Fft, WindowFunction};
+use crate::decoder::CANCEL_CHECK_INTERVAL;
+use crate::error::{error, ErrorCode, Result};
+use crate::types::CancelToken;
+use napi_derive::napi;
+use std::collections::VecDeque;
+
+/// Onset envelope frames per second
+const FRAMES_PER_SECOND: u32 = 100;
+/// Gain inside the log compression of spectral magnitudes
+const COMPRESSION: f64 = 1000.0;
+/// Flux is measured above its mean over this many previous frames
+const FLUX_MEAN_FRAMES: usize = 8;
+const DEFAULT_MIN_BPM: f64 = 60.0;
+const DEFAULT_MAX_BPM: f64 = 200.0;
+const DEFAULT_BEATS_PER_BAR: u32 = 4;
+/// Tempo prior: log-normal around 120 BPM with a one-octave deviation
+const PRIOR_BPM: f64 = 120.0;
+const PRIOR_OCTAVES: f64 = 1.0;
+/// Multiples of a candidate beat period summed by the comb filter
+const COMB_HARMONICS: usize = 4;
+/// How strongly beat tracking keeps to the estimated period
+const TIGHTNESS: f64 = 100.0;
+/// Onset history `AudioRecorder` keeps for `getTempo`
+pub(crate) const LIVE_WINDOW_SECONDS: u32 = 30;
+
+#[napi(object)]
+#[derive(Debug, Clone, Default)]
+pub struct TempoOptions {
+    /// Slowest tempo considered, at least 1 (default 60)
+    pub min_bpm: Option<f64>,
+    /// Fastest tempo considered (default 200)
+    pub max_bpm: Option<f64>,
+    /// Beats per bar for downbeat estimation (default 4)
+    pub beats_per_bar: Option<u32>,
+}
+
+/// Estimated tempo and beat grid; times are in seconds from the start of the input
+#[napi(object)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct TempoInfo {
+    /// 0 when no steady pulse was found
+    pub bpm: f64,
+    /// From 0 (no periodicity) to 1 (a strictly periodic onset pattern)
+    pub confidence: f64,
+    pub beats: Vec<f64>,
+    /// The beats estimated to start a bar
+    pub downbeats: Vec<f64>,
+}
+
+impl TempoInfo {
+    fn none() -> Self {
+        Self {
+            bpm: 0.0,
+            confidence: 0.0,
+            beats: Vec::new(),
+            downbeats: Vec::new(),
+        }
+    }
+}
+
+/// Streaming onset detector fed with interleaved samples in [-1, 1].
+/// Tempo and beats are estimated from the onset envelope on demand.
+pub struct TempoTracker {
+    sample_rate: u32,
+    channels: usize,
+    next_channel: usize,
+    frame_sum: f32,
+    hop_size: usize,
+    window: Vec<f64>,
+    pending: VecDeque<f32>,
+    fft: Fft,
+    re: Vec<f64>,
+    im: Vec<f64>,
+    // Log-compressed magnitudes of the previous transform
+    previous: Option<Vec<f64>>,
+    recent_flux: VecDeque<f64>,
+    envelope: VecDeque<f32>,
+    // Envelope frames dropped from the front to stay within `limit`
+    dropped: usize,
+    limit: Option<usize>,
+}
+
+impl TempoTracker {
+    /// Track tempo over everything added
+    pub fn new(sample_rate: u32, channels: u16) -> Self {
+        Self::with_history(sample_rate, channels, None)
+    }
+
+    /// Track tempo over the latest `seconds` of audio only
+    pub fn windowed(sample_rate: u32, channels: u16, seconds: u32) -> Self {
+        Self::with_history(
+            sample_rate,
+            channels,
+            Some((seconds * FRAMES_PER_SECOND) as usize),
+        )
+    }
+
+    fn with_history(sample_rate: u32, channels: u16, limit: Option<usize>) -> Self {
+        let sample_rate = sample_rate.max(1);
+        let hop_size = (sample_rate as f64 / FRAMES_PER_SECOND as f64)
+            .round()
+            .max(1.0) as usize;
+        let fft_size = (hop_size * 2).next_power_of_two().max(2);
+        Self {
+            sample_rate,
+            channels: channels.max(1) as usize,
+            next_channel: 0,
+            frame_sum: 0.0,
+            hop_size,
+            window: window_coefficients(WindowFunction::Hann, fft_size),
+            pending: VecDeque::with_capacity(fft_size),
+            fft: Fft::new(fft_size),
+            re: vec![0.0; fft_size],
+            im: vec![0.0; fft_size],
+            previous: None,
+            recent_flux: VecDeque::with_capacity(FLUX_MEAN_FRAMES),
+            envelope: VecDeque::new(),
+            dropped: 0,
+            limit,
+        }
+    }
+
+    pub fn add_samples<I: IntoIterator<Item = f32>>(&mut self, samples: I) {
+        for sample in samples {
+            self.frame_sum += sample;
+            self.next_channel += 1;
+            if self.next_channel == self.channels {
+                self.pending
+                    .push_back(self.frame_sum / self.channels as f32);
+                self.frame_sum = 0.0;
+                self.next_channel = 0;
+                if self.pending.len() == self.window.len() {
+                    self.onset();
+                    self.pending.drain(..self.hop_size);
+                }
+            }
+        }
+    }
+
+    /// Append the onset strength of the pending transform to the envelope
+    fn onset(&mut self) {
+        for ((re, &s), &w) in self.re.iter_mut().zip(&self.pending).zip(&self.window) {
+            *re = s as f64 * w;
+        }
+        self.im.fill(0.0);
+        self.fft.process(&mut self.re, &mut self.im);
+
+        let bins = self.window.len() / 2;
+        let current: Vec<f64> = (0..bins)
+            .map(|k| (1.0 + COMPRESSION * self.re[k].hypot(self.im[k])).ln())
+            .collect();
+        // The first transform has nothing to rise from
+        let flux = self.previous.as_ref().map_or(0.0, |previous| {
+            current
+                .iter()
+                .zip(previous)
+                .map(|(c, p)| (c - p).max(0.0))
+                .sum()
+        });
+        self.previous = Some(current);
+
+        let mean = self.recent_flux.iter().sum::<f64>() / FLUX_MEAN_FRAMES as f64;
+        if self.recent_flux.len() == FLUX_MEAN_FRAMES {
+            self.recent_flux.pop_front();
+        }
+        self.recent_flux.push_back(flux);
+
+        self.envelope.push_back((flux - mean).max(0.0) as f32);
+        if self.limit.is_some_and(|limit| self.envelope.len() > limit) {
+            self.envelope.pop_front();
+            self.dropped += 1;
+        }
+    }
+
+    /// Estimate tempo, beats and downbeats from the onsets seen so far
+    pub fn estimate(&self, options: &TempoOptions) -> Result<TempoInfo> {
+        self.envelope().estimate(options)
+    }
+
+    /// Copy the onset envelope, so it can be estimated without holding the tracker
+    pub fn envelope(&self) -> OnsetEnvelope {
+        OnsetEnvelope {
+            sample_rate: self.sample_rate,
+            hop_size: self.hop_size,
+            window_len: self.window.len(),
+            dropped: self.dropped,
+            frames: self.envelope.iter().map(|&v| v as f64).collect(),
+        }
+    }
+}
+
+/// Snapshot of a `TempoTracker` onset envelope
+pub struct OnsetEnvelope {
+    sample_rate: u32,
+    hop_size: usize,
+    window_len: usize,
+    dropped: usize,
+    frames: Vec<f64>,
+}
+
+impl OnsetEnvelope {
+    /// Estimate tempo, beats and downbeats from the envelope
+    pub fn estimate(&self, options: &TempoOptions) -> Result<TempoInfo> {
+        let min_bpm = options.min_bpm.unwrap_or(DEFAULT_MIN_BPM);
+        let max_bpm = options.max_bpm.unwrap_or(DEFAULT_MAX_BPM);
+        let beats_per_bar = options.beats_per_bar.unwrap_or(DEFAULT_BEATS_PER_BAR);
+        if !(min_bpm >= 1.0 && min_bpm < max_bpm && max_bpm.is_finite()) {
+            return Err(error(
+                ErrorCode::InvalidArgument,
+                "BPM range must satisfy 1 <= minBpm < maxBpm",
+            ));
+        }
+        if beats_per_bar == 0 {
+            return Err(error(
+                ErrorCode::InvalidArgument,
+                "beatsPerBar must be at least 1",
+            ));
+        }
+
+        let envelope = &self.frames;
+        let frame_rate = self.sample_rate as f64 / self.hop_size as f64;
+        let Some((period, confidence)) = estimate_period(envelope, frame_rate, min_bpm, max_bpm)
+        else {
+            return Ok(TempoInfo::none());
+        };
+
+        let beats = track_beats(envelope, period);
+        let strength = |frame: usize| {
+            envelope[frame.saturating_sub(2)..(frame + 3).min(envelope.len())]
+                .iter()
+                .fold(0.0f64, |a, &b| a.max(b))
+        };
+        // The bar phase whose beats carry the strongest onsets
+        let bar = beats_per_bar as usize;
+        let phase = (0..bar.min(beats.len()))
+            .max_by(|&a, &b| {
+                let mean = |phase: usize| {
+                    let accents: Vec<f64> = beats[phase..]
+                        .iter()
+                        .step_by(bar)
+                        .map(|&f| strength(f))
+                        .collect();
+                    accents.iter().sum::<f64>() / accents.len() as f64
+                };
+                mean(a).total_cmp(&mean(b))
+            })
+            .unwrap_or(0);
+
+        // Envelope frames are timed at the centre of their transform
+        let offset = self.window_len as f64 / 2.0;
+        let seconds = |frame: &usize| {
+            ((self.dropped + frame) * self.hop_size) as f64 / self.sample_rate as f64
+                + offset / self.sample_rate as f64
+        };
+        Ok(TempoInfo {
+            bpm: 60.0 * frame_rate / period,
+            confidence,
+            beats: beats.iter().map(seconds).collect(),
+            downbeats: beats.iter().skip(phase).step_by(bar).map(seconds).collect(),
+        })
+    }
+}
+
+/// Beat period in envelope frames and its confidence, from the autocorrelation
+/// of the envelope passed through a comb filter and weighted by a tempo prior
+fn estimate_period(
+    envelope: &[f64],
+    frame_rate: f64,
+    min_bpm: f64,
+    max_bpm: f64,
+) -> Option<(f64, f64)> {
+    let min_lag = ((60.0 * frame_rate / max_bpm).floor() as usize).max(1);
+    let max_lag = (60.0 * frame_rate / min_bpm).ceil() as usize;
+    if envelope.len() <= max_lag.saturating_mul(2) {
+        return None;
+    }
+
+    let mean = envelope.iter().sum::<f64>() / envelope.len() as f64;
+    let centred: Vec<f64> = envelope.iter().map(|v| v - mean).collect();
+    let lags = max_lag
+        .saturating_mul(COMB_HARMONICS)
+        .min(centred.len() - 1);
+    let autocorrelation: Vec<f64> = (0..=lags)
+        .map(|lag| {
+            let sum: f64 = centred
+                .iter()
+                .zip(&centred[lag..])
+                .map(|(a, b)| a * b)
+                .sum();
+            sum / (centred.len() - lag) as f64
+        })
+        .collect();
+    if autocorrelation[0] <= f64::EPSILON {
+        return None;
+    }
+
+    let score = |lag: usize| {
+        let (sum, weight) = (1..=COMB_HARMONICS).take_while(|k| k * lag <= lags).fold(
+            (0.0, 0.0),
+            |(sum, weight), k| {
+                (
+                    sum + autocorrelation[k * lag] / k as f64,
+                    weight + 1.0 / k as f64,
+                )
+            },
+        );
+        let bpm = 60.0 * frame_rate / lag as f64;
+        let prior = (-0.5 * ((bpm / PRIOR_BPM).log2() / PRIOR_OCTAVES).powi(2)).exp();
+        sum / weight * prior
+    };
+    let scores: Vec<f64> = (min_lag..=max_lag).map(score).collect();
+    let best = (0..scores.len()).max_by(|&a, &b| scores[a].total_cmp(&scores[b]))?;
+    let lag = min_lag + best;
+
+    // Parabolic interpolation between the neighbouring lags
+    let mut period = lag as f64;
+    if best > 0 && best + 1 < scores.len() {
+        let (a, b, c) = (scores[best - 1], scores[best], scores[best + 1]);
+        let curvature = a - 2.0 * b + c;
+        if curvature < 0.0 {
+            period += (0.5 * (a - c) / curvature).clamp(-0.5, 0.5);
+        }
+    }
+    let confidence = (autocorrelation[lag] / autocorrelation[0]).clamp(0.0, 1.0);
+    Some((period, confidence))
+}
+
+/// Dynamic-programming beat tracker (Ellis 2007): beats land on strong onsets
+/// while their spacing stays close to `period`. Returns envelope frames.
+fn track_beats(envelope: &[f64], period: f64) -> Vec<usize> {
+    let n = envelope.len();
+    let spread = envelope.iter().map(|v| v * v).sum::<f64>() / n as f64;
+    let norm = spread.sqrt().max(f64::EPSILON);
+    let mut scores = vec![0.0; n];
+    let mut previous: Vec<Option<usize>> = vec![None; n];
+
+    for t in 0..n {
+        let local = envelope[t] / norm;
+        let earliest = t.saturating_sub((2.0 * period).round() as usize);
+        let latest = t.saturating_sub((period / 2.0).round().max(1.0) as usize);
+        let best = (earliest..latest)
+            .filter(|&p| p < t)
+            .map(|p| {
+                let penalty = TIGHTNESS * ((t - p) as f64 / period).ln().powi(2);
+                (p, scores[p] - penalty)
+            })
+            .max_by(|a, b| a.1.total_cmp(&b.1));
+        match best {
+            Some((p, score)) if score > 0.0 => {
+                scores[t] = local + score;
+                previous[t] = Some(p);
+            }
+            _ => scores[t] = local,
+        }
+    }
+
+    let tail = n.saturating_sub(period.round() as usize);
+    let Some(last) = (tail..n).max_by(|&a, &b| scores[a].total_cmp(&scores[b])) else {
+        return Vec::new();
+    };
+    let mut beats = vec![last];
+    while let Some(p) = previous[*beats.last().unwrap_or(&last)] {
+        beats.push(p);
+    }
+    beats.reverse();
+
+    // Drop beats extrapolated into quiet lead-ins and tails
+    let strength = |&frame: &usize| envelope[frame];
+    let threshold = 0.5 * beats.iter().map(strength).sum::<f64>() / beats.len() as f64;
+    let first = beats
+        .iter()
+        .position(|b| strength(b) >= threshold)
+        .unwrap_or(0);
+    let end = beats
+        .iter()
+        .rposition(|b| strength(b) >= threshold)
+        .map_or(0, |i| i + 1);
+    beats[first..end.max(first)].to_vec()
+}
+
+/// Estimate tempo over interleaved `samples`
+pub(crate) fn measure<I: Iterator<Item = f32>>(
+    samples: I,
+    sample_rate: u32,
+    channels: u16,
+    options: &TempoOptions,
+    cancel: &CancelToken,
+) -> Result<TempoInfo> {
+    let mut tracker = TempoTracker::new(sample_rate, channels);
+    let mut samples = samples.peekable();
+    while samples.peek().is_some() {
+        cancel.check()?;
+        tracker.add_samples(samples.by_ref().take(CANCEL_CHECK_INTERVAL));
+    }
+    cancel.check()?;
+    tracker.estimate(options)
+}
@@ -1,5 +1,8 @@
+use crate::error::{error, ErrorCode, Result};
+use napi::bindgen_prelude::AbortSignal;
 use napi_derive::napi;
-use std::sync::atomic::AtomicBool;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 
 /// Audio sample type (16-bit signed integer)
 pub type Sample = i16;
@@ -36,6 +39,22 @@ pub enum PlaybackState {
     Paused,
 }
 
+/// Audio stream state, driven by the output sink
+#[napi(string_enum)]
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum StreamState {
+    /// Nothing has been played since the stream was opened or stopped
+    Idle,
+    Playing,
+    Paused,
+    /// A push stream was ended and its queued audio is being played out
+    Draining,
+    /// Everything queued has finished playing
+    Ended,
+    /// The output device failed or could not be opened
+    Error,
+}
+
 #[napi(object)]
 pub struct AudioPlayerConfig {
     pub volume: Option<f64>,
@@ -53,11 +72,30 @@ pub struct AudioMetadata {
 
 /// Error types for device operations
 #[napi(string_enum)]
+#[derive(Debug, PartialEq, Clone, Copy)]
 pub enum DevicesError {
     NoDevicesFound,
     PermissionDenied,
+    /// The requested device id or name does not exist
     InvalidDevice,
     NotInitialized,
+    /// The device exists but could not be opened or configured
+    DeviceUnavailable,
+    /// The device disappeared while a stream was running
+    DeviceLost,
+}
+
+impl AsRef<str> for DevicesError {
+    fn as_ref(&self) -> &str {
+        match self {
+            DevicesError::NoDevicesFound => "NoDevicesFound",
+            DevicesError::PermissionDenied => "PermissionDenied",
+            DevicesError::InvalidDevice => "InvalidDevice",
+            DevicesError::NotInitialized => "NotInitialized",
+            DevicesError::DeviceUnavailable => "DeviceUnavailable",
+            DevicesError::DeviceLost => "DeviceLost",
+        }
+    }
 }
 
 /// Audio source function types (for generator sources)
@@ -74,36 +112,104 @@ pub enum SourceFunction {
 
 /// Error types for stream operations
 #[napi(string_enum)]
+#[derive(Debug, PartialEq, Clone, Copy)]
 pub enum StreamError {
     NotPlaying,
     EndOfFile,
     InvalidData,
     UnsupportedFormat,
+    /// The stream or queue no longer accepts data
+    Ended,
+    /// More data was written than the stream can buffer
+    Overflow,
+}
+
+impl AsRef<str> for StreamError {
+    fn as_ref(&self) -> &str {
+        match self {
+            StreamError::NotPlaying => "NotPlaying",
+            StreamError::EndOfFile => "EndOfFile",
+            StreamError::InvalidData => "InvalidData",
+            StreamError::UnsupportedFormat => "UnsupportedFormat",
+            StreamError::Ended => "Ended",
+            StreamError::Overflow => "Overflow",
+        }
+    }
 }
 
 /// Play error for stream operations
 #[napi(string_enum)]
+#[derive(Debug, PartialEq, Clone, Copy)]
 pub enum PlayError {
     AlreadyPlaying,
     NotLoaded,
     SystemError,
 }
 
+impl AsRef<str> for PlayError {
+    fn as_ref(&self) -> &str {
+        match self {
+            PlayError::AlreadyPlaying => "AlreadyPlaying",
+            PlayError::NotLoaded => "NotLoaded",
+            PlayError::SystemError => "SystemError",
+        }
+    }
+}
+
 /// Seek error types
 #[napi(string_enum)]
+#[derive(Debug, PartialEq, Clone, Copy)]
 pub enum SeekError {
     InvalidPosition,
     NotSeekable,
     OutOfBounds,
 }
 
-/// Decoder error types (for audio decoding operations)
+impl AsRef<str> for SeekError {
+    fn as_ref(&self) -> &str {
+        match self {
+            SeekError::InvalidPosition => "InvalidPosition",
+            SeekError::NotSeekable => "NotSeekable",
+            SeekError::OutOfBounds => "OutOfBounds",
+        }
+    }
+}
+
+/// Decoder error types (for audio decoding operations), exposed as the JS error `code`
 #[napi(string_enum)]
+#[derive(Debug, PartialEq, Clone, Copy)]
 pub enum DecoderError {
+    /// The file does not exist
+    FileNotFound,
+    /// The magic bytes match no known audio container
     InvalidFormat,
+    /// The headers or the first packet could not be parsed
     CorruptedData,
+    /// The container is readable but its audio codec is not compiled in
     UnsupportedCodec,
+    /// The file could not be opened or read
     IoError,
+    /// A known container this build cannot read (AIFF, Matroska, CAF)
+    UnsupportedFormat,
+    /// The container holds no audio track
+    NoAudioTrack,
+    /// The input holds no data
+    EmptyInput,
+}
+
+impl AsRef<str> for DecoderError {
+    fn as_ref(&self) -> &str {
+        match self {
+            DecoderError::FileNotFound => "FileNotFound",
+            DecoderError::InvalidFormat => "InvalidFormat",
+            DecoderError::CorruptedData => "CorruptedData",
+            DecoderError::UnsupportedCodec => "UnsupportedCodec",
+            DecoderError::IoError => "IoError",
+            DecoderError::UnsupportedFormat => "UnsupportedFormat",
+            DecoderError::NoAudioTrack => "NoAudioTrack",
+            DecoderError::EmptyInput => "EmptyInput",
+        }
+    }
 }
 
 /// Stream play error
@@ -116,6 +222,7 @@ pub enum StreamPlayError {
 
 /// Supported stream configuration
 #[napi(object)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct SupportedStreamConfig {
     pub sample_rate: SampleRate,
     pub channel_count: ChannelCount,
@@ -124,12 +231,47 @@ pub struct SupportedStreamConfig {
 
 /// Stream output configuration
 #[napi(object)]
+#[derive(Debug, Clone)]
 pub struct StreamOutputConfig {
     pub sample_rate: Option<SampleRate>,
     pub channels: Option<ChannelCount>,
+    /// Buffer size in frames; unset means the device default
     pub buffer_size: Option<u32>,
 }
 
+/// Cancellation flag shared between an async task and its AbortSignal
+#[derive(Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    /// Create a token that is cancelled when `signal` aborts
+    pub fn from_signal(signal: Option<&AbortSignal>) -> Self {
+        let token = Self::default();
+        if let Some(signal) = signal {
+            let flag = token.0.clone();
+            signal.on_abort(move || flag.store(true, Ordering::SeqCst));
+        }
+        token
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+
+    /// Fail with the `Cancelled` code once the token has been cancelled
+    pub fn check(&self) -> Result<()> {
+        if self.is_cancelled() {
+            Err(error(ErrorCode::Cancelled, "Operation was aborted"))
+        } else {
+            Ok(())
+        }
+    }
+}
+
 /// Global debug flag (defaults to false)
 pub static DEBUG_ENABLED: AtomicBool = AtomicBool::new(false);
 
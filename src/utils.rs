@@ -1,19 +1,23 @@
-use crate::types::{AudioMetadata, DEBUG_ENABLED};
-use napi::{Error, Result, Status};
+use crate::engine::PlaybackHandle;
+use crate::error::{error, Result};
+use crate::types::{AudioMetadata, DecoderError, DEBUG_ENABLED};
 use napi_derive::napi;
-use rodio::{OutputStreamBuilder, Sink, Source};
+use rodio::Source;
 use std::path::Path;
 use std::sync::atomic::Ordering;
-use std::thread;
 use std::time::Duration;
+use symphonia::core::codecs::{
+    CodecType, CODEC_TYPE_AAC, CODEC_TYPE_FLAC, CODEC_TYPE_MP3, CODEC_TYPE_OPUS,
+    CODEC_TYPE_PCM_S16LE, CODEC_TYPE_VORBIS,
+};
 
 #[napi]
 pub fn initialize_audio() -> Result<String> {
-    match OutputStreamBuilder::open_default_stream() {
-        Ok(_stream) => Ok("Audio system initialized with rodio".to_string()),
-        Err(e) => Err(Error::new(
-            Status::GenericFailure,
-            format!("Failed to initialize audio: {}", e),
+    match crate::engine::AudioEngine::init(None) {
+        Ok(_) => Ok("Audio system initialized with rodio".to_string()),
+        Err(e) => Err(error(
+            e.status,
+            format!("Failed to initialize audio: {}", e.reason),
         )),
     }
 }
@@ -30,19 +34,31 @@ pub fn is_debug_enabled() -> bool {
     DEBUG_ENABLED.load(Ordering::Relaxed)
 }
 
+/// Get the file formats whose codecs are compiled into the decoder.
+/// `ogg` means Ogg Vorbis; Ogg Opus files need `opus`.
 #[napi]
 pub fn get_supported_formats() -> Vec<String> {
-    vec![
-        "wav".to_string(),
-        "mp3".to_string(),
-        "flac".to_string(),
-        "ogg".to_string(),
-        "aac".to_string(),
-        "m4a".to_string(),
-        "opus".to_string(),
-    ]
+    let codecs = symphonia::default::get_codecs();
+    FORMAT_CODECS
+        .iter()
+        .filter(|(_, codec)| codecs.get_codec(*codec).is_some())
+        .map(|(extension, _)| extension.to_string())
+        .collect()
 }
 
+/// File extensions and the codec each one needs in the decoder registry.
+/// The extension cannot tell Ogg Vorbis from Ogg Opus: "ogg" stands for Vorbis only,
+/// and Opus in either container is reported through "opus".
+const FORMAT_CODECS: &[(&str, CodecType)] = &[
+    ("wav", CODEC_TYPE_PCM_S16LE),
+    ("mp3", CODEC_TYPE_MP3),
+    ("flac", CODEC_TYPE_FLAC),
+    ("ogg", CODEC_TYPE_VORBIS),
+    ("aac", CODEC_TYPE_AAC),
+    ("m4a", CODEC_TYPE_AAC),
+    ("opus", CODEC_TYPE_OPUS),
+];
+
 #[napi]
 pub fn is_format_supported(format: String) -> bool {
     get_supported_formats().contains(&format.to_lowercase())
@@ -50,39 +66,27 @@ pub fn is_format_supported(format: String) -> bool {
 
 #[napi]
 pub fn get_audio_info() -> Result<String> {
-    Ok("Audio system: rodio\nDefault device: Default Output Device\nChannels: Stereo\nSample rate: 44100".to_string())
+    Ok(crate::devices::describe_audio_system())
 }
 
+/// Play a sine tone on the default output and return immediately
 #[napi]
-pub fn test_tone(frequency: f64, duration_ms: u32) -> Result<()> {
+pub fn test_tone(frequency: f64, duration_ms: u32) -> Result<PlaybackHandle> {
     use rodio::source::SineWave;
 
-    let stream = OutputStreamBuilder::open_default_stream().map_err(|e| {
-        Error::new(
-            Status::GenericFailure,
-            format!("Failed to create stream: {}", e),
-        )
-    })?;
-
-    let mixer = stream.mixer();
-    let sink = Sink::connect_new(mixer);
-
     let source = SineWave::new(frequency as f32)
         .take_duration(Duration::from_millis(duration_ms as u64))
         .amplify(0.3);
 
-    sink.append(source);
-    thread::sleep(Duration::from_millis(duration_ms as u64));
-
-    Ok(())
+    crate::engine::play_voice(source)
 }
 
 #[napi]
 pub fn get_audio_metadata(file_path: String) -> Result<AudioMetadata> {
     let path = Path::new(&file_path);
     if !path.exists() {
-        return Err(Error::new(
-            Status::InvalidArg,
+        return Err(error(
+            DecoderError::FileNotFound,
             format!("File not found: {}", file_path),
         ));
     }
@@ -0,0 +1,276 @@
+//! Waveform overviews - per-bin min/max/RMS peaks for drawing waveforms
+
+use crate::decoder::CANCEL_CHECK_INTERVAL;
+use crate::error::{error, ErrorCode, Result};
+use crate::types::CancelToken;
+use napi::bindgen_prelude::{Buffer, Float32Array};
+use napi_derive::napi;
+
+/// Format version written by `toJson` and `toDat` (audiowaveform v2, multi-channel)
+const AUDIOWAVEFORM_VERSION: i32 = 2;
+
+/// How finely `computePeaks` divides the audio; set exactly one field
+#[napi(object)]
+#[derive(Debug, Clone, Default)]
+pub struct PeaksOptions {
+    /// Bins per second of audio; the bin width is rounded to whole frames
+    pub bins_per_second: Option<f64>,
+    /// Number of bins over the whole input. Uses the declared duration when there
+    /// is one, otherwise the input is decoded once more to count its frames.
+    pub total_bins: Option<u32>,
+}
+
+/// Min/max/RMS per bin and channel, with values in [-1, 1]
+#[napi]
+#[derive(Debug, Clone)]
+pub struct WaveformPeaks {
+    sample_rate: u32,
+    channels: u16,
+    samples_per_bin: u32,
+    // Interleaved by channel: `bin * channels + channel`
+    min: Vec<f32>,
+    max: Vec<f32>,
+    rms: Vec<f32>,
+}
+
+#[napi]
+impl WaveformPeaks {
+    #[napi]
+    pub fn get_sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    #[napi]
+    pub fn get_channels(&self) -> u16 {
+        self.channels
+    }
+
+    /// Frames reduced into each bin (the last bin may hold fewer)
+    #[napi]
+    pub fn get_samples_per_bin(&self) -> u32 {
+        self.samples_per_bin
+    }
+
+    /// Number of bins per channel
+    #[napi]
+    pub fn get_length(&self) -> u32 {
+        (self.min.len() / self.channels.max(1) as usize) as u32
+    }
+
+    /// Smallest sample of each bin on `channel`
+    #[napi]
+    pub fn get_min(&self, channel: u32) -> Result<Float32Array> {
+        self.channel_values(&self.min, channel)
+            .map(Float32Array::new)
+    }
+
+    /// Largest sample of each bin on `channel`
+    #[napi]
+    pub fn get_max(&self, channel: u32) -> Result<Float32Array> {
+        self.channel_values(&self.max, channel)
+            .map(Float32Array::new)
+    }
+
+    /// Root mean square of each bin on `channel`
+    #[napi]
+    pub fn get_rms(&self, channel: u32) -> Result<Float32Array> {
+        self.channel_values(&self.rms, channel)
+            .map(Float32Array::new)
+    }
+
+    /// Serialise as audiowaveform JSON, readable by waveform-data.js and peaks.js.
+    /// `bits` is 8 or 16 (default).
+    #[napi]
+    pub fn to_json(&self, bits: Option<u32>) -> Result<String> {
+        let bits = export_bits(bits)?;
+        let data = self
+            .quantized(bits)
+            .map(|v| v.to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+        Ok(format!(
+            "{{\"version\":{},\"channels\":{},\"sample_rate\":{},\"samples_per_pixel\":{},\"bits\":{},\"length\":{},\"data\":[{}]}}",
+            AUDIOWAVEFORM_VERSION,
+            self.channels,
+            self.sample_rate,
+            self.samples_per_bin,
+            bits,
+            self.get_length(),
+            data
+        ))
+    }
+
+    /// Serialise as an audiowaveform binary `.dat` file. `bits` is 8 or 16 (default).
+    #[napi]
+    pub fn to_dat(&self, bits: Option<u32>) -> Result<Buffer> {
+        let bits = export_bits(bits)?;
+        let width = bits as usize / 8;
+        let mut dat = Vec::with_capacity(24 + self.min.len() * 2 * width);
+        dat.extend_from_slice(&AUDIOWAVEFORM_VERSION.to_le_bytes());
+        // Flags: bit 0 set means 8-bit data
+        dat.extend_from_slice(&u32::from(bits == 8).to_le_bytes());
+        dat.extend_from_slice(&self.sample_rate.to_le_bytes());
+        dat.extend_from_slice(&self.samples_per_bin.to_le_bytes());
+        dat.extend_from_slice(&self.get_length().to_le_bytes());
+        dat.extend_from_slice(&(self.channels as u32).to_le_bytes());
+        for v in self.quantized(bits) {
+            if bits == 8 {
+                dat.push(v as i8 as u8);
+            } else {
+                dat.extend_from_slice(&(v as i16).to_le_bytes());
+            }
+        }
+        Ok(dat.into())
+    }
+}
+
+impl WaveformPeaks {
+    fn channel_values(&self, values: &[f32], channel: u32) -> Result<Vec<f32>> {
+        let channels = self.channels as usize;
+        if channel as usize >= channels {
+            return Err(error(
+                ErrorCode::InvalidArgument,
+                format!(
+                    "Channel {} out of range (0-{})",
+                    channel,
+                    channels.saturating_sub(1)
+                ),
+            ));
+        }
+        Ok(values
+            .iter()
+            .skip(channel as usize)
+            .step_by(channels)
+            .copied()
+            .collect())
+    }
+
+    /// Min/max pairs in audiowaveform order: per bin, per channel
+    fn quantized(&self, bits: u32) -> impl Iterator<Item = i32> + '_ {
+        let scale = if bits == 8 { 127.0 } else { 32767.0 };
+        let quantize = move |v: f32| (v.clamp(-1.0, 1.0) * scale).round() as i32;
+        self.min
+            .iter()
+            .zip(&self.max)
+            .flat_map(move |(&lo, &hi)| [quantize(lo), quantize(hi)])
+    }
+}
+
+fn export_bits(bits: Option<u32>) -> Result<u32> {
+    match bits.unwrap_or(16) {
+        bits @ (8 | 16) => Ok(bits),
+        other => Err(error(
+            ErrorCode::InvalidArgument,
+            format!("Bits must be 8 or 16, got {}", other),
+        )),
+    }
+}
+
+/// Frames per bin for `options`; `frames` is only called for `totalBins`
+pub(crate) fn bin_width(
+    options: &PeaksOptions,
+    sample_rate: u32,
+    frames: impl FnOnce() -> Result<u64>,
+) -> Result<u32> {
+    match (options.bins_per_second, options.total_bins) {
+        (Some(bins_per_second), None) => {
+            if !bins_per_second.is_finite() || bins_per_second <= 0.0 {
+                return Err(error(
+                    ErrorCode::InvalidArgument,
+                    "binsPerSecond must be a positive number",
+                ));
+            }
+            let width = (sample_rate as f64 / bins_per_second).round();
+            Ok(width.clamp(1.0, u32::MAX as f64) as u32)
+        }
+        (None, Some(0)) => Err(error(
+            ErrorCode::InvalidArgument,
+            "totalBins must be at least 1",
+        )),
+        (None, Some(total_bins)) => {
+            let width = frames()?.div_ceil(total_bins as u64).max(1);
+            Ok(width.min(u32::MAX as u64) as u32)
+        }
+        _ => Err(error(
+            ErrorCode::InvalidArgument,
+            "Specify exactly one of binsPerSecond and totalBins",
+        )),
+    }
+}
+
+/// Reduce interleaved `samples` to peaks in one pass, holding only the current bin's
+/// running totals. A trailing partial frame is ignored.
+pub(crate) fn compute_peaks<I: Iterator<Item = f32>>(
+    samples: I,
+    sample_rate: u32,
+    channels: u16,
+    samples_per_bin: u32,
+    cancel: &CancelToken,
+) -> Result<WaveformPeaks> {
+    let n = channels.max(1) as usize;
+    let bin_len = samples_per_bin.max(1) as usize * n;
+    let mut peaks = WaveformPeaks {
+        sample_rate,
+        channels: n as u16,
+        samples_per_bin: samples_per_bin.max(1),
+        min: Vec::new(),
+        max: Vec::new(),
+        rms: Vec::new(),
+    };
+    let mut bin = BinTotals::new(n);
+    let mut filled = 0;
+
+    for (i, sample) in samples.enumerate() {
+        if i % CANCEL_CHECK_INTERVAL == 0 {
+            cancel.check()?;
+        }
+        bin.add(filled % n, sample);
+        filled += 1;
+        if filled == bin_len {
+            bin.flush(&mut peaks, filled / n);
+            filled = 0;
+        }
+    }
+    if filled >= n {
+        bin.flush(&mut peaks, filled / n);
+    }
+    cancel.check()?;
+    Ok(peaks)
+}
+
+/// Running per-channel totals for the bin being filled
+struct BinTotals {
+    min: Vec<f32>,
+    max: Vec<f32>,
+    sum_sq: Vec<f64>,
+}
+
+impl BinTotals {
+    fn new(channels: usize) -> Self {
+        Self {
+            min: vec![f32::MAX; channels],
+            max: vec![f32::MIN; channels],
+            sum_sq: vec![0.0; channels],
+        }
+    }
+
+    fn add(&mut self, channel: usize, sample: f32) {
+        self.min[channel] = self.min[channel].min(sample);
+        self.max[channel] = self.max[channel].max(sample);
+        self.sum_sq[channel] += sample as f64 * sample as f64;
+    }
+
+    /// Append the bin to `peaks` and start a new one
+    fn flush(&mut self, peaks: &mut WaveformPeaks, frames: usize) {
+        for ch in 0..self.min.len() {
+            peaks.min.push(self.min[ch]);
+            peaks.max.push(self.max[ch]);
+            peaks
+                .rms
+                .push((self.sum_sq[ch] / frames.max(1) as f64).sqrt() as f32);
+            self.min[ch] = f32::MAX;
+            self.max[ch] = f32::MIN;
+            self.sum_sq[ch] = 0.0;
+        }
+    }
+}